@@ -0,0 +1,47 @@
+#![no_std]
+
+numbat_wasm::imports!();
+
+/// Output amount per RATE_PRECISION units of input.
+pub const RATE_PRECISION: u64 = 1_000_000_000;
+
+/// Stand-in for a DEX pair in the scenarios, with the same swap endpoint as the real pairs.
+/// Swaps any token for any other at the rate set by the owner, paying from its own balance.
+#[numbat_wasm::contract]
+pub trait DexPairMock {
+    #[init]
+    fn init(&self, rate: BigUint) {
+        self.rate().set(&rate);
+    }
+
+    #[only_owner]
+    #[endpoint(setRate)]
+    fn set_rate(&self, rate: BigUint) {
+        self.rate().set(&rate);
+    }
+
+    /// The output tokens are sent back to the caller, like the real pairs do.
+    #[payable("*")]
+    #[endpoint(swapTokensFixedInput)]
+    fn swap_tokens_fixed_input(
+        &self,
+        #[payment_token] token_in: TokenIdentifier,
+        #[payment_amount] amount_in: BigUint,
+        token_out: TokenIdentifier,
+        amount_out_min: BigUint,
+    ) -> SCResult<()> {
+        require!(token_in != token_out, "Same token");
+
+        let amount_out = amount_in * self.rate().get() / BigUint::from(RATE_PRECISION);
+        require!(amount_out >= amount_out_min, "Slippage exceeded");
+
+        let caller = self.blockchain().get_caller();
+        self.send().direct(&caller, &token_out, 0, &amount_out, &[]);
+
+        Ok(())
+    }
+
+    #[view(getRate)]
+    #[storage_mapper("rate")]
+    fn rate(&self) -> SingleValueMapper<BigUint>;
+}
@@ -0,0 +1,79 @@
+numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+/// the user's deposited amount from `block_nonce` until the next checkpoint
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct BalanceCheckpoint<M: ManagedTypeApi> {
+    pub block_nonce: u64,
+    pub amount: BigUint<M>,
+}
+
+#[numbat_wasm::module]
+pub trait BalanceCheckpointModule {
+    /// The user's deposited amount at the end of the block, for retroactive airdrops.
+    /// Only covers the regular deposits, not the positions or the liquidity deposits.
+    /// In timestamp mode, `block_nonce` is a timestamp.
+    #[view(balanceAt)]
+    fn balance_at(&self, address: ManagedAddress, block_nonce: u64) -> BigUint {
+        let checkpoints = self.balance_checkpoints(&address);
+        let len = checkpoints.len();
+        if len == 0 || checkpoints.get(1).block_nonce > block_nonce {
+            return BigUint::zero();
+        }
+
+        // VecMapper indexes start at 1. block(low) <= block_nonce < block(high)
+        let mut low = 1;
+        let mut high = len + 1;
+        while high - low > 1 {
+            let mid = (low + high) / 2;
+            if checkpoints.get(mid).block_nonce <= block_nonce {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        checkpoints.get(low).amount
+    }
+
+    /// Only records a checkpoint if the amount changed.
+    /// Several changes in the same block keep only the last amount.
+    fn record_balance_checkpoint(
+        &self,
+        address: &ManagedAddress,
+        amount: &BigUint,
+        block_nonce: u64,
+    ) {
+        let mut checkpoints = self.balance_checkpoints(address);
+        let len = checkpoints.len();
+        let new_checkpoint = BalanceCheckpoint {
+            block_nonce,
+            amount: amount.clone(),
+        };
+        if len == 0 {
+            if amount > &0 {
+                checkpoints.push(&new_checkpoint);
+            }
+            return;
+        }
+
+        let last_checkpoint = checkpoints.get(len);
+        if &last_checkpoint.amount == amount {
+            return;
+        }
+
+        if last_checkpoint.block_nonce == block_nonce {
+            checkpoints.set(len, &new_checkpoint);
+        } else {
+            checkpoints.push(&new_checkpoint);
+        }
+    }
+
+    // storage
+
+    #[storage_mapper("balanceCheckpoints")]
+    fn balance_checkpoints(
+        &self,
+        address: &ManagedAddress,
+    ) -> VecMapper<BalanceCheckpoint<Self::Api>>;
+}
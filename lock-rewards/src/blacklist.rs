@@ -0,0 +1,49 @@
+numbat_wasm::imports!();
+
+#[numbat_wasm::module]
+pub trait BlacklistModule: crate::events::EventsModule {
+    /// Blacklisted addresses can't deposit, claim or compound anymore.
+    /// They can still withdraw their deposits. Rewards keep being recorded,
+    /// so they can be claimed if the address is removed from the blacklist.
+    #[only_owner]
+    #[endpoint(addToBlacklist)]
+    fn add_to_blacklist(&self, #[var_args] addresses: VarArgs<ManagedAddress>) -> SCResult<()> {
+        for address in addresses.into_vec() {
+            if self.blacklist().insert(address.clone()) {
+                self.blacklist_add_event(&address);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint(removeFromBlacklist)]
+    fn remove_from_blacklist(
+        &self,
+        #[var_args] addresses: VarArgs<ManagedAddress>,
+    ) -> SCResult<()> {
+        for address in addresses.into_vec() {
+            if self.blacklist().remove(&address) {
+                self.blacklist_remove_event(&address);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[view(isBlacklisted)]
+    fn is_blacklisted(&self, address: &ManagedAddress) -> bool {
+        self.blacklist().contains(address)
+    }
+
+    fn require_not_blacklisted(&self, address: &ManagedAddress) -> SCResult<()> {
+        require!(!self.is_blacklisted(address), "Address is blacklisted");
+        Ok(())
+    }
+
+    // storage
+
+    #[storage_mapper("blacklist")]
+    fn blacklist(&self) -> SetMapper<ManagedAddress>;
+}
@@ -0,0 +1,23 @@
+numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct BoostTier<M: ManagedTypeApi> {
+    pub min_amount: BigUint<M>,
+    pub multiplier: BigUint<M>,
+}
+
+/// Multiplier of the highest tier the amount qualifies for, the precision (1x) if none.
+/// Tiers must be sorted by min_amount.
+pub fn get_tier_multiplier<M: ManagedTypeApi>(
+    tiers: &[BoostTier<M>],
+    amount: &BigUint<M>,
+    precision: &BigUint<M>,
+) -> BigUint<M> {
+    tiers
+        .iter()
+        .rev()
+        .find(|tier| &tier.min_amount <= amount)
+        .map(|tier| tier.multiplier.clone())
+        .unwrap_or_else(|| precision.clone())
+}
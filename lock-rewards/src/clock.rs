@@ -0,0 +1,19 @@
+numbat_wasm::imports!();
+
+#[numbat_wasm::module]
+pub trait ClockModule {
+    /// the current block nonce, or the current timestamp in timestamp mode
+    fn get_current_block_nonce(&self) -> u64 {
+        if self.timestamp_accrual().get() {
+            self.blockchain().get_block_timestamp()
+        } else {
+            self.blockchain().get_block_nonce()
+        }
+    }
+
+    // storage
+
+    #[view(isTimestampAccrual)]
+    #[storage_mapper("timestampAccrual")]
+    fn timestamp_accrual(&self) -> SingleValueMapper<bool>;
+}
@@ -0,0 +1,365 @@
+numbat_wasm::imports!();
+
+#[numbat_wasm::module]
+pub trait EventsModule {
+    #[event("deposit")]
+    fn deposit_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] block_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("depositFee")]
+    fn deposit_fee_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] block_nonce: u64,
+        fee_amount: &BigUint,
+    );
+
+    #[event("withdraw")]
+    fn withdraw_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] block_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("requestWithdraw")]
+    fn withdraw_requested_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] block_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("emergencyWithdraw")]
+    fn emergency_withdraw_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] block_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("claimRewards")]
+    fn claim_rewards_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] block_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("claimTokenRewards")]
+    fn claim_token_rewards_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] token_id: &TokenIdentifier,
+        amount: &BigUint,
+    );
+
+    #[event("claimGovernanceRewards")]
+    fn claim_governance_rewards_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] block_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("claimReferralRewards")]
+    fn claim_referral_rewards_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] block_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("vestingStarted")]
+    fn vesting_started_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] block_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("claimSwappedRewards")]
+    fn claim_swapped_rewards_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] token_id: &TokenIdentifier,
+        amount: &BigUint,
+    );
+
+    #[event("claimVested")]
+    fn claim_vested_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] block_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("setAutoRestake")]
+    fn auto_restake_changed_event(&self, #[indexed] caller: &ManagedAddress, enabled: bool);
+
+    #[event("positionDeposit")]
+    fn position_deposit_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] position_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("positionWithdraw")]
+    fn position_withdraw_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] position_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("claimPositionRewards")]
+    fn claim_position_rewards_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] position_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("liquidityDeposit")]
+    fn liquidity_deposit_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] sft_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("liquidityWithdraw")]
+    fn liquidity_withdraw_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] sft_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("claimLiquidityRewards")]
+    fn claim_liquidity_rewards_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] sft_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("extendLock")]
+    fn extend_lock_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] unlock_block_nonce: u64,
+        multiplier: &BigUint,
+    );
+
+    #[event("compound")]
+    fn compound_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] block_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("bonusRewards")]
+    fn bonus_rewards_event(
+        &self,
+        #[indexed] address: &ManagedAddress,
+        #[indexed] block_nonce: u64,
+        amount: &BigUint,
+    );
+
+    #[event("migrateDeposit")]
+    fn migrate_deposit_event(
+        &self,
+        #[indexed] user: &ManagedAddress,
+        #[indexed] new_contract_address: &ManagedAddress,
+        amount: &BigUint,
+    );
+
+    #[event("setPercentageRewardPerBlock")]
+    fn percentage_reward_per_block_changed_event(
+        &self,
+        #[indexed] start_block_nonce: u64,
+        new_percentage: &BigUint,
+    );
+
+    #[event("setLockOption")]
+    fn lock_option_set_event(&self, #[indexed] lock_blocks: u64, multiplier: &BigUint);
+
+    #[event("removeLockOption")]
+    fn lock_option_removed_event(&self, #[indexed] lock_blocks: u64);
+
+    #[event("setBoostTier")]
+    fn boost_tier_set_event(&self, #[indexed] min_amount: &BigUint, multiplier: &BigUint);
+
+    #[event("setGovernancePercentageRewardPerBlock")]
+    fn governance_percentage_reward_per_block_changed_event(
+        &self,
+        #[indexed] start_block_nonce: u64,
+        new_percentage: &BigUint,
+    );
+
+    #[event("setLiquidityPercentageRewardPerBlock")]
+    fn liquidity_percentage_reward_per_block_changed_event(
+        &self,
+        #[indexed] start_block_nonce: u64,
+        new_percentage: &BigUint,
+    );
+
+    #[event("setUtilizationCurve")]
+    fn utilization_curve_set_event(
+        &self,
+        #[indexed] block_nonce: u64,
+        target_total_deposits: &BigUint,
+        slope: &BigUint,
+        min_multiplier: &BigUint,
+    );
+
+    #[event("removeUtilizationCurve")]
+    fn utilization_curve_removed_event(&self, #[indexed] block_nonce: u64);
+
+    #[event("setExtraRewardToken")]
+    fn extra_reward_token_set_event(
+        &self,
+        #[indexed] token_id: &TokenIdentifier,
+        percentage_reward_per_block: &BigUint,
+    );
+
+    #[event("removeExtraRewardToken")]
+    fn extra_reward_token_removed_event(&self, #[indexed] token_id: &TokenIdentifier);
+
+    #[event("setReferralRewardPercentage")]
+    fn referral_reward_percentage_changed_event(
+        &self,
+        #[indexed] block_nonce: u64,
+        new_percentage: &BigUint,
+    );
+
+    #[event("setEarlyWithdrawPenaltyPercentage")]
+    fn early_withdraw_penalty_percentage_changed_event(
+        &self,
+        #[indexed] block_nonce: u64,
+        new_percentage: &BigUint,
+    );
+
+    #[event("setDepositFeePercentage")]
+    fn deposit_fee_percentage_changed_event(
+        &self,
+        #[indexed] block_nonce: u64,
+        percentage: &BigUint,
+    );
+
+    #[event("setRewardReserveMode")]
+    fn reward_reserve_mode_changed_event(&self, #[indexed] block_nonce: u64, enabled: bool);
+
+    #[event("fundRewards")]
+    fn fund_rewards_event(&self, #[indexed] block_nonce: u64, amount: &BigUint);
+
+    #[event("withdrawRewardReserve")]
+    fn withdraw_reward_reserve_event(&self, #[indexed] block_nonce: u64, amount: &BigUint);
+
+    #[event("setRewardsEndBlockNonce")]
+    fn rewards_end_block_nonce_changed_event(
+        &self,
+        #[indexed] block_nonce: u64,
+        end_block_nonce: u64,
+    );
+
+    #[event("setMaxTotalRewards")]
+    fn max_total_rewards_changed_event(
+        &self,
+        #[indexed] block_nonce: u64,
+        max_total_rewards: &BigUint,
+    );
+
+    #[event("setMaxRewardsPerClaim")]
+    fn max_rewards_per_claim_changed_event(
+        &self,
+        #[indexed] block_nonce: u64,
+        max_rewards_per_claim: &BigUint,
+    );
+
+    #[event("setEpochRewardsLimit")]
+    fn epoch_rewards_limit_changed_event(
+        &self,
+        #[indexed] block_nonce: u64,
+        #[indexed] epoch_duration_blocks: u64,
+        max_rewards_per_epoch: &BigUint,
+    );
+
+    #[event("setVestingDurationBlocks")]
+    fn vesting_duration_changed_event(&self, #[indexed] block_nonce: u64, duration_blocks: u64);
+
+    #[event("setWithdrawCooldownBlocks")]
+    fn withdraw_cooldown_changed_event(&self, #[indexed] block_nonce: u64, cooldown_blocks: u64);
+
+    #[event("setMinDepositAmount")]
+    fn min_deposit_amount_changed_event(
+        &self,
+        #[indexed] block_nonce: u64,
+        min_deposit_amount: &BigUint,
+    );
+
+    #[event("setMaxDepositPerUser")]
+    fn max_deposit_per_user_changed_event(
+        &self,
+        #[indexed] block_nonce: u64,
+        max_deposit_per_user: &BigUint,
+    );
+
+    #[event("setMaxTotalDeposits")]
+    fn max_total_deposits_changed_event(
+        &self,
+        #[indexed] block_nonce: u64,
+        max_total_deposits: &BigUint,
+    );
+
+    /// the zero address means the treasury was removed
+    #[event("setTreasuryAddress")]
+    fn treasury_address_changed_event(&self, #[indexed] block_nonce: u64, address: &ManagedAddress);
+
+    #[event("setWhitelistEnabled")]
+    fn whitelist_enabled_event(&self, #[indexed] block_nonce: u64, enabled: bool);
+
+    #[event("addToWhitelist")]
+    fn whitelist_add_event(&self, #[indexed] address: &ManagedAddress);
+
+    #[event("removeFromWhitelist")]
+    fn whitelist_remove_event(&self, #[indexed] address: &ManagedAddress);
+
+    #[event("addToBlacklist")]
+    fn blacklist_add_event(&self, #[indexed] address: &ManagedAddress);
+
+    #[event("removeFromBlacklist")]
+    fn blacklist_remove_event(&self, #[indexed] address: &ManagedAddress);
+
+    #[event("setSwapPair")]
+    fn swap_pair_changed_event(
+        &self,
+        #[indexed] pair_address: &ManagedAddress,
+        #[indexed] output_token_id: &TokenIdentifier,
+    );
+
+    #[event("recoverTokens")]
+    fn recover_tokens_event(
+        &self,
+        #[indexed] token_id: &TokenIdentifier,
+        #[indexed] nonce: u64,
+        amount: &BigUint,
+    );
+
+    /// the zero address means the reward manager was removed
+    #[event("setRewardManager")]
+    fn reward_manager_changed_event(&self, #[indexed] address: &ManagedAddress);
+
+    #[event("pause")]
+    fn pause_event(&self, #[indexed] block_nonce: u64);
+
+    #[event("unpause")]
+    fn unpause_event(&self, #[indexed] block_nonce: u64);
+}
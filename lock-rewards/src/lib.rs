@@ -2,192 +2,3394 @@
 
 numbat_wasm::imports!();
 
+pub mod balance_checkpoint;
+pub mod blacklist;
+pub mod boost_tier;
+pub mod clock;
+pub mod events;
+pub mod pause;
+pub mod position_token;
+pub mod reward_history;
+pub mod reward_manager;
+pub mod reward_schedule;
+pub mod storage_version;
+pub mod successor_proxy;
+pub mod swap_proxy;
 pub mod user_deposit;
+pub mod utilization_curve;
+pub mod vesting;
+pub mod whitelist;
+use boost_tier::*;
+use reward_history::RewardHistoryAction;
+use reward_schedule::*;
+use storage_version::CURRENT_STORAGE_VERSION;
 use user_deposit::*;
+use utilization_curve::*;
+use vesting::*;
 
-#[numbat_wasm_derive::contract]
-pub trait LockRewards {
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+#[numbat_wasm::contract]
+pub trait LockRewards:
+    balance_checkpoint::BalanceCheckpointModule
+    + blacklist::BlacklistModule
+    + clock::ClockModule
+    + events::EventsModule
+    + pause::PauseModule
+    + position_token::PositionTokenModule
+    + reward_history::RewardHistoryModule
+    + reward_manager::RewardManagerModule
+    + storage_version::StorageVersionModule
+    + whitelist::WhitelistModule
+{
+    /// Also called on upgrade. When upgrading from the first version, the contract is paused
+    /// until the deposits are converted to the new layout with migrateStorage.
+    /// The optional precision is the denominator for all the percentages and multipliers,
+    /// BASE_PRECISION by default. It can't be changed on upgrade.
     #[init]
     fn init(
         &self,
         stablecoin_token_id: TokenIdentifier,
-        percentage_reward_per_block: Self::BigUint,
+        percentage_reward_per_block: BigUint,
+        #[var_args] opt_precision: OptionalArg<u64>,
     ) -> SCResult<()> {
         require!(
             stablecoin_token_id.is_valid_dcdt_identifier(),
             "invalid stablecoin token id"
         );
 
-        self.try_set_percentage_rewards_per_block(&percentage_reward_per_block)
+        let precision = opt_precision.into_option().unwrap_or(BASE_PRECISION);
+        require!(
+            (MIN_PRECISION..=MAX_PRECISION).contains(&precision),
+            "Invalid precision"
+        );
+        let is_upgrade = !self.reward_checkpoints().is_empty() || !self.user_deposits().is_empty();
+        require!(
+            !is_upgrade || self.get_precision() == precision,
+            "Precision can't be changed"
+        );
+        self.precision().set(&precision);
+
+        self.require_valid_percentage(&percentage_reward_per_block)?;
+
+        let current_block_nonce = self.get_current_block_nonce();
+        let is_first_version_upgrade =
+            self.storage_version().get() == 0 && !self.user_deposits().is_empty();
+        if is_first_version_upgrade {
+            // the old percentage applies until now
+            self.push_reward_checkpoint(
+                self.reward_checkpoints(),
+                &RewardScheduleEntry {
+                    start_block_nonce: 0,
+                    percentage_reward_per_block: self.legacy_percentage_reward_per_block().get(),
+                },
+            );
+            self.legacy_deposits_left().set(&self.user_deposits().len());
+            self.paused().set(&true);
+        } else {
+            self.storage_version().set(&CURRENT_STORAGE_VERSION);
+        }
+
+        if !self.utilization_curve().is_empty() {
+            self.base_percentage_reward_per_block()
+                .set(&percentage_reward_per_block);
+        }
+        self.push_reward_checkpoint(
+            self.reward_checkpoints(),
+            &RewardScheduleEntry {
+                start_block_nonce: current_block_nonce,
+                percentage_reward_per_block,
+            },
+        );
+        self.update_utilization_rate();
+
+        Ok(())
     }
 
-    // endpoints - owner-only
+    // endpoints - owner-only, or reward manager for the emission settings
 
+    /// The percentage applies from the current block onwards, replacing any scheduled entries.
+    /// Rewards up to this point are still calculated with the old percentages.
     #[endpoint(setPercentageRewardPerBlock)]
     fn set_percentage_reward_per_block(
         &self,
-        percentage_reward_per_block: Self::BigUint,
+        percentage_reward_per_block: BigUint,
+    ) -> SCResult<()> {
+        self.require_owner_or_reward_manager()?;
+        self.require_valid_percentage(&percentage_reward_per_block)?;
+
+        let current_block_nonce = self.get_current_block_nonce();
+        self.apply_started_reward_schedule_entries(current_block_nonce);
+        self.pending_reward_schedule().clear();
+        self.percentage_reward_per_block_changed_event(
+            current_block_nonce,
+            &percentage_reward_per_block,
+        );
+
+        if !self.utilization_curve().is_empty() {
+            self.base_percentage_reward_per_block()
+                .set(&percentage_reward_per_block);
+            self.update_utilization_rate();
+            return Ok(());
+        }
+
+        self.push_reward_checkpoint(
+            self.reward_checkpoints(),
+            &RewardScheduleEntry {
+                start_block_nonce: current_block_nonce,
+                percentage_reward_per_block,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Pre-programs the reward percentages as (start_block_nonce, percentage) pairs.
+    /// Replaces all the entries starting after the current block.
+    /// Each percentage is active until the next entry starts.
+    #[endpoint(setRewardSchedule)]
+    fn set_reward_schedule(
+        &self,
+        #[var_args] entries: MultiArgVec<MultiArg2<u64, BigUint>>,
     ) -> SCResult<()> {
-        only_owner!(self, "only owner may call this function");
+        self.require_owner_or_reward_manager()?;
+        require!(
+            self.utilization_curve().is_empty(),
+            "Not available with the utilization curve"
+        );
 
-        let old_percentage = self.percentage_reward_per_block().get();
-        self.try_set_percentage_rewards_per_block(&percentage_reward_per_block)?;
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut last_start_block_nonce = current_block_nonce;
+        let mut new_entries = Vec::new();
+        for entry in entries.into_vec() {
+            let (start_block_nonce, percentage_reward_per_block) = entry.into_tuple();
+            require!(
+                start_block_nonce > last_start_block_nonce,
+                "Entries must start in the future, in increasing order"
+            );
+            self.require_valid_percentage(&percentage_reward_per_block)?;
 
-        let current_block_nonce = self.blockchain().get_block_nonce();
-        for address in self.user_deposits().keys() {
-            self.user_deposits()
-                .entry(address)
-                .and_modify(|user_deposit| {
-                    user_deposit.accummulate_rewards(current_block_nonce, &old_percentage);
-                });
+            last_start_block_nonce = start_block_nonce;
+            new_entries.push(RewardScheduleEntry {
+                start_block_nonce,
+                percentage_reward_per_block,
+            });
+        }
+
+        self.apply_started_reward_schedule_entries(current_block_nonce);
+        for entry in &new_entries {
+            self.percentage_reward_per_block_changed_event(
+                entry.start_block_nonce,
+                &entry.percentage_reward_per_block,
+            );
         }
+        self.pending_reward_schedule().set(&new_entries);
 
         Ok(())
     }
 
-    // endpoints
+    /// The stablecoin percentage is scaled down once the total deposits go over the target,
+    /// by `slope` for each 1x over the target, down to `min_multiplier`. Both use the precision.
+    /// The percentage set through setPercentageRewardPerBlock is used as the base.
+    /// Scheduled entries must have started, or be removed, first.
+    #[only_owner]
+    #[endpoint(setUtilizationCurve)]
+    fn set_utilization_curve(
+        &self,
+        target_total_deposits: BigUint,
+        slope: BigUint,
+        min_multiplier: BigUint,
+    ) -> SCResult<()> {
+        require!(target_total_deposits > 0, "Invalid target");
+        require!(
+            min_multiplier <= self.get_precision(),
+            "Invalid min multiplier"
+        );
+
+        let current_block_nonce = self.get_current_block_nonce();
+        self.apply_started_reward_schedule_entries(current_block_nonce);
+        require!(
+            self.pending_reward_schedule().get().is_empty(),
+            "Reward schedule has entries that didn't start yet"
+        );
+
+        if self.utilization_curve().is_empty() {
+            let checkpoints = self.reward_checkpoints();
+            let base_percentage = if checkpoints.is_empty() {
+                BigUint::zero()
+            } else {
+                checkpoints
+                    .get(checkpoints.len())
+                    .percentage_reward_per_block
+            };
+            self.base_percentage_reward_per_block()
+                .set(&base_percentage);
+        }
+
+        self.utilization_curve_set_event(
+            current_block_nonce,
+            &target_total_deposits,
+            &slope,
+            &min_multiplier,
+        );
+        self.utilization_curve().set(&UtilizationCurve {
+            target_total_deposits,
+            slope,
+            min_multiplier,
+        });
+        self.update_utilization_rate();
+
+        Ok(())
+    }
+
+    /// the base percentage applies again from the current block onwards
+    #[only_owner]
+    #[endpoint(removeUtilizationCurve)]
+    fn remove_utilization_curve(&self) -> SCResult<()> {
+        require!(
+            !self.utilization_curve().is_empty(),
+            "Utilization curve not set"
+        );
+
+        let current_block_nonce = self.get_current_block_nonce();
+        self.push_reward_checkpoint(
+            self.reward_checkpoints(),
+            &RewardScheduleEntry {
+                start_block_nonce: current_block_nonce,
+                percentage_reward_per_block: self.base_percentage_reward_per_block().get(),
+            },
+        );
+        self.utilization_curve().clear();
+        self.base_percentage_reward_per_block().clear();
+        self.utilization_curve_removed_event(current_block_nonce);
+
+        Ok(())
+    }
+
+    /// multiplier uses the precision, so 1.5x is 1_500_000_000 with the default one
+    #[only_owner]
+    #[endpoint(setLockOption)]
+    fn set_lock_option(&self, lock_blocks: u64, multiplier: BigUint) -> SCResult<()> {
+        require!(lock_blocks > 0, "Invalid lock duration");
+        require!(multiplier >= self.get_precision(), "Invalid multiplier");
+
+        self.lock_option_set_event(lock_blocks, &multiplier);
+        self.lock_multipliers().insert(lock_blocks, multiplier);
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint(removeLockOption)]
+    fn remove_lock_option(&self, lock_blocks: u64) -> SCResult<()> {
+        let _ = self.lock_multipliers().remove(&lock_blocks);
+        self.lock_option_removed_event(lock_blocks);
+
+        Ok(())
+    }
+
+    /// percentage uses the precision. 0 disables early withdrawals.
+    #[only_owner]
+    #[endpoint(setEarlyWithdrawPenaltyPercentage)]
+    fn set_early_withdraw_penalty_percentage(&self, percentage: BigUint) -> SCResult<()> {
+        require!(percentage <= self.get_precision(), "Invalid percentage");
 
+        self.early_withdraw_penalty_percentage().set(&percentage);
+        self.early_withdraw_penalty_percentage_changed_event(
+            self.get_current_block_nonce(),
+            &percentage,
+        );
+
+        Ok(())
+    }
+
+    /// Claimed rewards are released linearly over this many blocks. 0 pays rewards instantly.
+    /// Only affects future claims.
+    #[only_owner]
+    #[endpoint(setVestingDurationBlocks)]
+    fn set_vesting_duration_blocks(&self, duration_blocks: u64) -> SCResult<()> {
+        self.vesting_duration_blocks().set(&duration_blocks);
+        self.vesting_duration_changed_event(self.get_current_block_nonce(), duration_blocks);
+
+        Ok(())
+    }
+
+    /// In reserve mode, stablecoin rewards are paid from the reserve funded through fundRewards,
+    /// so the contract doesn't need the Mint role. Claims pay at most what is left in the reserve.
+    #[only_owner]
+    #[endpoint(setRewardReserveMode)]
+    fn set_reward_reserve_mode(&self, enabled: bool) -> SCResult<()> {
+        self.reward_reserve_mode().set(&enabled);
+        self.reward_reserve_mode_changed_event(self.get_current_block_nonce(), enabled);
+
+        Ok(())
+    }
+
+    #[only_owner]
     #[payable("*")]
-    #[endpoint]
-    fn deposit(
+    #[endpoint(fundRewards)]
+    fn fund_rewards(
         &self,
         #[payment_token] token_id: TokenIdentifier,
-        #[payment] amount: Self::BigUint,
+        #[payment_amount] amount: BigUint,
     ) -> SCResult<()> {
         require!(
             token_id == self.stablecoin_token_id().get(),
             "Wrong payment token"
         );
-        require!(amount > 0, "Must deposit more than 0");
+        require!(amount > 0, "Must fund more than 0");
 
-        let caller = self.blockchain().get_caller();
-        let current_block_nonce = self.blockchain().get_block_nonce();
-        let percentage_reward_per_block = self.percentage_reward_per_block().get();
+        self.reward_reserve().update(|reserve| *reserve += &amount);
+        self.fund_rewards_event(self.get_current_block_nonce(), &amount);
 
-        self.user_deposits()
-            .entry(caller)
-            .or_default()
-            .update(|user_deposit| {
-                user_deposit.accummulate_rewards(current_block_nonce, &percentage_reward_per_block);
-                user_deposit.amount += amount;
-            });
+        Ok(())
+    }
+
+    /// sends the unused part of the reserve back to the owner
+    #[only_owner]
+    #[endpoint(withdrawRewardReserve)]
+    fn withdraw_reward_reserve(&self, amount: BigUint) -> SCResult<()> {
+        let reward_reserve = self.reward_reserve().get();
+        require!(amount > 0 && amount <= reward_reserve, "Invalid amount");
+
+        self.reward_reserve().set(&(reward_reserve - &amount));
+        self.send_stablecoins(&self.blockchain().get_caller(), &amount);
+        self.withdraw_reward_reserve_event(self.get_current_block_nonce(), &amount);
 
         Ok(())
     }
 
-    /// optional amount to withdraw. Defaults to max possible.
-    #[endpoint]
-    fn withdraw(&self, #[var_args] opt_amount: OptionalArg<Self::BigUint>) -> SCResult<()> {
-        let caller = self.blockchain().get_caller();
-        let mut user_deposit = self.get_user_deposit_or_default(&caller);
-        let amount = opt_amount
-            .into_option()
-            .unwrap_or_else(|| user_deposit.amount.clone());
+    /// No rewards accrue after this block. Can be moved, as long as it's in the future,
+    /// until the end block is reached. After that, it can't be changed anymore,
+    /// as users that didn't update their deposit would earn rewards for the blocks in between.
+    /// 0 removes the end block.
+    #[endpoint(setRewardsEndBlockNonce)]
+    fn set_rewards_end_block_nonce(&self, end_block_nonce: u64) -> SCResult<()> {
+        self.require_owner_or_reward_manager()?;
 
-        require!(amount > 0, "Must withdraw more than 0");
+        let current_block_nonce = self.get_current_block_nonce();
         require!(
-            amount <= user_deposit.amount,
-            "Cannot withdraw more than deposited amount"
+            !self.have_rewards_ended(current_block_nonce),
+            "Rewards already ended"
+        );
+        require!(
+            end_block_nonce == 0 || end_block_nonce > current_block_nonce,
+            "End block must be in the future"
         );
 
-        self.send_stablecoins(&caller, &amount);
+        self.rewards_end_block_nonce().set(&end_block_nonce);
+        self.rewards_end_block_nonce_changed_event(current_block_nonce, end_block_nonce);
+
+        Ok(())
+    }
 
-        let current_block_nonce = self.blockchain().get_block_nonce();
-        let percentage_reward_per_block = self.percentage_reward_per_block().get();
-        user_deposit.accummulate_rewards(current_block_nonce, &percentage_reward_per_block);
-        user_deposit.amount -= amount;
+    /// 0 means no cap. Like the end block, it can't be changed anymore once it's reached,
+    /// as users that didn't update their deposit would earn rewards for the blocks in between.
+    #[only_owner]
+    #[endpoint(setMaxTotalRewards)]
+    fn set_max_total_rewards(&self, max_total_rewards: BigUint) -> SCResult<()> {
+        require!(
+            self.reward_cap_reached_block_nonce().get() == 0,
+            "Reward cap already reached"
+        );
 
-        self.update_user_deposit_or_remove_if_cleared(caller, user_deposit);
+        self.max_total_rewards().set(&max_total_rewards);
+        self.max_total_rewards_changed_event(self.get_current_block_nonce(), &max_total_rewards);
 
         Ok(())
     }
 
-    #[endpoint(claimRewards)]
-    fn claim_rewards(&self) -> SCResult<()> {
-        let caller = self.blockchain().get_caller();
-        let current_block_nonce = self.blockchain().get_block_nonce();
-        let percentage_reward_per_block = self.percentage_reward_per_block().get();
-        let mut user_deposit = self.get_user_deposit_or_default(&caller);
+    /// Circuit breaker, limits the stablecoin rewards paid by a single claim.
+    /// The rest stays recorded and can be claimed later. 0 means no limit.
+    #[only_owner]
+    #[endpoint(setMaxRewardsPerClaim)]
+    fn set_max_rewards_per_claim(&self, max_rewards_per_claim: BigUint) -> SCResult<()> {
+        self.max_rewards_per_claim().set(&max_rewards_per_claim);
+        self.max_rewards_per_claim_changed_event(
+            self.get_current_block_nonce(),
+            &max_rewards_per_claim,
+        );
 
-        user_deposit.accummulate_rewards(current_block_nonce, &percentage_reward_per_block);
+        Ok(())
+    }
 
-        self.try_mint_stablecoins(&user_deposit.cummulated_rewards)?;
-        self.send_stablecoins(&caller, &user_deposit.cummulated_rewards);
+    /// Circuit breaker, limits the stablecoin rewards paid by all the claims in an epoch
+    /// of `epoch_duration_blocks`. The rest stays recorded and can be claimed in the next epochs.
+    /// A 0 duration removes the limit.
+    #[only_owner]
+    #[endpoint(setEpochRewardsLimit)]
+    fn set_epoch_rewards_limit(
+        &self,
+        epoch_duration_blocks: u64,
+        max_rewards_per_epoch: BigUint,
+    ) -> SCResult<()> {
+        require!(
+            epoch_duration_blocks == 0 || max_rewards_per_epoch > 0,
+            "Invalid max rewards per epoch"
+        );
 
-        user_deposit.cummulated_rewards = Self::BigUint::zero();
-        self.update_user_deposit_or_remove_if_cleared(caller, user_deposit);
+        self.epoch_duration_blocks().set(&epoch_duration_blocks);
+        self.max_rewards_per_epoch().set(&max_rewards_per_epoch);
+        self.current_epoch().clear();
+        self.current_epoch_rewards_paid().clear();
+        self.epoch_rewards_limit_changed_event(
+            self.get_current_block_nonce(),
+            epoch_duration_blocks,
+            &max_rewards_per_epoch,
+        );
 
         Ok(())
     }
 
-    // private
+    /// Sets the (min_amount, multiplier) boost tiers, replacing the previous ones.
+    /// Deposits earn the multiplier of the highest tier their amount reaches, on top of the lock multiplier.
+    /// The tier is picked using the deposited amount at each reward update.
+    #[only_owner]
+    #[endpoint(setBoostTiers)]
+    fn set_boost_tiers(
+        &self,
+        #[var_args] tiers: MultiArgVec<MultiArg2<BigUint, BigUint>>,
+    ) -> SCResult<()> {
+        let mut boost_tiers: Vec<BoostTier<Self::Api>> = Vec::new();
+        for tier in tiers.into_vec() {
+            let (min_amount, multiplier) = tier.into_tuple();
+            if let Some(last_tier) = boost_tiers.last() {
+                require!(
+                    min_amount > last_tier.min_amount,
+                    "Tiers must be in increasing order"
+                );
+            }
+            require!(multiplier >= self.get_precision(), "Invalid multiplier");
 
-    fn require_local_mint_role_set(&self) -> SCResult<()> {
-        let token_id = self.stablecoin_token_id().get();
-        let roles = self.blockchain().get_dcdt_local_roles(&token_id);
+            self.boost_tier_set_event(&min_amount, &multiplier);
+            boost_tiers.push(BoostTier {
+                min_amount,
+                multiplier,
+            });
+        }
+
+        self.boost_tiers().set(&boost_tiers);
+
+        Ok(())
+    }
+
+    /// The governance token is paid as a second reward stream, with its own percentage.
+    /// Can only be set once. The contract needs the local Mint role for the token.
+    #[only_owner]
+    #[endpoint(setGovernanceToken)]
+    fn set_governance_token(&self, token_id: TokenIdentifier) -> SCResult<()> {
         require!(
-            roles.contains(&DcdtLocalRole::Mint),
-            "Local Mint role not set"
+            self.governance_token_id().is_empty(),
+            "Governance token already set"
+        );
+        require!(
+            token_id.is_valid_dcdt_identifier()
+                && token_id != self.stablecoin_token_id().get()
+                && !self.extra_reward_tokens().contains(&token_id),
+            "Invalid governance token"
         );
 
+        self.governance_token_id().set(&token_id);
+
         Ok(())
     }
 
-    fn try_set_percentage_rewards_per_block(
+    /// Same as setPercentageRewardPerBlock, for the governance token rewards.
+    /// Applies from the current block onwards.
+    #[endpoint(setGovernancePercentageRewardPerBlock)]
+    fn set_governance_percentage_reward_per_block(
         &self,
-        percentage_reward_per_block: &Self::BigUint,
+        percentage_reward_per_block: BigUint,
     ) -> SCResult<()> {
+        self.require_owner_or_reward_manager()?;
         require!(
-            *percentage_reward_per_block > 0 && *percentage_reward_per_block <= BASE_PRECISION,
-            "Invalid percentage"
+            !self.governance_token_id().is_empty(),
+            "Governance token must be set first"
         );
+        self.require_valid_percentage(&percentage_reward_per_block)?;
 
-        self.percentage_reward_per_block()
-            .set(percentage_reward_per_block);
+        let current_block_nonce = self.get_current_block_nonce();
+        self.push_reward_checkpoint(
+            self.governance_reward_checkpoints(),
+            &RewardScheduleEntry {
+                start_block_nonce: current_block_nonce,
+                percentage_reward_per_block: percentage_reward_per_block.clone(),
+            },
+        );
+        self.governance_percentage_reward_per_block_changed_event(
+            current_block_nonce,
+            &percentage_reward_per_block,
+        );
 
         Ok(())
     }
 
-    fn try_mint_stablecoins(&self, amount: &Self::BigUint) -> SCResult<()> {
-        self.require_local_mint_role_set()?;
+    /// The liquidity SFT issued by stablecoin-v2 can be staked next to the stablecoin.
+    /// Can only be set once.
+    #[only_owner]
+    #[endpoint(setLiquidityToken)]
+    fn set_liquidity_token(&self, token_id: TokenIdentifier) -> SCResult<()> {
+        require!(
+            self.liquidity_token_id().is_empty(),
+            "Liquidity token already set"
+        );
+        require!(
+            token_id.is_valid_dcdt_identifier() && token_id != self.stablecoin_token_id().get(),
+            "Invalid liquidity token"
+        );
 
-        let token_id = self.stablecoin_token_id().get();
-        self.send().dcdt_local_mint(&token_id, amount);
+        self.liquidity_token_id().set(&token_id);
 
         Ok(())
     }
 
-    fn send_stablecoins(&self, to: &Address, amount: &Self::BigUint) {
-        if amount > &0 {
-            let token_id = self.stablecoin_token_id().get();
-            self.send().direct(to, &token_id, amount, &[]);
-        }
+    /// Stablecoin rewards for the staked liquidity SFTs, separate from the stablecoin deposits.
+    /// Applies from the current block onwards.
+    #[endpoint(setLiquidityPercentageRewardPerBlock)]
+    fn set_liquidity_percentage_reward_per_block(
+        &self,
+        percentage_reward_per_block: BigUint,
+    ) -> SCResult<()> {
+        self.require_owner_or_reward_manager()?;
+        require!(
+            !self.liquidity_token_id().is_empty(),
+            "Liquidity token must be set first"
+        );
+        self.require_valid_percentage(&percentage_reward_per_block)?;
+
+        let current_block_nonce = self.get_current_block_nonce();
+        self.push_reward_checkpoint(
+            self.liquidity_reward_checkpoints(),
+            &RewardScheduleEntry {
+                start_block_nonce: current_block_nonce,
+                percentage_reward_per_block: percentage_reward_per_block.clone(),
+            },
+        );
+        self.liquidity_percentage_reward_per_block_changed_event(
+            current_block_nonce,
+            &percentage_reward_per_block,
+        );
+
+        Ok(())
+    }
+
+    /// Rewards in another token, paid on top of the stablecoin rewards.
+    /// The contract needs the local Mint role for the token.
+    #[endpoint(setExtraRewardToken)]
+    fn set_extra_reward_token(
+        &self,
+        token_id: TokenIdentifier,
+        percentage_reward_per_block: BigUint,
+    ) -> SCResult<()> {
+        self.require_owner_or_reward_manager()?;
+        require!(
+            token_id.is_valid_dcdt_identifier()
+                && token_id != self.stablecoin_token_id().get()
+                && (self.governance_token_id().is_empty()
+                    || token_id != self.governance_token_id().get()),
+            "Invalid reward token"
+        );
+        self.require_valid_percentage(&percentage_reward_per_block)?;
+
+        self.extra_reward_token_set_event(&token_id, &percentage_reward_per_block);
+        self.push_reward_checkpoint(
+            self.extra_reward_checkpoints(&token_id),
+            &RewardScheduleEntry {
+                start_block_nonce: self.get_current_block_nonce(),
+                percentage_reward_per_block,
+            },
+        );
+        self.extra_reward_tokens().insert(token_id);
+
+        Ok(())
     }
 
-    fn get_user_deposit_or_default(&self, address: &Address) -> UserDeposit<Self::BigUint> {
-        self.user_deposits().get(address).unwrap_or_default()
+    /// already accumulated rewards in this token can still be claimed
+    #[endpoint(removeExtraRewardToken)]
+    fn remove_extra_reward_token(&self, token_id: TokenIdentifier) -> SCResult<()> {
+        self.require_owner_or_reward_manager()?;
+        require!(
+            self.get_current_extra_reward_percentage(&token_id) > 0,
+            "Unknown reward token"
+        );
+
+        // the token stays in the list, so the rewards earned until now can still be accrued
+        self.push_reward_checkpoint(
+            self.extra_reward_checkpoints(&token_id),
+            &RewardScheduleEntry {
+                start_block_nonce: self.get_current_block_nonce(),
+                percentage_reward_per_block: BigUint::zero(),
+            },
+        );
+        self.extra_reward_token_removed_event(&token_id);
+
+        Ok(())
     }
 
-    fn update_user_deposit_or_remove_if_cleared(
+    /// credits bonus rewards to each address, claimable the same way as the regular rewards
+    #[only_owner]
+    #[endpoint(distributeBonusRewards)]
+    fn distribute_bonus_rewards(
         &self,
-        address: Address,
-        user_deposit: UserDeposit<Self::BigUint>,
-    ) {
-        if user_deposit.amount > 0 || user_deposit.cummulated_rewards > 0 {
-            self.user_deposits().insert(address, user_deposit);
+        #[var_args] bonus_entries: MultiArgVec<MultiArg2<ManagedAddress, BigUint>>,
+    ) -> SCResult<()> {
+        let current_block_nonce = self.get_current_block_nonce();
+        for entry in bonus_entries.into_vec() {
+            let (address, bonus_amount) = entry.into_tuple();
+            if bonus_amount == 0 {
+                continue;
+            }
+
+            let mut user_deposit = self.get_user_deposit_or_default(&address);
+            self.accumulate_user_rewards(&address, &mut user_deposit, current_block_nonce)?;
+            user_deposit.cummulated_rewards += &bonus_amount;
+
+            self.bonus_rewards_event(&address, current_block_nonce, &bonus_amount);
+            self.update_user_deposit_or_remove_if_cleared(address, user_deposit);
+        }
+
+        Ok(())
+    }
+
+    /// Converts up to `max_users` deposits from the first version's layout per call.
+    /// Has to be called until no legacy deposits are left, then the contract can be unpaused.
+    /// Returns the number of deposits left to convert.
+    /// The balance checkpoints of the converted deposits start at the current block.
+    #[only_owner]
+    #[endpoint(migrateStorage)]
+    fn migrate_storage(&self, max_users: usize) -> SCResult<usize> {
+        require!(!self.is_storage_up_to_date(), "Storage already up to date");
+
+        // converted entries are moved to the end of the map, so the legacy ones are always first
+        let current_block_nonce = self.get_current_block_nonce();
+        let legacy_deposits_left = self.legacy_deposits_left().get();
+        let addresses: Vec<ManagedAddress> = self
+            .legacy_user_deposits()
+            .keys()
+            .take(core::cmp::min(max_users, legacy_deposits_left))
+            .collect();
+        for address in &addresses {
+            if let Some(legacy_user_deposit) = self.legacy_user_deposits().remove(address) {
+                self.increase_total_deposits(&legacy_user_deposit.amount);
+                self.record_balance_checkpoint(
+                    address,
+                    &legacy_user_deposit.amount,
+                    current_block_nonce,
+                );
+                self.user_deposits()
+                    .insert(address.clone(), UserDeposit::from(legacy_user_deposit));
+            }
+        }
+
+        let legacy_deposits_left = legacy_deposits_left - addresses.len();
+        if legacy_deposits_left == 0 {
+            self.legacy_deposits_left().clear();
+            self.legacy_percentage_reward_per_block().clear();
+            self.storage_version().set(&CURRENT_STORAGE_VERSION);
         } else {
+            self.legacy_deposits_left().set(&legacy_deposits_left);
+        }
+
+        Ok(legacy_deposits_left)
+    }
+
+    /// The contract has to be paused first. Migrates up to `max_users` deposits per call,
+    /// so it has to be called until no deposits are left.
+    /// Stablecoin rewards are carried over, governance and extra token rewards are paid out to the users.
+    #[only_owner]
+    #[endpoint(migrateTo)]
+    fn migrate_to(&self, new_contract_address: ManagedAddress, max_users: usize) -> SCResult<()> {
+        require!(self.paused().get(), "Contract must be paused first");
+        require!(
+            self.blockchain().is_smart_contract(&new_contract_address)
+                && new_contract_address != self.blockchain().get_sc_address(),
+            "Invalid successor address"
+        );
+
+        require!(
+            self.successor_address().is_empty()
+                || self.successor_address().get() == new_contract_address,
+            "Migration to another contract already started"
+        );
+        self.successor_address().set(&new_contract_address);
+
+        let current_block_nonce = self.get_current_block_nonce();
+        let addresses: Vec<ManagedAddress> = self.user_deposits().keys().take(max_users).collect();
+        for address in addresses {
+            let mut user_deposit = self.get_user_deposit_or_default(&address);
             self.user_deposits().remove(&address);
+            self.record_balance_checkpoint(&address, &BigUint::zero(), current_block_nonce);
+
+            self.accumulate_user_rewards(&address, &mut user_deposit, current_block_nonce)?;
+            self.send_extra_rewards(&address, &mut user_deposit)?;
+            self.send_governance_rewards(&address, &mut user_deposit)?;
+
+            let (token_id, amount) = if user_deposit.amount > 0 {
+                (
+                    self.stablecoin_token_id().get(),
+                    user_deposit.amount.clone(),
+                )
+            } else {
+                (TokenIdentifier::rewa(), BigUint::zero())
+            };
+
+            self.decrease_total_deposits(&user_deposit.amount);
+            self.migrate_deposit_event(&address, &new_contract_address, &user_deposit.amount);
+            self.successor_proxy(new_contract_address.clone())
+                .accept_migrated_deposit(
+                    token_id,
+                    amount,
+                    address,
+                    user_deposit.cummulated_rewards,
+                    user_deposit.unlock_block_nonce,
+                    user_deposit.reward_multiplier,
+                )
+                .execute_on_dest_context();
         }
+
+        Ok(())
     }
 
-    // storage
+    #[only_owner]
+    #[endpoint(setPredecessorAddress)]
+    fn set_predecessor_address(&self, predecessor_address: ManagedAddress) -> SCResult<()> {
+        require!(
+            self.blockchain().is_smart_contract(&predecessor_address),
+            "Invalid predecessor address"
+        );
 
-    #[storage_mapper("stablecoinTokenId")]
-    fn stablecoin_token_id(&self) -> SingleValueMapper<Self::Storage, TokenIdentifier>;
+        self.predecessor_address().set(&predecessor_address);
 
-    #[storage_mapper("percentageRewardPerBlock")]
-    fn percentage_reward_per_block(&self) -> SingleValueMapper<Self::Storage, Self::BigUint>;
+        Ok(())
+    }
+
+    /// Percentage of the referred users' stablecoin rewards that is paid to their referrer.
+    /// Applies to all the rewards accrued after the change.
+    #[only_owner]
+    #[endpoint(setReferralRewardPercentage)]
+    fn set_referral_reward_percentage(&self, percentage: BigUint) -> SCResult<()> {
+        require!(percentage <= self.get_precision(), "Invalid percentage");
+
+        self.referral_reward_percentage().set(&percentage);
+        self.referral_reward_percentage_changed_event(self.get_current_block_nonce(), &percentage);
+
+        Ok(())
+    }
+
+    /// In timestamp mode, rewards accrue per second instead of per block. All the block nonces
+    /// and block counts used by the contract (reward schedule, locks, vesting, cooldown)
+    /// are then timestamps and seconds. Can only be changed while there are no deposits,
+    /// vesting streams or pending withdrawals, and no lock options, cooldown or vesting duration,
+    /// as those would be read in the other unit. The current percentages apply from now on,
+    /// scheduled ones are removed.
+    #[only_owner]
+    #[endpoint(setTimestampAccrual)]
+    fn set_timestamp_accrual(&self, enabled: bool) -> SCResult<()> {
+        require!(
+            self.user_deposits().is_empty()
+                && self.total_deposits().get() == 0
+                && self.liquidity_sft_nonces().is_empty(),
+            "Can only be changed while there are no deposits"
+        );
+        require!(
+            self.vesting_streams_count().get() == 0 && self.pending_withdrawals_count().get() == 0,
+            "Can only be changed while nothing is vesting or pending withdrawal"
+        );
+        require!(
+            self.lock_multipliers().is_empty()
+                && self.withdraw_cooldown_blocks().get() == 0
+                && self.vesting_duration_blocks().get() == 0,
+            "Lock options, cooldown and vesting duration must be removed first"
+        );
+        require!(
+            self.rewards_end_block_nonce().get() == 0,
+            "Rewards end block must be removed first"
+        );
+        require!(
+            self.reward_cap_reached_block_nonce().get() == 0,
+            "Reward cap already reached"
+        );
+
+        self.apply_started_reward_schedule_entries(self.get_current_block_nonce());
+        self.pending_reward_schedule().clear();
+
+        self.timestamp_accrual().set(&enabled);
+
+        let now = self.get_current_block_nonce();
+        self.restart_reward_checkpoints(self.reward_checkpoints(), now);
+        self.restart_reward_checkpoints(self.governance_reward_checkpoints(), now);
+        self.restart_reward_checkpoints(self.liquidity_reward_checkpoints(), now);
+        for token_id in self.extra_reward_tokens().iter() {
+            self.restart_reward_checkpoints(self.extra_reward_checkpoints(&token_id), now);
+        }
+
+        Ok(())
+    }
+
+    /// used to convert the per-block percentage into a yearly one, see getAPY
+    #[only_owner]
+    #[endpoint(setRoundDurationSeconds)]
+    fn set_round_duration_seconds(&self, round_duration_seconds: u64) -> SCResult<()> {
+        require!(
+            round_duration_seconds > 0 && round_duration_seconds <= SECONDS_PER_YEAR,
+            "Invalid round duration"
+        );
+
+        self.round_duration_seconds().set(&round_duration_seconds);
+
+        Ok(())
+    }
+
+    /// When set, withdrawals go through requestWithdraw and finalizeWithdraw,
+    /// with this many blocks in between. 0 disables the cooldown.
+    #[only_owner]
+    #[endpoint(setWithdrawCooldownBlocks)]
+    fn set_withdraw_cooldown_blocks(&self, cooldown_blocks: u64) -> SCResult<()> {
+        self.withdraw_cooldown_blocks().set(&cooldown_blocks);
+        self.withdraw_cooldown_changed_event(self.get_current_block_nonce(), cooldown_blocks);
+
+        Ok(())
+    }
+
+    /// Minimum amount accepted per deposit, to keep dust entries out of userDeposits
+    #[only_owner]
+    #[endpoint(setMinDepositAmount)]
+    fn set_min_deposit_amount(&self, min_deposit_amount: BigUint) -> SCResult<()> {
+        self.min_deposit_amount().set(&min_deposit_amount);
+        self.min_deposit_amount_changed_event(self.get_current_block_nonce(), &min_deposit_amount);
+
+        Ok(())
+    }
+
+    /// 0 means no cap. The cap covers the user's deposit together with the positions they opened.
+    /// Existing deposits over the cap are not affected.
+    #[only_owner]
+    #[endpoint(setMaxDepositPerUser)]
+    fn set_max_deposit_per_user(&self, max_deposit_per_user: BigUint) -> SCResult<()> {
+        self.max_deposit_per_user().set(&max_deposit_per_user);
+        self.max_deposit_per_user_changed_event(
+            self.get_current_block_nonce(),
+            &max_deposit_per_user,
+        );
+
+        Ok(())
+    }
+
+    /// 0 means no cap
+    #[only_owner]
+    #[endpoint(setMaxTotalDeposits)]
+    fn set_max_total_deposits(&self, max_total_deposits: BigUint) -> SCResult<()> {
+        self.max_total_deposits().set(&max_total_deposits);
+        self.max_total_deposits_changed_event(self.get_current_block_nonce(), &max_total_deposits);
+
+        Ok(())
+    }
+
+    /// Percentage of each deposit that is sent to the treasury, using the precision.
+    /// Requires the treasury address to be set.
+    #[only_owner]
+    #[endpoint(setDepositFeePercentage)]
+    fn set_deposit_fee_percentage(&self, percentage: BigUint) -> SCResult<()> {
+        require!(percentage < self.get_precision(), "Invalid percentage");
+        require!(
+            percentage == 0 || !self.treasury_address().is_empty(),
+            "Treasury address must be set first"
+        );
+
+        self.deposit_fee_percentage().set(&percentage);
+        self.deposit_fee_percentage_changed_event(self.get_current_block_nonce(), &percentage);
+
+        Ok(())
+    }
+
+    /// Penalties are sent to the treasury if set, and burned otherwise.
+    /// Deposit fees are always sent to the treasury, so it can't be removed while there is a fee.
+    #[only_owner]
+    #[endpoint(setTreasuryAddress)]
+    fn set_treasury_address(
+        &self,
+        #[var_args] opt_address: OptionalArg<ManagedAddress>,
+    ) -> SCResult<()> {
+        let address = match opt_address.into_option() {
+            Some(address) => {
+                self.treasury_address().set(&address);
+                address
+            }
+            None => {
+                require!(
+                    self.deposit_fee_percentage().get() == 0,
+                    "Cannot remove the treasury while there is a deposit fee"
+                );
+                self.treasury_address().clear();
+                ManagedAddress::zero()
+            }
+        };
+        self.treasury_address_changed_event(self.get_current_block_nonce(), &address);
+
+        Ok(())
+    }
+
+    /// DEX pair used by claimRewardsSwapped, swapping the stablecoin into the output token.
+    /// No arguments removes the pair.
+    #[only_owner]
+    #[endpoint(setSwapPair)]
+    fn set_swap_pair(
+        &self,
+        #[var_args] opt_pair: OptionalArg<MultiArg2<ManagedAddress, TokenIdentifier>>,
+    ) -> SCResult<()> {
+        match opt_pair.into_option() {
+            Some(pair) => {
+                let (pair_address, output_token_id) = pair.into_tuple();
+                require!(
+                    self.blockchain().is_smart_contract(&pair_address),
+                    "Invalid pair address"
+                );
+                require!(
+                    output_token_id.is_valid_dcdt_identifier()
+                        && output_token_id != self.stablecoin_token_id().get(),
+                    "Invalid output token"
+                );
+
+                self.swap_pair_changed_event(&pair_address, &output_token_id);
+                self.swap_pair_address().set(&pair_address);
+                self.swap_output_token_id().set(&output_token_id);
+            }
+            None => {
+                self.swap_pair_changed_event(&ManagedAddress::zero(), &TokenIdentifier::rewa());
+                self.swap_pair_address().clear();
+                self.swap_output_token_id().clear();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends tokens that were sent to the contract by mistake to the owner.
+    /// The stablecoin and the liquidity token can't be recovered, as they belong to the depositors.
+    #[only_owner]
+    #[endpoint(recoverTokens)]
+    fn recover_tokens(
+        &self,
+        token_id: TokenIdentifier,
+        nonce: u64,
+        amount: BigUint,
+    ) -> SCResult<()> {
+        require!(
+            token_id != self.stablecoin_token_id().get()
+                && (self.liquidity_token_id().is_empty()
+                    || token_id != self.liquidity_token_id().get()),
+            "Cannot recover the staked tokens"
+        );
+        require!(amount > 0, "Invalid amount");
+
+        let caller = self.blockchain().get_caller();
+        self.send().direct(&caller, &token_id, nonce, &amount, &[]);
+        self.recover_tokens_event(&token_id, nonce, &amount);
+
+        Ok(())
+    }
+
+    // endpoints
+
+    /// optional lock duration in blocks. Must be one of the configured lock options.
+    /// Locking again can only extend the current lock, never shorten it.
+    /// The referrer is only recorded on the first deposit. Pass a 0 lock duration to skip the lock.
+    /// Accepts several stablecoin transfers in one call, which are deposited as a single amount.
+    #[payable("*")]
+    #[endpoint]
+    fn deposit(
+        &self,
+        #[var_args] opt_lock_blocks: OptionalArg<u64>,
+        #[var_args] opt_referrer: OptionalArg<ManagedAddress>,
+    ) -> SCResult<()> {
+        let (token_id, amount) = self.get_stablecoin_payments_total()?;
+
+        let caller = self.blockchain().get_caller();
+        if let Some(referrer) = opt_referrer.into_option() {
+            self.try_set_referrer(&caller, referrer)?;
+        }
+
+        let opt_lock_blocks = opt_lock_blocks
+            .into_option()
+            .filter(|lock_blocks| *lock_blocks > 0);
+
+        self.deposit_common(caller, token_id, amount, opt_lock_blocks)
+    }
+
+    /// deposits on behalf of the beneficiary. The beneficiary's lock, if any, is not changed.
+    /// While the whitelist is enabled, both the caller and the beneficiary have to be whitelisted.
+    #[payable("*")]
+    #[endpoint(depositFor)]
+    fn deposit_for(
+        &self,
+        #[payment_token] token_id: TokenIdentifier,
+        #[payment_amount] amount: BigUint,
+        beneficiary: ManagedAddress,
+    ) -> SCResult<()> {
+        require!(!beneficiary.is_zero(), "Invalid beneficiary");
+        let caller = self.blockchain().get_caller();
+        self.require_whitelisted(&caller)?;
+        self.require_not_blacklisted(&caller)?;
+
+        self.deposit_common(beneficiary, token_id, amount, None)
+    }
+
+    /// optional amount to withdraw. Defaults to max possible.
+    /// optional destination for the withdrawn tokens. Defaults to the caller.
+    /// The amount has to be passed as well when a destination is given.
+    #[endpoint]
+    fn withdraw(
+        &self,
+        #[var_args] opt_amount: OptionalArg<BigUint>,
+        #[var_args] opt_destination: OptionalArg<ManagedAddress>,
+    ) -> SCResult<()> {
+        self.require_not_paused()?;
+        require!(
+            self.withdraw_cooldown_blocks().get() == 0,
+            "Withdrawals have a cooldown, use requestWithdraw"
+        );
+
+        let caller = self.blockchain().get_caller();
+        let destination = opt_destination
+            .into_option()
+            .unwrap_or_else(|| caller.clone());
+        require!(!destination.is_zero(), "Invalid destination");
+
+        let current_block_nonce = self.get_current_block_nonce();
+        let (amount, amount_after_penalty) =
+            self.debit_user_deposit(&caller, opt_amount.into_option(), current_block_nonce)?;
+
+        self.send_stablecoins(&destination, &amount_after_penalty);
+        self.withdraw_event(&caller, current_block_nonce, &amount);
+
+        Ok(())
+    }
+
+    /// Starts the cooldown for the amount, which stops earning rewards right away.
+    /// Requesting again adds to the pending amount and restarts the cooldown.
+    /// Optional amount, defaults to max possible.
+    #[endpoint(requestWithdraw)]
+    fn request_withdraw(&self, #[var_args] opt_amount: OptionalArg<BigUint>) -> SCResult<()> {
+        self.require_not_paused()?;
+
+        require!(
+            self.withdraw_cooldown_blocks().get() > 0,
+            "No cooldown, use withdraw"
+        );
+
+        let caller = self.blockchain().get_caller();
+        let current_block_nonce = self.get_current_block_nonce();
+        let (amount, amount_after_penalty) =
+            self.debit_user_deposit(&caller, opt_amount.into_option(), current_block_nonce)?;
+
+        self.add_pending_withdrawal(&caller, amount_after_penalty, current_block_nonce);
+        self.withdraw_requested_event(&caller, current_block_nonce, &amount);
+
+        Ok(())
+    }
+
+    /// Sends the pending withdrawal once the cooldown passed.
+    /// Not gated by pause, as the amount is no longer part of the deposits.
+    #[endpoint(finalizeWithdraw)]
+    fn finalize_withdraw(&self) -> SCResult<()> {
+        let caller = self.blockchain().get_caller();
+        require!(
+            !self.pending_withdrawal(&caller).is_empty(),
+            "No pending withdrawal"
+        );
+
+        let current_block_nonce = self.get_current_block_nonce();
+        let pending_withdrawal = self.pending_withdrawal(&caller).get();
+        require!(
+            current_block_nonce >= pending_withdrawal.unlock_block_nonce,
+            "Cooldown not over yet"
+        );
+
+        self.pending_withdrawal(&caller).clear();
+        self.pending_withdrawals_count().update(|count| *count -= 1);
+        self.send_stablecoins(&caller, &pending_withdrawal.amount);
+        self.withdraw_event(&caller, current_block_nonce, &pending_withdrawal.amount);
+
+        Ok(())
+    }
+
+    /// optional amount to claim. Defaults to all the accumulated rewards.
+    #[endpoint(claimRewards)]
+    fn claim_rewards(&self, #[var_args] opt_amount: OptionalArg<BigUint>) -> SCResult<()> {
+        let caller = self.blockchain().get_caller();
+
+        self.claim_rewards_common(caller, opt_amount.into_option())
+    }
+
+    /// Anyone can trigger the claim for another user. The rewards are always sent to that user.
+    /// Not available with vesting on, as the claim would move the end of the user's stream.
+    #[endpoint(claimRewardsFor)]
+    fn claim_rewards_for(&self, address: ManagedAddress) -> SCResult<()> {
+        require!(
+            self.user_deposits().contains_key(&address),
+            "Address has no deposit"
+        );
+        require!(
+            self.vesting_duration_blocks().get() == 0,
+            "Cannot claim for others while rewards vest"
+        );
+
+        self.claim_rewards_common(address, None)
+    }
+
+    /// Claims all the caller's stablecoin rewards and swaps them through the configured DEX pair.
+    /// The output token is sent to the caller once the swap is done.
+    /// If the swap fails, the caller receives the stablecoin rewards instead.
+    /// Can't be used while rewards are vesting, as they are not paid out right away.
+    #[endpoint(claimRewardsSwapped)]
+    fn claim_rewards_swapped(&self, amount_out_min: BigUint) -> SCResult<AsyncCall> {
+        self.require_not_paused()?;
+        require!(!self.swap_pair_address().is_empty(), "Swap pair not set");
+        require!(
+            self.vesting_duration_blocks().get() == 0,
+            "Rewards are vesting, use claimRewards"
+        );
+
+        let caller = self.blockchain().get_caller();
+        self.require_not_blacklisted(&caller)?;
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut user_deposit = self.get_user_deposit_or_default(&caller);
+
+        self.accumulate_user_rewards(&caller, &mut user_deposit, current_block_nonce)?;
+
+        let rewards_amount = self.mint_rewards(&user_deposit.cummulated_rewards)?;
+        require!(rewards_amount > 0, "No rewards to claim");
+        self.claim_rewards_event(&caller, current_block_nonce, &rewards_amount);
+        self.record_user_reward_history(
+            &caller,
+            RewardHistoryAction::Claimed,
+            &rewards_amount,
+            current_block_nonce,
+        );
+
+        // anything that couldn't be paid stays recorded, but can't be claimed yet
+        user_deposit.cummulated_rewards -= &rewards_amount;
+        self.update_user_deposit_or_remove_if_cleared(caller.clone(), user_deposit);
+
+        Ok(self
+            .swap_proxy(self.swap_pair_address().get())
+            .swap_tokens_fixed_input(
+                self.stablecoin_token_id().get(),
+                rewards_amount.clone(),
+                self.swap_output_token_id().get(),
+                amount_out_min,
+            )
+            .async_call()
+            .with_callback(
+                self.callbacks()
+                    .swap_rewards_callback(&caller, &rewards_amount),
+            ))
+    }
+
+    /// Claims the caller's stablecoin rewards and sends them to `endpoint_name` of `target_sc`
+    /// in the same transaction, followed by the optional arguments.
+    /// Can't be used while rewards are vesting, as they are not paid out right away.
+    #[endpoint(claimAndForward)]
+    fn claim_and_forward(
+        &self,
+        target_sc: ManagedAddress,
+        endpoint_name: ManagedBuffer,
+        #[var_args] args: VarArgs<ManagedBuffer>,
+    ) -> SCResult<()> {
+        self.require_not_paused()?;
+        require!(
+            self.vesting_duration_blocks().get() == 0,
+            "Rewards are vesting, use claimRewards"
+        );
+        require!(
+            self.blockchain().is_smart_contract(&target_sc)
+                && target_sc != self.blockchain().get_sc_address(),
+            "Invalid target address"
+        );
+        require!(!endpoint_name.is_empty(), "Invalid endpoint name");
+
+        let caller = self.blockchain().get_caller();
+        self.require_not_blacklisted(&caller)?;
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut user_deposit = self.get_user_deposit_or_default(&caller);
+
+        self.accumulate_user_rewards(&caller, &mut user_deposit, current_block_nonce)?;
+
+        let rewards_amount = self.mint_rewards(&user_deposit.cummulated_rewards)?;
+        require!(rewards_amount > 0, "No rewards to claim");
+        self.claim_rewards_event(&caller, current_block_nonce, &rewards_amount);
+        self.record_user_reward_history(
+            &caller,
+            RewardHistoryAction::Claimed,
+            &rewards_amount,
+            current_block_nonce,
+        );
+
+        // anything that couldn't be paid stays recorded, but can't be claimed yet
+        user_deposit.cummulated_rewards -= &rewards_amount;
+        self.update_user_deposit_or_remove_if_cleared(caller, user_deposit);
+
+        let mut contract_call =
+            ContractCall::<Self::Api, ()>::new(self.raw_vm_api(), target_sc, endpoint_name)
+                .add_token_transfer(self.stablecoin_token_id().get(), 0, rewards_amount);
+        for arg in args.into_vec() {
+            contract_call.push_endpoint_arg(arg);
+        }
+        contract_call.execute_on_dest_context();
+
+        Ok(())
+    }
+
+    /// Sends the matured part of the caller's vesting stream.
+    /// Not gated by pause, as the rewards were already minted and streams are not migrated.
+    #[endpoint(claimVested)]
+    fn claim_vested(&self) -> SCResult<()> {
+        let caller = self.blockchain().get_caller();
+        require!(!self.user_vesting(&caller).is_empty(), "Nothing vesting");
+
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut vesting_stream = self.user_vesting(&caller).get();
+        let claimable_amount = vesting_stream.get_claimable_amount(current_block_nonce);
+        require!(claimable_amount > 0, "Nothing to claim yet");
+
+        self.release_vested(
+            &caller,
+            &mut vesting_stream,
+            &claimable_amount,
+            current_block_nonce,
+        );
+
+        Ok(())
+    }
+
+    #[endpoint(claimGovernanceRewards)]
+    fn claim_governance_rewards(&self) -> SCResult<()> {
+        self.require_not_paused()?;
+
+        let caller = self.blockchain().get_caller();
+        self.require_not_blacklisted(&caller)?;
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut user_deposit = self.get_user_deposit_or_default(&caller);
+
+        self.accumulate_user_rewards(&caller, &mut user_deposit, current_block_nonce)?;
+        require!(
+            user_deposit.governance_rewards > 0,
+            "No governance rewards to claim"
+        );
+        self.send_governance_rewards(&caller, &mut user_deposit)?;
+
+        self.update_user_deposit_or_remove_if_cleared(caller, user_deposit);
+
+        Ok(())
+    }
+
+    /// claims the stablecoin, governance token and extra token rewards in one call
+    #[endpoint(claimAllRewards)]
+    fn claim_all_rewards(&self) -> SCResult<()> {
+        self.require_not_paused()?;
+
+        let caller = self.blockchain().get_caller();
+        self.require_not_blacklisted(&caller)?;
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut user_deposit = self.get_user_deposit_or_default(&caller);
+
+        self.accumulate_user_rewards(&caller, &mut user_deposit, current_block_nonce)?;
+        let rewards_amount = user_deposit.cummulated_rewards.clone();
+        self.send_stablecoin_rewards(
+            &caller,
+            &mut user_deposit,
+            &rewards_amount,
+            current_block_nonce,
+        )?;
+        self.send_governance_rewards(&caller, &mut user_deposit)?;
+        self.send_extra_rewards(&caller, &mut user_deposit)?;
+
+        self.update_user_deposit_or_remove_if_cleared(caller, user_deposit);
+
+        Ok(())
+    }
+
+    #[endpoint(claimTokenRewards)]
+    fn claim_token_rewards(&self, token_id: TokenIdentifier) -> SCResult<()> {
+        self.require_not_paused()?;
+
+        let caller = self.blockchain().get_caller();
+        self.require_not_blacklisted(&caller)?;
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut user_deposit = self.get_user_deposit_or_default(&caller);
+
+        self.accumulate_user_rewards(&caller, &mut user_deposit, current_block_nonce)?;
+
+        let rewards_amount = user_deposit.take_extra_rewards(&token_id);
+        require!(rewards_amount > 0, "No rewards to claim for this token");
+
+        self.mint_token_rewards(&token_id, &rewards_amount)?;
+        self.send()
+            .direct(&caller, &token_id, 0, &rewards_amount, &[]);
+        self.claim_token_rewards_event(&caller, &token_id, &rewards_amount);
+
+        self.update_user_deposit_or_remove_if_cleared(caller, user_deposit);
+
+        Ok(())
+    }
+
+    #[endpoint(claimReferralRewards)]
+    fn claim_referral_rewards(&self) -> SCResult<()> {
+        self.require_not_paused()?;
+
+        let caller = self.blockchain().get_caller();
+        self.require_not_blacklisted(&caller)?;
+        let referral_rewards = self.referral_rewards(&caller).get();
+        require!(referral_rewards > 0, "No referral rewards to claim");
+
+        let rewards_amount = self.mint_rewards(&referral_rewards)?;
+        self.send_stablecoins(&caller, &rewards_amount);
+        self.claim_referral_rewards_event(&caller, self.get_current_block_nonce(), &rewards_amount);
+
+        // anything that couldn't be paid stays recorded, but can't be claimed yet
+        self.referral_rewards(&caller)
+            .set(&(referral_rewards - rewards_amount));
+
+        Ok(())
+    }
+
+    /// Creates a separate deposit, represented by an NFT sent to the caller.
+    /// Whoever holds the NFT can claim its rewards or withdraw it.
+    /// Positions don't earn referral rewards and are not migrated by migrateTo.
+    #[payable("*")]
+    #[endpoint(depositPosition)]
+    fn deposit_position(
+        &self,
+        #[payment_token] token_id: TokenIdentifier,
+        #[payment_amount] amount: BigUint,
+        #[var_args] opt_lock_blocks: OptionalArg<u64>,
+    ) -> SCResult<u64> {
+        self.require_not_paused()?;
+        self.require_position_token_issued()?;
+
+        let caller = self.blockchain().get_caller();
+        self.require_whitelisted(&caller)?;
+        self.require_not_blacklisted(&caller)?;
+        require!(
+            token_id == self.stablecoin_token_id().get(),
+            "Wrong payment token"
+        );
+        require!(amount > 0, "Must deposit more than 0");
+        require!(
+            amount >= self.min_deposit_amount().get(),
+            "Deposit amount too small"
+        );
+
+        let current_block_nonce = self.get_current_block_nonce();
+        let amount = self.take_deposit_fee(&caller, amount, current_block_nonce);
+        let new_user_amount = self.get_user_deposit_or_default(&caller).amount
+            + self.user_positions_amount(&caller).get()
+            + &amount;
+        self.require_under_deposit_caps(&new_user_amount, &amount)?;
+
+        let mut position = self.new_user_deposit();
+        position.amount = amount.clone();
+        position.last_claim_block_nonce = current_block_nonce;
+
+        if let OptionalArg::Some(lock_blocks) = opt_lock_blocks {
+            if lock_blocks > 0 {
+                let multiplier = self.get_lock_multiplier(lock_blocks)?;
+                position.lock(current_block_nonce + lock_blocks, multiplier);
+            }
+        }
+
+        self.increase_total_deposits(&amount);
+
+        let nonce = self.create_and_send_position_token(&caller, &position);
+        self.positions(nonce).set(&position);
+        self.position_depositor(nonce).set(&caller);
+        self.user_positions_amount(&caller)
+            .update(|positions_amount| *positions_amount += &amount);
+        self.position_deposit_event(&caller, nonce, &amount);
+
+        Ok(nonce)
+    }
+
+    /// Pays the position's rewards and returns a new NFT with the updated checkpoint.
+    /// The old NFT is burned. Returns the nonce of the new NFT.
+    #[payable("*")]
+    #[endpoint(claimPositionRewards)]
+    fn claim_position_rewards(
+        &self,
+        #[payment_token] token_id: TokenIdentifier,
+        #[payment_nonce] nonce: u64,
+        #[payment_amount] payment_amount: BigUint,
+    ) -> SCResult<u64> {
+        self.require_not_paused()?;
+        self.require_position_token(&token_id, nonce, &payment_amount)?;
+
+        let caller = self.blockchain().get_caller();
+        self.require_not_blacklisted(&caller)?;
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut position = self.positions(nonce).get();
+
+        self.pay_position_rewards(&caller, nonce, &mut position, current_block_nonce)?;
+
+        self.burn_position_token(nonce);
+        self.positions(nonce).clear();
+
+        let new_nonce = self.create_and_send_position_token(&caller, &position);
+        self.positions(new_nonce).set(&position);
+        let depositor_mapper = self.position_depositor(nonce);
+        if !depositor_mapper.is_empty() {
+            self.position_depositor(new_nonce)
+                .set(&depositor_mapper.get());
+            depositor_mapper.clear();
+        }
+
+        Ok(new_nonce)
+    }
+
+    /// Withdraws the whole position, together with its rewards, and burns the NFT.
+    /// Lock and cooldown rules apply the same way as for regular deposits.
+    /// Blacklisted callers forfeit the position's rewards.
+    #[payable("*")]
+    #[endpoint(withdrawPosition)]
+    fn withdraw_position(
+        &self,
+        #[payment_token] token_id: TokenIdentifier,
+        #[payment_nonce] nonce: u64,
+        #[payment_amount] payment_amount: BigUint,
+    ) -> SCResult<()> {
+        self.require_not_paused()?;
+        self.require_position_token(&token_id, nonce, &payment_amount)?;
+
+        let caller = self.blockchain().get_caller();
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut position = self.positions(nonce).get();
+        let amount = position.amount.clone();
+
+        let penalty_amount =
+            self.calculate_withdraw_penalty(&position, &amount, current_block_nonce)?;
+        if !self.is_blacklisted(&caller) {
+            self.pay_position_rewards(&caller, nonce, &mut position, current_block_nonce)?;
+        }
+
+        self.handle_penalty(&penalty_amount)?;
+        self.send_or_add_pending_withdrawal(
+            &caller,
+            &amount - &penalty_amount,
+            current_block_nonce,
+        );
+        self.decrease_total_deposits(&amount);
+        self.remove_position_from_depositor(nonce, &amount);
+        self.position_withdraw_event(&caller, nonce, &amount);
+
+        // rewards that couldn't be paid are lost together with the position
+        self.burn_position_token(nonce);
+        self.positions(nonce).clear();
+
+        Ok(())
+    }
+
+    /// Stakes stablecoin-v2 liquidity SFTs. Each SFT nonce is tracked separately,
+    /// and earns stablecoin rewards with the liquidity percentage.
+    /// Liquidity deposits have no lock options, boost tiers or withdraw cooldown.
+    #[payable("*")]
+    #[endpoint(depositLiquidity)]
+    fn deposit_liquidity(
+        &self,
+        #[payment_token] token_id: TokenIdentifier,
+        #[payment_nonce] sft_nonce: u64,
+        #[payment_amount] amount: BigUint,
+    ) -> SCResult<()> {
+        self.require_not_paused()?;
+        require!(
+            !self.liquidity_token_id().is_empty() && token_id == self.liquidity_token_id().get(),
+            "Wrong payment token"
+        );
+        require!(amount > 0, "Must deposit more than 0");
+
+        let caller = self.blockchain().get_caller();
+        self.require_whitelisted(&caller)?;
+        self.require_not_blacklisted(&caller)?;
+
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut liquidity_deposit = self
+            .liquidity_deposits(sft_nonce)
+            .get(&caller)
+            .unwrap_or_else(|| self.new_user_deposit());
+
+        self.accumulate_liquidity_rewards(&mut liquidity_deposit, current_block_nonce);
+        liquidity_deposit.amount += &amount;
+
+        self.total_liquidity_deposits(sft_nonce)
+            .update(|total| *total += &amount);
+        self.liquidity_sft_nonces().insert(sft_nonce);
+        self.liquidity_deposit_event(&caller, sft_nonce, &amount);
+        self.liquidity_deposits(sft_nonce)
+            .insert(caller, liquidity_deposit);
+
+        Ok(())
+    }
+
+    /// optional amount to withdraw. Defaults to max possible.
+    /// The pending rewards are paid out as well.
+    #[endpoint(withdrawLiquidity)]
+    fn withdraw_liquidity(
+        &self,
+        sft_nonce: u64,
+        #[var_args] opt_amount: OptionalArg<BigUint>,
+    ) -> SCResult<()> {
+        self.require_not_paused()?;
+
+        let caller = self.blockchain().get_caller();
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut liquidity_deposit = self
+            .liquidity_deposits(sft_nonce)
+            .get(&caller)
+            .unwrap_or_else(|| self.new_user_deposit());
+        let amount = opt_amount
+            .into_option()
+            .unwrap_or_else(|| liquidity_deposit.amount.clone());
+
+        require!(amount > 0, "Must withdraw more than 0");
+        require!(
+            amount <= liquidity_deposit.amount,
+            "Cannot withdraw more than deposited amount"
+        );
+
+        self.accumulate_liquidity_rewards(&mut liquidity_deposit, current_block_nonce);
+        if !self.is_blacklisted(&caller) {
+            self.send_liquidity_rewards(
+                &caller,
+                sft_nonce,
+                &mut liquidity_deposit,
+                current_block_nonce,
+            )?;
+        }
+        liquidity_deposit.amount -= &amount;
+
+        self.total_liquidity_deposits(sft_nonce)
+            .update(|total| *total -= &amount);
+        self.send().direct(
+            &caller,
+            &self.liquidity_token_id().get(),
+            sft_nonce,
+            &amount,
+            &[],
+        );
+        self.liquidity_withdraw_event(&caller, sft_nonce, &amount);
+
+        self.update_liquidity_deposit_or_remove_if_cleared(caller, sft_nonce, liquidity_deposit);
+
+        Ok(())
+    }
+
+    #[endpoint(claimLiquidityRewards)]
+    fn claim_liquidity_rewards(&self, sft_nonce: u64) -> SCResult<()> {
+        self.require_not_paused()?;
+
+        let caller = self.blockchain().get_caller();
+        self.require_not_blacklisted(&caller)?;
+        require!(
+            self.liquidity_deposits(sft_nonce).contains_key(&caller),
+            "No liquidity deposit"
+        );
+
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut liquidity_deposit = self.liquidity_deposits(sft_nonce).get(&caller).unwrap();
+
+        self.accumulate_liquidity_rewards(&mut liquidity_deposit, current_block_nonce);
+        self.send_liquidity_rewards(
+            &caller,
+            sft_nonce,
+            &mut liquidity_deposit,
+            current_block_nonce,
+        )?;
+
+        self.update_liquidity_deposit_or_remove_if_cleared(caller, sft_nonce, liquidity_deposit);
+
+        Ok(())
+    }
+
+    /// Withdraws the whole deposit without touching the reward minting logic.
+    /// Any accumulated rewards are forfeited. A locked deposit can still be withdrawn,
+    /// paying the early withdraw penalty if one is set. The amount is sent right away,
+    /// skipping the withdraw cooldown.
+    #[endpoint(emergencyWithdraw)]
+    fn emergency_withdraw(&self) -> SCResult<()> {
+        let caller = self.blockchain().get_caller();
+        let mut user_deposit = self.get_user_deposit_or_default(&caller);
+        let amount = core::mem::replace(&mut user_deposit.amount, BigUint::zero());
+        require!(amount > 0, "Nothing to withdraw");
+
+        let current_block_nonce = self.get_current_block_nonce();
+        let penalty_amount = if user_deposit.is_locked(current_block_nonce) {
+            self.calculate_penalty_amount(&amount)
+        } else {
+            BigUint::zero()
+        };
+
+        self.handle_penalty(&penalty_amount)?;
+        self.send_stablecoins(&caller, &(&amount - &penalty_amount));
+        self.decrease_total_deposits(&amount);
+
+        user_deposit.cummulated_rewards = BigUint::zero();
+        user_deposit.extra_rewards.clear();
+        user_deposit.governance_rewards = BigUint::zero();
+        user_deposit.last_claim_block_nonce = current_block_nonce;
+        user_deposit.penalties_paid += penalty_amount;
+
+        self.emergency_withdraw_event(&caller, current_block_nonce, &amount);
+        self.update_user_deposit_or_remove_if_cleared(caller, user_deposit);
+
+        Ok(())
+    }
+
+    /// mints the pending rewards and adds them to the deposited amount instead of sending them
+    #[endpoint]
+    fn compound(&self) -> SCResult<()> {
+        self.require_not_paused()?;
+
+        let caller = self.blockchain().get_caller();
+        self.require_not_blacklisted(&caller)?;
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut user_deposit = self.get_user_deposit_or_default(&caller);
+
+        self.accumulate_user_rewards(&caller, &mut user_deposit, current_block_nonce)?;
+        require!(
+            user_deposit.cummulated_rewards > 0,
+            "No rewards to compound"
+        );
+
+        let rewards_amount =
+            self.compound_user_rewards(&caller, &mut user_deposit, current_block_nonce)?;
+        require!(rewards_amount > 0, "No rewards available");
+
+        self.update_user_deposit_or_remove_if_cleared(caller, user_deposit);
+
+        Ok(())
+    }
+
+    /// Moves the unlock block of the caller's deposit further, without withdrawing.
+    /// The multiplier becomes the one of the longest lock option that fits until the new unlock block,
+    /// if it's higher than the current one. Rewards until now use the previous multiplier.
+    #[endpoint(extendLock)]
+    fn extend_lock(&self, new_unlock_block_nonce: u64) -> SCResult<()> {
+        self.require_not_paused()?;
+
+        let caller = self.blockchain().get_caller();
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut user_deposit = self.get_user_deposit_or_default(&caller);
+        require!(user_deposit.amount > 0, "Nothing deposited");
+        require!(
+            new_unlock_block_nonce > current_block_nonce
+                && new_unlock_block_nonce > user_deposit.unlock_block_nonce,
+            "Can only extend the lock"
+        );
+
+        let lock_blocks = new_unlock_block_nonce - current_block_nonce;
+        let mut opt_multiplier: Option<BigUint> = None;
+        for (option_lock_blocks, option_multiplier) in self.lock_multipliers().iter() {
+            let is_higher = match &opt_multiplier {
+                Some(multiplier) => &option_multiplier > multiplier,
+                None => true,
+            };
+            if option_lock_blocks <= lock_blocks && is_higher {
+                opt_multiplier = Some(option_multiplier);
+            }
+        }
+        let multiplier = match opt_multiplier {
+            Some(multiplier) => multiplier,
+            None => return sc_error!("Lock too short for any lock option"),
+        };
+
+        self.accumulate_user_rewards(&caller, &mut user_deposit, current_block_nonce)?;
+
+        let multiplier = if user_deposit.is_locked(current_block_nonce)
+            && user_deposit.reward_multiplier > multiplier
+        {
+            user_deposit.reward_multiplier.clone()
+        } else {
+            multiplier
+        };
+        user_deposit.lock(new_unlock_block_nonce, multiplier);
+
+        self.extend_lock_event(
+            &caller,
+            new_unlock_block_nonce,
+            &user_deposit.reward_multiplier,
+        );
+        self.update_user_deposit_or_remove_if_cleared(caller, user_deposit);
+
+        Ok(())
+    }
+
+    /// When enabled, rewards are added to the caller's deposit every time they are accumulated,
+    /// instead of being kept for claiming
+    #[endpoint(setAutoRestake)]
+    fn set_auto_restake(&self, enabled: bool) -> SCResult<()> {
+        let caller = self.blockchain().get_caller();
+        if enabled {
+            self.auto_restake(&caller).set(&true);
+        } else {
+            self.auto_restake(&caller).clear();
+        }
+        self.auto_restake_changed_event(&caller, enabled);
+
+        Ok(())
+    }
+
+    /// called by the predecessor contract during migration, see successor_proxy
+    #[payable("*")]
+    #[endpoint(acceptMigratedDeposit)]
+    fn accept_migrated_deposit(
+        &self,
+        #[payment_token] token_id: TokenIdentifier,
+        #[payment_amount] amount: BigUint,
+        user: ManagedAddress,
+        cummulated_rewards: BigUint,
+        unlock_block_nonce: u64,
+        reward_multiplier: BigUint,
+    ) -> SCResult<()> {
+        let caller = self.blockchain().get_caller();
+        require!(
+            !self.predecessor_address().is_empty() && caller == self.predecessor_address().get(),
+            "Only the predecessor contract may migrate deposits"
+        );
+        require!(
+            token_id == self.stablecoin_token_id().get() || amount == 0,
+            "Wrong payment token"
+        );
+
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut user_deposit = self.get_user_deposit_or_default(&user);
+
+        self.accumulate_user_rewards(&user, &mut user_deposit, current_block_nonce)?;
+        self.increase_total_deposits(&amount);
+        user_deposit.amount += &amount;
+        user_deposit.cummulated_rewards += cummulated_rewards;
+        if unlock_block_nonce > user_deposit.unlock_block_nonce {
+            user_deposit.lock(unlock_block_nonce, reward_multiplier);
+        }
+
+        self.deposit_event(&user, current_block_nonce, &amount);
+        self.update_user_deposit_or_remove_if_cleared(user, user_deposit);
+
+        Ok(())
+    }
+
+    // views
+
+    /// rewards accumulated up to the current block, that were not claimed yet
+    #[view(getPendingRewards)]
+    fn get_pending_rewards(&self, address: ManagedAddress) -> BigUint {
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut user_deposit = self.get_user_deposit_or_default(&address);
+
+        self.accumulate_deposit_rewards(&mut user_deposit, current_block_nonce);
+
+        user_deposit.cummulated_rewards
+    }
+
+    #[view(getPendingGovernanceRewards)]
+    fn get_pending_governance_rewards(&self, address: ManagedAddress) -> BigUint {
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut user_deposit = self.get_user_deposit_or_default(&address);
+
+        self.accumulate_deposit_rewards(&mut user_deposit, current_block_nonce);
+
+        user_deposit.governance_rewards
+    }
+
+    #[view(getPendingTokenRewards)]
+    fn get_pending_token_rewards(
+        &self,
+        address: ManagedAddress,
+        token_id: TokenIdentifier,
+    ) -> BigUint {
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut user_deposit = self.get_user_deposit_or_default(&address);
+
+        self.accumulate_deposit_rewards(&mut user_deposit, current_block_nonce);
+
+        user_deposit.get_extra_rewards(&token_id)
+    }
+
+    /// returns the deposited amount, the pending rewards and the last update block nonce
+    #[view(getUserDeposit)]
+    fn get_user_deposit_view(
+        &self,
+        address: ManagedAddress,
+    ) -> MultiResult3<BigUint, BigUint, u64> {
+        let user_deposit = self.get_user_deposit_or_default(&address);
+        let pending_rewards = self.get_pending_rewards(address);
+
+        (
+            user_deposit.amount,
+            pending_rewards,
+            user_deposit.last_claim_block_nonce,
+        )
+            .into()
+    }
+
+    /// amount, pending rewards, unlock block
+    #[view(getPosition)]
+    fn get_position(&self, nonce: u64) -> MultiResult3<BigUint, BigUint, u64> {
+        if self.positions(nonce).is_empty() {
+            return (BigUint::zero(), BigUint::zero(), 0).into();
+        }
+
+        let mut position = self.positions(nonce).get();
+        self.accumulate_deposit_rewards(&mut position, self.get_current_block_nonce());
+
+        (
+            position.amount,
+            position.cummulated_rewards,
+            position.unlock_block_nonce,
+        )
+            .into()
+    }
+
+    /// returns the staked amount of the liquidity SFT and the pending rewards
+    #[view(getLiquidityDeposit)]
+    fn get_liquidity_deposit_view(
+        &self,
+        address: ManagedAddress,
+        sft_nonce: u64,
+    ) -> MultiResult2<BigUint, BigUint> {
+        let mut liquidity_deposit = self
+            .liquidity_deposits(sft_nonce)
+            .get(&address)
+            .unwrap_or_else(|| self.new_user_deposit());
+        self.accumulate_liquidity_rewards(&mut liquidity_deposit, self.get_current_block_nonce());
+
+        (
+            liquidity_deposit.amount,
+            liquidity_deposit.cummulated_rewards,
+        )
+            .into()
+    }
+
+    #[view(getCurrentPercentageRewardPerBlock)]
+    fn get_current_percentage_reward_per_block(&self) -> BigUint {
+        let current_block_nonce = self.get_current_block_nonce();
+        if self.have_rewards_ended(current_block_nonce) {
+            return BigUint::zero();
+        }
+
+        self.get_reward_checkpoint_at(
+            &self.reward_checkpoints(),
+            &self.pending_reward_schedule().get(),
+            current_block_nonce,
+        )
+        .map(|checkpoint| checkpoint.percentage_reward_per_block)
+        .unwrap_or_else(BigUint::zero)
+    }
+
+    /// Current yearly reward percentage for unlocked deposits, using the precision.
+    /// Rewards are not compounded, so this is equal to the APR.
+    #[view(getAPY)]
+    fn get_apy(&self) -> SCResult<BigUint> {
+        let periods_per_year = if self.timestamp_accrual().get() {
+            SECONDS_PER_YEAR
+        } else {
+            let round_duration_seconds = self.round_duration_seconds().get();
+            require!(round_duration_seconds > 0, "Round duration not set");
+
+            SECONDS_PER_YEAR / round_duration_seconds
+        };
+
+        Ok(self.get_current_percentage_reward_per_block() * periods_per_year.into())
+    }
+
+    /// (start_block_nonce, percentage_reward_per_block) pairs
+    #[view(getRewardSchedule)]
+    fn get_reward_schedule_view(&self) -> MultiResultVec<MultiResult2<u64, BigUint>> {
+        let mut entries: Vec<MultiResult2<u64, BigUint>> = self
+            .reward_checkpoints()
+            .iter()
+            .map(|checkpoint| {
+                (
+                    checkpoint.start_block_nonce,
+                    checkpoint.percentage_reward_per_block,
+                )
+                    .into()
+            })
+            .collect();
+        for entry in self.pending_reward_schedule().get() {
+            entries.push((entry.start_block_nonce, entry.percentage_reward_per_block).into());
+        }
+
+        entries.into()
+    }
+
+    /// total amount, amount claimable now, amount still vesting, end block
+    #[view(getVestingStatus)]
+    fn get_vesting_status(
+        &self,
+        address: ManagedAddress,
+    ) -> MultiResult4<BigUint, BigUint, BigUint, u64> {
+        if self.user_vesting(&address).is_empty() {
+            return (BigUint::zero(), BigUint::zero(), BigUint::zero(), 0).into();
+        }
+
+        let current_block_nonce = self.get_current_block_nonce();
+        let vesting_stream = self.user_vesting(&address).get();
+
+        (
+            vesting_stream.total_amount.clone(),
+            vesting_stream.get_claimable_amount(current_block_nonce),
+            vesting_stream.get_locked_amount(current_block_nonce),
+            vesting_stream.end_block_nonce,
+        )
+            .into()
+    }
+
+    /// None if there is no cap
+    #[view(getRemainingUserDepositCapacity)]
+    fn get_remaining_user_deposit_capacity(&self, address: ManagedAddress) -> Option<BigUint> {
+        let max_deposit_per_user = self.max_deposit_per_user().get();
+        if max_deposit_per_user == 0 {
+            return None;
+        }
+
+        let user_amount = self.get_user_deposit_or_default(&address).amount
+            + self.user_positions_amount(&address).get();
+        let remaining = if user_amount < max_deposit_per_user {
+            max_deposit_per_user - user_amount
+        } else {
+            BigUint::zero()
+        };
+
+        Some(remaining)
+    }
+
+    /// None if there is no cap
+    #[view(getRemainingTotalDepositCapacity)]
+    fn get_remaining_total_deposit_capacity(&self) -> Option<BigUint> {
+        let max_total_deposits = self.max_total_deposits().get();
+        if max_total_deposits == 0 {
+            return None;
+        }
+
+        let total_deposits = self.total_deposits().get();
+        let remaining = if total_deposits < max_total_deposits {
+            max_total_deposits - total_deposits
+        } else {
+            BigUint::zero()
+        };
+
+        Some(remaining)
+    }
+
+    /// (min_amount, multiplier) pairs
+    #[view(getBoostTiers)]
+    fn get_boost_tiers(&self) -> MultiResultVec<MultiResult2<BigUint, BigUint>> {
+        let tiers: Vec<MultiResult2<BigUint, BigUint>> = self
+            .boost_tiers()
+            .get()
+            .into_iter()
+            .map(|tier| (tier.min_amount, tier.multiplier).into())
+            .collect();
+
+        tiers.into()
+    }
+
+    #[view(getUserTierMultiplier)]
+    fn get_user_tier_multiplier(&self, address: ManagedAddress) -> BigUint {
+        let user_deposit = self.get_user_deposit_or_default(&address);
+
+        get_tier_multiplier(
+            &self.boost_tiers().get(),
+            &user_deposit.amount,
+            &self.get_precision(),
+        )
+    }
+
+    /// total deposited amount, number of depositors and total stablecoin rewards paid
+    #[view(getStats)]
+    fn get_stats(&self) -> MultiResult3<BigUint, usize, BigUint> {
+        let stablecoin_token_id = self.stablecoin_token_id().get();
+
+        (
+            self.total_deposits().get(),
+            self.user_deposits().len(),
+            self.total_rewards_paid(&stablecoin_token_id).get(),
+        )
+            .into()
+    }
+
+    #[view(getDepositorsCount)]
+    fn get_depositors_count(&self) -> usize {
+        self.user_deposits().len()
+    }
+
+    /// (address, deposited amount) pairs, at most `count` of them, starting from `from_index`
+    #[view(getDepositors)]
+    fn get_depositors(
+        &self,
+        from_index: usize,
+        count: usize,
+    ) -> MultiResultVec<MultiResult2<ManagedAddress, BigUint>> {
+        let depositors: Vec<MultiResult2<ManagedAddress, BigUint>> = self
+            .user_deposits()
+            .iter()
+            .skip(from_index)
+            .take(count)
+            .map(|(address, user_deposit)| (address, user_deposit.amount).into())
+            .collect();
+
+        depositors.into()
+    }
+
+    #[view(getLockOptions)]
+    fn get_lock_options(&self) -> MultiResultVec<MultiResult2<u64, BigUint>> {
+        let lock_options: Vec<MultiResult2<u64, BigUint>> = self
+            .lock_multipliers()
+            .iter()
+            .map(|(lock_blocks, multiplier)| (lock_blocks, multiplier).into())
+            .collect();
+
+        lock_options.into()
+    }
+
+    #[view(getExtraRewardTokens)]
+    fn get_extra_reward_tokens(&self) -> MultiResultVec<MultiResult2<TokenIdentifier, BigUint>> {
+        let extra_reward_tokens: Vec<MultiResult2<TokenIdentifier, BigUint>> = self
+            .extra_reward_tokens()
+            .iter()
+            .map(|token_id| {
+                let percentage = self.get_current_extra_reward_percentage(&token_id);
+                (token_id, percentage)
+            })
+            .filter(|(_, percentage)| percentage > &0)
+            .map(|(token_id, percentage)| (token_id, percentage).into())
+            .collect();
+
+        extra_reward_tokens.into()
+    }
+
+    // proxies
+
+    #[proxy]
+    fn successor_proxy(&self, sc_address: ManagedAddress) -> successor_proxy::Proxy<Self::Api>;
+
+    #[proxy]
+    fn swap_proxy(&self, sc_address: ManagedAddress) -> swap_proxy::Proxy<Self::Api>;
+
+    // callbacks
+
+    /// Sends the swapped tokens to the caller. On failure, the stablecoins
+    /// were sent back to the contract, so the caller receives them instead.
+    #[callback]
+    fn swap_rewards_callback(
+        &self,
+        caller: &ManagedAddress,
+        rewards_amount: &BigUint,
+        #[call_result] result: ManagedAsyncCallResult<MultiResultVec<ManagedBuffer>>,
+    ) {
+        match result {
+            ManagedAsyncCallResult::Ok(_) => {
+                let token_id = self.call_value().token();
+                let amount = self.call_value().dcdt_value();
+                if amount > 0 {
+                    self.send().direct(caller, &token_id, 0, &amount, &[]);
+                    self.claim_swapped_rewards_event(caller, &token_id, &amount);
+                }
+            }
+            ManagedAsyncCallResult::Err(_) => {
+                self.send_stablecoins(caller, rewards_amount);
+            }
+        }
+    }
+
+    // private
+
+    /// Takes the amount out of the user's deposit, after accumulating the rewards and handling
+    /// the early withdraw penalty. Returns the amount and the amount left after the penalty.
+    fn debit_user_deposit(
+        &self,
+        address: &ManagedAddress,
+        opt_amount: Option<BigUint>,
+        current_block_nonce: u64,
+    ) -> SCResult<(BigUint, BigUint)> {
+        let mut user_deposit = self.get_user_deposit_or_default(address);
+        let amount = opt_amount.unwrap_or_else(|| user_deposit.amount.clone());
+
+        require!(amount > 0, "Must withdraw more than 0");
+        require!(
+            amount <= user_deposit.amount,
+            "Cannot withdraw more than deposited amount"
+        );
+
+        let penalty_amount =
+            self.calculate_withdraw_penalty(&user_deposit, &amount, current_block_nonce)?;
+        self.handle_penalty(&penalty_amount)?;
+
+        self.accumulate_user_rewards(address, &mut user_deposit, current_block_nonce)?;
+
+        self.decrease_total_deposits(&amount);
+        user_deposit.amount -= &amount;
+        user_deposit.penalties_paid += &penalty_amount;
+
+        self.update_user_deposit_or_remove_if_cleared(address.clone(), user_deposit);
+
+        let amount_after_penalty = &amount - &penalty_amount;
+
+        Ok((amount, amount_after_penalty))
+    }
+
+    /// adds to the user's pending withdrawal and restarts the cooldown
+    fn add_pending_withdrawal(
+        &self,
+        address: &ManagedAddress,
+        amount: BigUint,
+        current_block_nonce: u64,
+    ) {
+        let mut pending_amount = amount;
+        if !self.pending_withdrawal(address).is_empty() {
+            pending_amount += self.pending_withdrawal(address).get().amount;
+        } else {
+            self.pending_withdrawals_count().update(|count| *count += 1);
+        }
+
+        let cooldown_blocks = self.withdraw_cooldown_blocks().get();
+        self.pending_withdrawal(address).set(&PendingWithdrawal {
+            amount: pending_amount,
+            unlock_block_nonce: current_block_nonce + cooldown_blocks,
+        });
+    }
+
+    fn send_or_add_pending_withdrawal(
+        &self,
+        address: &ManagedAddress,
+        amount: BigUint,
+        current_block_nonce: u64,
+    ) {
+        if self.withdraw_cooldown_blocks().get() > 0 {
+            self.add_pending_withdrawal(address, amount, current_block_nonce);
+        } else {
+            self.send_stablecoins(address, &amount);
+        }
+    }
+
+    fn claim_rewards_common(
+        &self,
+        address: ManagedAddress,
+        opt_amount: Option<BigUint>,
+    ) -> SCResult<()> {
+        self.require_not_paused()?;
+        self.require_not_blacklisted(&address)?;
+
+        let current_block_nonce = self.get_current_block_nonce();
+        let mut user_deposit = self.get_user_deposit_or_default(&address);
+
+        self.accumulate_user_rewards(&address, &mut user_deposit, current_block_nonce)?;
+
+        let amount = match opt_amount {
+            Some(amount) => {
+                require!(
+                    amount > 0 && amount <= user_deposit.cummulated_rewards,
+                    "Invalid claim amount"
+                );
+                amount
+            }
+            None => user_deposit.cummulated_rewards.clone(),
+        };
+        self.send_stablecoin_rewards(&address, &mut user_deposit, &amount, current_block_nonce)?;
+
+        self.update_user_deposit_or_remove_if_cleared(address, user_deposit);
+
+        Ok(())
+    }
+
+    /// amount has to be at most the user's accumulated rewards
+    fn send_stablecoin_rewards(
+        &self,
+        to: &ManagedAddress,
+        user_deposit: &mut UserDeposit<Self::Api>,
+        amount: &BigUint,
+        current_block_nonce: u64,
+    ) -> SCResult<()> {
+        let rewards_amount = self.mint_rewards(amount)?;
+        self.send_or_vest_rewards(to, &rewards_amount, current_block_nonce);
+        self.claim_rewards_event(to, current_block_nonce, &rewards_amount);
+        self.record_user_reward_history(
+            to,
+            RewardHistoryAction::Claimed,
+            &rewards_amount,
+            current_block_nonce,
+        );
+
+        // anything that couldn't be paid stays recorded, but can't be claimed yet
+        user_deposit.cummulated_rewards -= rewards_amount;
+
+        Ok(())
+    }
+
+    fn try_set_referrer(&self, address: &ManagedAddress, referrer: ManagedAddress) -> SCResult<()> {
+        if !self.user_referrer(address).is_empty() || self.user_deposits().contains_key(address) {
+            return Ok(());
+        }
+
+        require!(
+            &referrer != address && !referrer.is_zero(),
+            "Invalid referrer"
+        );
+
+        self.user_referrer(address).set(&referrer);
+
+        Ok(())
+    }
+
+    fn deposit_common(
+        &self,
+        beneficiary: ManagedAddress,
+        token_id: TokenIdentifier,
+        amount: BigUint,
+        opt_lock_blocks: Option<u64>,
+    ) -> SCResult<()> {
+        self.require_not_paused()?;
+        self.require_whitelisted(&beneficiary)?;
+        self.require_not_blacklisted(&beneficiary)?;
+        require!(
+            token_id == self.stablecoin_token_id().get(),
+            "Wrong payment token"
+        );
+        require!(amount > 0, "Must deposit more than 0");
+        require!(
+            amount >= self.min_deposit_amount().get(),
+            "Deposit amount too small"
+        );
+
+        let current_block_nonce = self.get_current_block_nonce();
+        let amount = self.take_deposit_fee(&beneficiary, amount, current_block_nonce);
+        let mut user_deposit = self.get_user_deposit_or_default(&beneficiary);
+
+        self.accumulate_user_rewards(&beneficiary, &mut user_deposit, current_block_nonce)?;
+        user_deposit.amount += &amount;
+        let new_user_amount =
+            &user_deposit.amount + &self.user_positions_amount(&beneficiary).get();
+        self.require_under_deposit_caps(&new_user_amount, &amount)?;
+        self.increase_total_deposits(&amount);
+
+        if let Some(lock_blocks) = opt_lock_blocks {
+            let multiplier = self.get_lock_multiplier(lock_blocks)?;
+            let unlock_block_nonce = current_block_nonce + lock_blocks;
+            require!(
+                unlock_block_nonce >= user_deposit.unlock_block_nonce,
+                "Cannot shorten an existing lock"
+            );
+
+            user_deposit.lock(unlock_block_nonce, multiplier);
+        }
+
+        self.deposit_event(&beneficiary, current_block_nonce, &amount);
+        self.update_user_deposit_or_remove_if_cleared(beneficiary, user_deposit);
+
+        Ok(())
+    }
+
+    /// sums up the payments of a multi-transfer, which all have to be in the stablecoin
+    fn get_stablecoin_payments_total(&self) -> SCResult<(TokenIdentifier, BigUint)> {
+        let transfers = self.call_value().all_dcdt_transfers();
+        require!(!transfers.is_empty(), "Wrong payment token");
+
+        let stablecoin_token_id = self.stablecoin_token_id().get();
+        let mut total_amount = BigUint::zero();
+        for transfer in transfers {
+            require!(
+                transfer.token_identifier == stablecoin_token_id && transfer.token_nonce == 0,
+                "Wrong payment token"
+            );
+            total_amount += transfer.amount;
+        }
+
+        Ok((stablecoin_token_id, total_amount))
+    }
+
+    fn get_lock_multiplier(&self, lock_blocks: u64) -> SCResult<BigUint> {
+        match self.lock_multipliers().get(&lock_blocks) {
+            Some(multiplier) => Ok(multiplier),
+            None => sc_error!("Invalid lock duration"),
+        }
+    }
+
+    /// sends the fee to the treasury and returns the amount left to deposit
+    fn take_deposit_fee(
+        &self,
+        payer: &ManagedAddress,
+        amount: BigUint,
+        current_block_nonce: u64,
+    ) -> BigUint {
+        let deposit_fee_percentage = self.deposit_fee_percentage().get();
+        if deposit_fee_percentage == 0 {
+            return amount;
+        }
+
+        let fee_amount = (&amount * &deposit_fee_percentage) / self.get_precision();
+        if fee_amount > 0 {
+            let treasury_address = self.treasury_address().get();
+            self.send_stablecoins(&treasury_address, &fee_amount);
+            self.deposit_fee_event(payer, current_block_nonce, &fee_amount);
+        }
+
+        amount - fee_amount
+    }
+
+    /// positions opened before the depositor was recorded don't count against any user
+    fn remove_position_from_depositor(&self, nonce: u64, amount: &BigUint) {
+        let depositor_mapper = self.position_depositor(nonce);
+        if depositor_mapper.is_empty() {
+            return;
+        }
+
+        self.user_positions_amount(&depositor_mapper.get())
+            .update(|positions_amount| {
+                if *positions_amount > *amount {
+                    *positions_amount -= amount;
+                } else {
+                    *positions_amount = BigUint::zero();
+                }
+            });
+        depositor_mapper.clear();
+    }
+
+    fn require_under_deposit_caps(
+        &self,
+        new_user_amount: &BigUint,
+        deposit_amount: &BigUint,
+    ) -> SCResult<()> {
+        let max_deposit_per_user = self.max_deposit_per_user().get();
+        require!(
+            max_deposit_per_user == 0 || new_user_amount <= &max_deposit_per_user,
+            "Over max deposit per user"
+        );
+
+        let max_total_deposits = self.max_total_deposits().get();
+        require!(
+            max_total_deposits == 0
+                || &(&self.total_deposits().get() + deposit_amount) <= &max_total_deposits,
+            "Over max total deposits"
+        );
+
+        Ok(())
+    }
+
+    fn require_local_mint_role_set(&self, token_id: &TokenIdentifier) -> SCResult<()> {
+        let roles = self.blockchain().get_dcdt_local_roles(token_id);
+        require!(
+            roles.has_role(&DcdtLocalRole::Mint),
+            "Local Mint role not set"
+        );
+
+        Ok(())
+    }
+
+    fn require_local_burn_role_set(&self) -> SCResult<()> {
+        let token_id = self.stablecoin_token_id().get();
+        let roles = self.blockchain().get_dcdt_local_roles(&token_id);
+        require!(
+            roles.has_role(&DcdtLocalRole::Burn),
+            "Local Burn role not set"
+        );
+
+        Ok(())
+    }
+
+    fn require_valid_percentage(&self, percentage: &BigUint) -> SCResult<()> {
+        require!(
+            *percentage > 0 && *percentage <= self.get_precision(),
+            "Invalid percentage"
+        );
+
+        Ok(())
+    }
+
+    /// moves the scheduled entries that already started into the checkpoints
+    fn apply_started_reward_schedule_entries(&self, current_block_nonce: u64) {
+        let mut pending_entries = self.pending_reward_schedule().get();
+        let started_count = pending_entries
+            .iter()
+            .take_while(|entry| entry.start_block_nonce <= current_block_nonce)
+            .count();
+        if started_count == 0 {
+            return;
+        }
+
+        for entry in pending_entries.drain(..started_count) {
+            self.push_reward_checkpoint(self.reward_checkpoints(), &entry);
+        }
+        self.pending_reward_schedule().set(&pending_entries);
+    }
+
+    /// Several checkpoints can start at the same block, in which case the last one is used
+    fn push_reward_checkpoint(
+        &self,
+        mut checkpoints: VecMapper<RewardCheckpoint<Self::Api>>,
+        entry: &RewardScheduleEntry<Self::Api>,
+    ) {
+        let checkpoint = if checkpoints.is_empty() {
+            RewardCheckpoint::first(entry)
+        } else {
+            checkpoints.get(checkpoints.len()).next(entry)
+        };
+
+        checkpoints.push(&checkpoint);
+    }
+
+    /// The last checkpoint starting at or before the block, found with a binary search.
+    /// Pending entries that already started at that block are applied on top of the last checkpoint.
+    fn get_reward_checkpoint_at(
+        &self,
+        checkpoints: &VecMapper<RewardCheckpoint<Self::Api>>,
+        pending_entries: &[RewardScheduleEntry<Self::Api>],
+        block_nonce: u64,
+    ) -> Option<RewardCheckpoint<Self::Api>> {
+        let len = checkpoints.len();
+        if len == 0 || checkpoints.get(1).start_block_nonce > block_nonce {
+            return None;
+        }
+
+        let last_checkpoint = checkpoints.get(len);
+        if last_checkpoint.start_block_nonce <= block_nonce {
+            let mut checkpoint = last_checkpoint;
+            for entry in pending_entries {
+                if entry.start_block_nonce > block_nonce {
+                    break;
+                }
+                checkpoint = checkpoint.next(entry);
+            }
+
+            return Some(checkpoint);
+        }
+
+        // VecMapper indexes start at 1. start(low) <= block_nonce < start(high)
+        let mut low = 1;
+        let mut high = len;
+        while high - low > 1 {
+            let mid = (low + high) / 2;
+            if checkpoints.get(mid).start_block_nonce <= block_nonce {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        Some(checkpoints.get(low))
+    }
+
+    /// nothing accrues after `rewards_end_block_nonce`, 0 meaning no end
+    fn get_reward_per_share_snapshot(
+        &self,
+        checkpoints: &VecMapper<RewardCheckpoint<Self::Api>>,
+        pending_entries: &[RewardScheduleEntry<Self::Api>],
+        user_deposit: &UserDeposit<Self::Api>,
+        current_block_nonce: u64,
+        rewards_end_block_nonce: u64,
+    ) -> RewardPerShareSnapshot<Self::Api> {
+        let lock_end_block_nonce = user_deposit.get_lock_end_block_nonce(current_block_nonce);
+        let get_reward_per_share = |block_nonce: u64| {
+            // nothing accrues after the end block
+            let block_nonce = if rewards_end_block_nonce > 0 {
+                core::cmp::min(block_nonce, rewards_end_block_nonce)
+            } else {
+                block_nonce
+            };
+
+            self.get_reward_checkpoint_at(checkpoints, pending_entries, block_nonce)
+                .map(|checkpoint| checkpoint.get_reward_per_share(block_nonce))
+                .unwrap_or_else(BigUint::zero)
+        };
+
+        RewardPerShareSnapshot {
+            at_last_claim: get_reward_per_share(user_deposit.last_claim_block_nonce),
+            at_lock_end: get_reward_per_share(lock_end_block_nonce),
+            at_current: get_reward_per_share(current_block_nonce),
+        }
+    }
+
+    fn get_current_extra_reward_percentage(&self, token_id: &TokenIdentifier) -> BigUint {
+        let checkpoints = self.extra_reward_checkpoints(token_id);
+        if checkpoints.is_empty() {
+            return BigUint::zero();
+        }
+
+        checkpoints
+            .get(checkpoints.len())
+            .percentage_reward_per_block
+    }
+
+    fn try_mint_stablecoins(&self, amount: &BigUint) -> SCResult<()> {
+        let token_id = self.stablecoin_token_id().get();
+        self.try_mint_tokens(&token_id, amount)
+    }
+
+    /// for the governance and extra reward tokens
+    fn mint_token_rewards(&self, token_id: &TokenIdentifier, amount: &BigUint) -> SCResult<()> {
+        self.try_mint_tokens(token_id, amount)?;
+        self.total_rewards_paid(token_id)
+            .update(|total| *total += amount);
+
+        Ok(())
+    }
+
+    fn try_mint_tokens(&self, token_id: &TokenIdentifier, amount: &BigUint) -> SCResult<()> {
+        self.require_local_mint_role_set(token_id)?;
+        self.send().dcdt_local_mint(token_id, 0, amount);
+
+        Ok(())
+    }
+
+    fn calculate_withdraw_penalty(
+        &self,
+        user_deposit: &UserDeposit<Self::Api>,
+        amount: &BigUint,
+        current_block_nonce: u64,
+    ) -> SCResult<BigUint> {
+        if !user_deposit.is_locked(current_block_nonce) {
+            return Ok(BigUint::zero());
+        }
+
+        require!(
+            self.early_withdraw_penalty_percentage().get() > 0,
+            "Deposit is still locked"
+        );
+
+        Ok(self.calculate_penalty_amount(amount))
+    }
+
+    fn calculate_penalty_amount(&self, amount: &BigUint) -> BigUint {
+        let penalty_percentage = self.early_withdraw_penalty_percentage().get();
+
+        (amount * &penalty_percentage) / self.get_precision()
+    }
+
+    fn handle_penalty(&self, penalty_amount: &BigUint) -> SCResult<()> {
+        if penalty_amount == &0 {
+            return Ok(());
+        }
+
+        if self.treasury_address().is_empty() {
+            self.require_local_burn_role_set()?;
+
+            let token_id = self.stablecoin_token_id().get();
+            self.send().dcdt_local_burn(&token_id, 0, penalty_amount);
+        } else {
+            let treasury_address = self.treasury_address().get();
+            self.send_stablecoins(&treasury_address, penalty_amount);
+        }
+
+        Ok(())
+    }
+
+    /// Mints at most the amount left until the emission cap is reached, and returns the minted amount.
+    /// In reserve mode, the rewards are taken from the reserve instead, limited to what is left in it.
+    /// The per claim and per epoch limits apply as well.
+    fn mint_rewards(&self, amount: &BigUint) -> SCResult<BigUint> {
+        let mut mint_amount = match self.get_remaining_rewards_until_cap() {
+            Some(remaining) if &remaining < amount => remaining,
+            _ => amount.clone(),
+        };
+
+        let max_rewards_per_claim = self.max_rewards_per_claim().get();
+        if max_rewards_per_claim > 0 && max_rewards_per_claim < mint_amount {
+            mint_amount = max_rewards_per_claim;
+        }
+
+        let current_block_nonce = self.get_current_block_nonce();
+        if let Some(remaining) = self.get_remaining_epoch_rewards(current_block_nonce) {
+            if remaining < mint_amount {
+                mint_amount = remaining;
+            }
+        }
+
+        let reserve_mode = self.reward_reserve_mode().get();
+        if reserve_mode {
+            let reward_reserve = self.reward_reserve().get();
+            if reward_reserve < mint_amount {
+                mint_amount = reward_reserve;
+            }
+        }
+
+        if mint_amount > 0 {
+            if reserve_mode {
+                self.reward_reserve()
+                    .update(|reserve| *reserve -= &mint_amount);
+            } else {
+                self.try_mint_stablecoins(&mint_amount)?;
+            }
+
+            self.total_rewards_minted()
+                .update(|total| *total += &mint_amount);
+            if self.is_reward_cap_reached() {
+                self.reward_cap_reached_block_nonce()
+                    .set(&current_block_nonce);
+            }
+            self.add_epoch_rewards_paid(&mint_amount, current_block_nonce);
+            self.total_rewards_paid(&self.stablecoin_token_id().get())
+                .update(|total| *total += &mint_amount);
+        }
+
+        Ok(mint_amount)
+    }
+
+    /// None if there is no cap
+    fn get_remaining_rewards_until_cap(&self) -> Option<BigUint> {
+        let max_total_rewards = self.max_total_rewards().get();
+        if max_total_rewards == 0 {
+            return None;
+        }
+
+        let total_rewards_minted = self.total_rewards_minted().get();
+        if total_rewards_minted >= max_total_rewards {
+            Some(BigUint::zero())
+        } else {
+            Some(max_total_rewards - total_rewards_minted)
+        }
+    }
+
+    fn increase_total_deposits(&self, amount: &BigUint) {
+        self.total_deposits().update(|total| *total += amount);
+        self.update_utilization_rate();
+    }
+
+    fn decrease_total_deposits(&self, amount: &BigUint) {
+        self.total_deposits().update(|total| *total -= amount);
+        self.update_utilization_rate();
+    }
+
+    /// With the utilization curve, a new checkpoint starts each time the effective percentage changes.
+    /// Rewards up to the current block are still calculated with the previous percentage.
+    fn update_utilization_rate(&self) {
+        if self.utilization_curve().is_empty() {
+            return;
+        }
+
+        let effective_percentage = self.utilization_curve().get().get_effective_percentage(
+            &self.base_percentage_reward_per_block().get(),
+            &self.total_deposits().get(),
+            &self.get_precision(),
+        );
+        let checkpoints = self.reward_checkpoints();
+        if !checkpoints.is_empty()
+            && checkpoints
+                .get(checkpoints.len())
+                .percentage_reward_per_block
+                == effective_percentage
+        {
+            return;
+        }
+
+        self.push_reward_checkpoint(
+            checkpoints,
+            &RewardScheduleEntry {
+                start_block_nonce: self.get_current_block_nonce(),
+                percentage_reward_per_block: effective_percentage,
+            },
+        );
+    }
+
+    /// None if there is no epoch limit
+    fn get_remaining_epoch_rewards(&self, current_block_nonce: u64) -> Option<BigUint> {
+        let epoch_duration_blocks = self.epoch_duration_blocks().get();
+        if epoch_duration_blocks == 0 {
+            return None;
+        }
+
+        let max_rewards_per_epoch = self.max_rewards_per_epoch().get();
+        let epoch = current_block_nonce / epoch_duration_blocks;
+        if epoch != self.current_epoch().get() {
+            return Some(max_rewards_per_epoch);
+        }
+
+        let epoch_rewards_paid = self.current_epoch_rewards_paid().get();
+        if epoch_rewards_paid >= max_rewards_per_epoch {
+            Some(BigUint::zero())
+        } else {
+            Some(max_rewards_per_epoch - epoch_rewards_paid)
+        }
+    }
+
+    fn add_epoch_rewards_paid(&self, amount: &BigUint, current_block_nonce: u64) {
+        let epoch_duration_blocks = self.epoch_duration_blocks().get();
+        if epoch_duration_blocks == 0 {
+            return;
+        }
+
+        let epoch = current_block_nonce / epoch_duration_blocks;
+        if epoch != self.current_epoch().get() {
+            self.current_epoch().set(&epoch);
+            self.current_epoch_rewards_paid().set(amount);
+        } else {
+            self.current_epoch_rewards_paid()
+                .update(|total| *total += amount);
+        }
+    }
+
+    /// keeps only the current percentage, starting at `start_block_nonce`
+    fn restart_reward_checkpoints(
+        &self,
+        mut checkpoints: VecMapper<RewardCheckpoint<Self::Api>>,
+        start_block_nonce: u64,
+    ) {
+        if checkpoints.is_empty() {
+            return;
+        }
+
+        let percentage_reward_per_block = checkpoints
+            .get(checkpoints.len())
+            .percentage_reward_per_block;
+        checkpoints.clear();
+        checkpoints.push(&RewardCheckpoint::first(&RewardScheduleEntry {
+            start_block_nonce,
+            percentage_reward_per_block,
+        }));
+    }
+
+    fn have_rewards_ended(&self, current_block_nonce: u64) -> bool {
+        let rewards_end_block_nonce = self.rewards_end_block_nonce().get();
+        rewards_end_block_nonce > 0 && current_block_nonce >= rewards_end_block_nonce
+    }
+
+    fn is_reward_cap_reached(&self) -> bool {
+        matches!(self.get_remaining_rewards_until_cap(), Some(remaining) if remaining == 0)
+    }
+
+    /// Stablecoin rewards stop accruing at the end block, or at the block the emission cap
+    /// was reached, whichever comes first. What accrued until then stays recorded.
+    fn get_stablecoin_rewards_end_block_nonce(&self) -> u64 {
+        let rewards_end_block_nonce = self.rewards_end_block_nonce().get();
+        let reward_cap_reached_block_nonce = self.reward_cap_reached_block_nonce().get();
+        if rewards_end_block_nonce == 0 {
+            return reward_cap_reached_block_nonce;
+        }
+        if reward_cap_reached_block_nonce == 0 {
+            return rewards_end_block_nonce;
+        }
+
+        core::cmp::min(rewards_end_block_nonce, reward_cap_reached_block_nonce)
+    }
+
+    /// Only reads the global accumulators, so the cost doesn't depend on the number of users
+    fn accumulate_deposit_rewards(
+        &self,
+        user_deposit: &mut UserDeposit<Self::Api>,
+        current_block_nonce: u64,
+    ) {
+        let rewards_end_block_nonce = self.rewards_end_block_nonce().get();
+        let reward_per_share = self.get_reward_per_share_snapshot(
+            &self.reward_checkpoints(),
+            &self.pending_reward_schedule().get(),
+            user_deposit,
+            current_block_nonce,
+            self.get_stablecoin_rewards_end_block_nonce(),
+        );
+
+        let extra_reward_per_share: Vec<(TokenIdentifier, RewardPerShareSnapshot<Self::Api>)> =
+            self.extra_reward_tokens()
+                .iter()
+                .map(|token_id| {
+                    let snapshot = self.get_reward_per_share_snapshot(
+                        &self.extra_reward_checkpoints(&token_id),
+                        &[],
+                        user_deposit,
+                        current_block_nonce,
+                        rewards_end_block_nonce,
+                    );
+                    (token_id, snapshot)
+                })
+                .collect();
+
+        let governance_reward_per_share = self.get_reward_per_share_snapshot(
+            &self.governance_reward_checkpoints(),
+            &[],
+            user_deposit,
+            current_block_nonce,
+            rewards_end_block_nonce,
+        );
+
+        let precision = self.get_precision();
+        let tier_multiplier =
+            get_tier_multiplier(&self.boost_tiers().get(), &user_deposit.amount, &precision);
+        user_deposit.accummulate_rewards(
+            current_block_nonce,
+            &precision,
+            &tier_multiplier,
+            &reward_per_share,
+            &governance_reward_per_share,
+            &extra_reward_per_share,
+        );
+    }
+
+    /// Liquidity deposits only earn stablecoin rewards, with the liquidity percentage
+    fn accumulate_liquidity_rewards(
+        &self,
+        liquidity_deposit: &mut UserDeposit<Self::Api>,
+        current_block_nonce: u64,
+    ) {
+        let reward_per_share = self.get_reward_per_share_snapshot(
+            &self.liquidity_reward_checkpoints(),
+            &[],
+            liquidity_deposit,
+            current_block_nonce,
+            self.get_stablecoin_rewards_end_block_nonce(),
+        );
+
+        let precision = self.get_precision();
+        liquidity_deposit.accummulate_rewards(
+            current_block_nonce,
+            &precision,
+            &precision,
+            &reward_per_share,
+            &RewardPerShareSnapshot::zero(),
+            &[],
+        );
+    }
+
+    fn send_liquidity_rewards(
+        &self,
+        to: &ManagedAddress,
+        sft_nonce: u64,
+        liquidity_deposit: &mut UserDeposit<Self::Api>,
+        current_block_nonce: u64,
+    ) -> SCResult<()> {
+        if liquidity_deposit.cummulated_rewards == 0 {
+            return Ok(());
+        }
+
+        let rewards_amount = self.mint_rewards(&liquidity_deposit.cummulated_rewards)?;
+        self.send_or_vest_rewards(to, &rewards_amount, current_block_nonce);
+        self.claim_liquidity_rewards_event(to, sft_nonce, &rewards_amount);
+
+        // anything that couldn't be paid stays recorded, but can't be claimed yet
+        liquidity_deposit.cummulated_rewards -= rewards_amount;
+
+        Ok(())
+    }
+
+    fn update_liquidity_deposit_or_remove_if_cleared(
+        &self,
+        address: ManagedAddress,
+        sft_nonce: u64,
+        liquidity_deposit: UserDeposit<Self::Api>,
+    ) {
+        if liquidity_deposit.amount > 0 || liquidity_deposit.cummulated_rewards > 0 {
+            self.liquidity_deposits(sft_nonce)
+                .insert(address, liquidity_deposit);
+        } else {
+            self.liquidity_deposits(sft_nonce).remove(&address);
+            if self.liquidity_deposits(sft_nonce).is_empty() {
+                self.liquidity_sft_nonces().remove(&sft_nonce);
+            }
+        }
+    }
+
+    fn accumulate_user_rewards(
+        &self,
+        address: &ManagedAddress,
+        user_deposit: &mut UserDeposit<Self::Api>,
+        current_block_nonce: u64,
+    ) -> SCResult<()> {
+        let rewards_before = user_deposit.cummulated_rewards.clone();
+        self.accumulate_deposit_rewards(user_deposit, current_block_nonce);
+
+        let new_rewards = &user_deposit.cummulated_rewards - &rewards_before;
+        self.accrue_referral_rewards(address, &new_rewards);
+        self.record_user_reward_history(
+            address,
+            RewardHistoryAction::Accrued,
+            &new_rewards,
+            current_block_nonce,
+        );
+
+        if self.auto_restake(address).get()
+            && !self.is_blacklisted(address)
+            && user_deposit.amount > 0
+            && user_deposit.cummulated_rewards > 0
+        {
+            let _ = self.compound_user_rewards(address, user_deposit, current_block_nonce)?;
+        }
+
+        Ok(())
+    }
+
+    /// records the entry with the stablecoin reward percentage currently in effect
+    fn record_user_reward_history(
+        &self,
+        address: &ManagedAddress,
+        action: RewardHistoryAction,
+        amount: &BigUint,
+        current_block_nonce: u64,
+    ) {
+        let percentage_reward_per_block = self.get_current_percentage_reward_per_block();
+        self.record_reward_history(
+            address,
+            action,
+            &percentage_reward_per_block,
+            amount,
+            current_block_nonce,
+        );
+    }
+
+    /// mints the pending rewards into the deposited amount and returns the compounded amount
+    fn compound_user_rewards(
+        &self,
+        address: &ManagedAddress,
+        user_deposit: &mut UserDeposit<Self::Api>,
+        current_block_nonce: u64,
+    ) -> SCResult<BigUint> {
+        let rewards_amount = self.mint_rewards(&user_deposit.cummulated_rewards)?;
+        if rewards_amount > 0 {
+            self.compound_event(address, current_block_nonce, &rewards_amount);
+            self.record_user_reward_history(
+                address,
+                RewardHistoryAction::Compounded,
+                &rewards_amount,
+                current_block_nonce,
+            );
+
+            self.increase_total_deposits(&rewards_amount);
+            user_deposit.cummulated_rewards -= &rewards_amount;
+            user_deposit.amount += &rewards_amount;
+        }
+
+        Ok(rewards_amount)
+    }
+
+    /// the referrer earns a percentage of the stablecoin rewards of the users they referred
+    fn accrue_referral_rewards(&self, address: &ManagedAddress, new_rewards: &BigUint) {
+        if new_rewards == &0 || self.user_referrer(address).is_empty() {
+            return;
+        }
+
+        let referral_reward_percentage = self.referral_reward_percentage().get();
+        let referral_rewards = (new_rewards * &referral_reward_percentage) / self.get_precision();
+        if referral_rewards > 0 {
+            let referrer = self.user_referrer(address).get();
+            self.referral_rewards(&referrer)
+                .update(|rewards| *rewards += referral_rewards);
+        }
+    }
+
+    fn send_or_vest_rewards(
+        &self,
+        to: &ManagedAddress,
+        amount: &BigUint,
+        current_block_nonce: u64,
+    ) {
+        if self.vesting_duration_blocks().get() == 0 {
+            self.send_stablecoins(to, amount);
+        } else {
+            self.start_vesting(to, amount, current_block_nonce);
+        }
+    }
+
+    /// Rewards already minted are added to the caller's stream. If a stream exists,
+    /// its matured part is paid out and the rest vests together with the new rewards,
+    /// until the amount-weighted average of both end blocks.
+    fn start_vesting(&self, address: &ManagedAddress, amount: &BigUint, current_block_nonce: u64) {
+        if amount == &0 {
+            return;
+        }
+
+        let mut still_vesting = amount.clone();
+        let mut end_block_nonce = current_block_nonce + self.vesting_duration_blocks().get();
+        if !self.user_vesting(address).is_empty() {
+            let mut vesting_stream = self.user_vesting(address).get();
+            let claimable_amount = vesting_stream.get_claimable_amount(current_block_nonce);
+            if claimable_amount > 0 {
+                self.release_vested(
+                    address,
+                    &mut vesting_stream,
+                    &claimable_amount,
+                    current_block_nonce,
+                );
+            }
+
+            if vesting_stream.end_block_nonce > current_block_nonce {
+                let locked_amount = vesting_stream.get_locked_amount(current_block_nonce);
+                end_block_nonce = self.get_merged_vesting_end_block_nonce(
+                    &locked_amount,
+                    vesting_stream.end_block_nonce,
+                    amount,
+                    end_block_nonce,
+                );
+                still_vesting += locked_amount;
+            }
+        }
+
+        if self.user_vesting(address).is_empty() {
+            self.vesting_streams_count().update(|count| *count += 1);
+        }
+        self.user_vesting(address).set(&VestingStream::new(
+            still_vesting,
+            current_block_nonce,
+            end_block_nonce - current_block_nonce,
+        ));
+        self.vesting_started_event(address, current_block_nonce, amount);
+    }
+
+    /// never before the running stream's end, so merging can't shorten the vesting of either part
+    fn get_merged_vesting_end_block_nonce(
+        &self,
+        locked_amount: &BigUint,
+        old_end_block_nonce: u64,
+        amount: &BigUint,
+        new_end_block_nonce: u64,
+    ) -> u64 {
+        if new_end_block_nonce <= old_end_block_nonce {
+            return old_end_block_nonce;
+        }
+
+        let max_extra_blocks = new_end_block_nonce - old_end_block_nonce;
+        let extra_blocks = (BigUint::from(max_extra_blocks) * amount) / (locked_amount + amount);
+
+        old_end_block_nonce + extra_blocks.to_u64().unwrap_or(max_extra_blocks)
+    }
+
+    fn release_vested(
+        &self,
+        address: &ManagedAddress,
+        vesting_stream: &mut VestingStream<Self::Api>,
+        amount: &BigUint,
+        current_block_nonce: u64,
+    ) {
+        self.send_stablecoins(address, amount);
+        self.claim_vested_event(address, current_block_nonce, amount);
+
+        vesting_stream.claimed_amount += amount;
+        if vesting_stream.is_fully_claimed() {
+            self.user_vesting(address).clear();
+            self.vesting_streams_count().update(|count| *count -= 1);
+        } else {
+            self.user_vesting(address).set(vesting_stream);
+        }
+    }
+
+    fn pay_position_rewards(
+        &self,
+        to: &ManagedAddress,
+        nonce: u64,
+        position: &mut UserDeposit<Self::Api>,
+        current_block_nonce: u64,
+    ) -> SCResult<()> {
+        self.accumulate_deposit_rewards(position, current_block_nonce);
+
+        let rewards_amount = self.mint_rewards(&position.cummulated_rewards)?;
+        self.send_or_vest_rewards(to, &rewards_amount, current_block_nonce);
+        self.claim_position_rewards_event(to, nonce, &rewards_amount);
+
+        // anything that couldn't be paid stays recorded, but can't be claimed yet
+        position.cummulated_rewards -= rewards_amount;
+        self.send_extra_rewards(to, position)?;
+        self.send_governance_rewards(to, position)
+    }
+
+    fn send_stablecoins(&self, to: &ManagedAddress, amount: &BigUint) {
+        if amount > &0 {
+            let token_id = self.stablecoin_token_id().get();
+            self.send().direct(to, &token_id, 0, amount, &[]);
+        }
+    }
+
+    fn send_governance_rewards(
+        &self,
+        to: &ManagedAddress,
+        user_deposit: &mut UserDeposit<Self::Api>,
+    ) -> SCResult<()> {
+        let rewards_amount =
+            core::mem::replace(&mut user_deposit.governance_rewards, BigUint::zero());
+        if rewards_amount == 0 {
+            return Ok(());
+        }
+
+        let token_id = self.governance_token_id().get();
+        self.mint_token_rewards(&token_id, &rewards_amount)?;
+        self.send().direct(to, &token_id, 0, &rewards_amount, &[]);
+        self.claim_governance_rewards_event(to, self.get_current_block_nonce(), &rewards_amount);
+
+        Ok(())
+    }
+
+    fn send_extra_rewards(
+        &self,
+        to: &ManagedAddress,
+        user_deposit: &mut UserDeposit<Self::Api>,
+    ) -> SCResult<()> {
+        for extra_reward in user_deposit.extra_rewards.drain(..) {
+            self.mint_token_rewards(&extra_reward.token_id, &extra_reward.amount)?;
+            self.send()
+                .direct(to, &extra_reward.token_id, 0, &extra_reward.amount, &[]);
+            self.claim_token_rewards_event(to, &extra_reward.token_id, &extra_reward.amount);
+        }
+
+        Ok(())
+    }
+
+    fn get_user_deposit_or_default(&self, address: &ManagedAddress) -> UserDeposit<Self::Api> {
+        self.user_deposits()
+            .get(address)
+            .unwrap_or_else(|| self.new_user_deposit())
+    }
+
+    fn new_user_deposit(&self) -> UserDeposit<Self::Api> {
+        UserDeposit::new(self.get_precision())
+    }
+
+    /// BASE_PRECISION for the contracts deployed before the precision was configurable
+    #[view(getPrecision)]
+    fn get_precision(&self) -> BigUint {
+        let precision = self.precision().get();
+        if precision == 0 {
+            BigUint::from(BASE_PRECISION)
+        } else {
+            BigUint::from(precision)
+        }
+    }
+
+    /// records the balance checkpoint as well, see balanceAt.
+    /// Every deposit update goes through here, so userDeposits never holds an empty entry
+    /// and needs no separate pruning
+    fn update_user_deposit_or_remove_if_cleared(
+        &self,
+        address: ManagedAddress,
+        user_deposit: UserDeposit<Self::Api>,
+    ) {
+        self.record_balance_checkpoint(
+            &address,
+            &user_deposit.amount,
+            self.get_current_block_nonce(),
+        );
+
+        if user_deposit.is_empty() {
+            self.user_deposits().remove(&address);
+        } else {
+            self.user_deposits().insert(address, user_deposit);
+        }
+    }
+
+    // storage
+
+    /// 0 for the contracts deployed before the precision was configurable, see get_precision
+    #[storage_mapper("precision")]
+    fn precision(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("stablecoinTokenId")]
+    fn stablecoin_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
+
+    #[storage_mapper("rewardCheckpoints")]
+    fn reward_checkpoints(&self) -> VecMapper<RewardCheckpoint<Self::Api>>;
+
+    /// entries starting after the last checkpoint, sorted by start block
+    #[storage_mapper("pendingRewardSchedule")]
+    fn pending_reward_schedule(&self) -> SingleValueMapper<Vec<RewardScheduleEntry<Self::Api>>>;
+
+    #[view(getUtilizationCurve)]
+    #[storage_mapper("utilizationCurve")]
+    fn utilization_curve(&self) -> SingleValueMapper<UtilizationCurve<Self::Api>>;
+
+    /// the percentage before applying the utilization curve, only set while the curve is used
+    #[view(getBasePercentageRewardPerBlock)]
+    #[storage_mapper("basePercentageRewardPerBlock")]
+    fn base_percentage_reward_per_block(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("boostTiers")]
+    fn boost_tiers(&self) -> SingleValueMapper<Vec<BoostTier<Self::Api>>>;
+
+    #[storage_mapper("lockMultipliers")]
+    fn lock_multipliers(&self) -> MapMapper<u64, BigUint>;
+
+    #[view(getMaxTotalRewards)]
+    #[storage_mapper("maxTotalRewards")]
+    fn max_total_rewards(&self) -> SingleValueMapper<BigUint>;
+
+    /// 0 if there is no end block
+    #[view(getRewardsEndBlockNonce)]
+    #[storage_mapper("rewardsEndBlockNonce")]
+    fn rewards_end_block_nonce(&self) -> SingleValueMapper<u64>;
+
+    /// 0 means no limit
+    #[view(getMaxRewardsPerClaim)]
+    #[storage_mapper("maxRewardsPerClaim")]
+    fn max_rewards_per_claim(&self) -> SingleValueMapper<BigUint>;
+
+    /// 0 if there is no epoch limit
+    #[view(getEpochDurationBlocks)]
+    #[storage_mapper("epochDurationBlocks")]
+    fn epoch_duration_blocks(&self) -> SingleValueMapper<u64>;
+
+    #[view(getMaxRewardsPerEpoch)]
+    #[storage_mapper("maxRewardsPerEpoch")]
+    fn max_rewards_per_epoch(&self) -> SingleValueMapper<BigUint>;
+
+    /// current block nonce / epoch duration, at the last paid claim
+    #[storage_mapper("currentEpoch")]
+    fn current_epoch(&self) -> SingleValueMapper<u64>;
+
+    #[view(getCurrentEpochRewardsPaid)]
+    #[storage_mapper("currentEpochRewardsPaid")]
+    fn current_epoch_rewards_paid(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(isRewardReserveMode)]
+    #[storage_mapper("rewardReserveMode")]
+    fn reward_reserve_mode(&self) -> SingleValueMapper<bool>;
+
+    #[view(getRewardReserve)]
+    #[storage_mapper("rewardReserve")]
+    fn reward_reserve(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getTotalRewardsMinted)]
+    #[storage_mapper("totalRewardsMinted")]
+    fn total_rewards_minted(&self) -> SingleValueMapper<BigUint>;
+
+    /// 0 while the emission cap isn't reached
+    #[view(getRewardCapReachedBlockNonce)]
+    #[storage_mapper("rewardCapReachedBlockNonce")]
+    fn reward_cap_reached_block_nonce(&self) -> SingleValueMapper<u64>;
+
+    #[view(getGovernanceTokenId)]
+    #[storage_mapper("governanceTokenId")]
+    fn governance_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
+
+    #[storage_mapper("governanceRewardCheckpoints")]
+    fn governance_reward_checkpoints(&self) -> VecMapper<RewardCheckpoint<Self::Api>>;
+
+    #[storage_mapper("extraRewardTokens")]
+    fn extra_reward_tokens(&self) -> SetMapper<TokenIdentifier>;
+
+    #[storage_mapper("extraRewardCheckpoints")]
+    fn extra_reward_checkpoints(
+        &self,
+        token_id: &TokenIdentifier,
+    ) -> VecMapper<RewardCheckpoint<Self::Api>>;
+
+    #[view(getLiquidityTokenId)]
+    #[storage_mapper("liquidityTokenId")]
+    fn liquidity_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
+
+    #[storage_mapper("liquidityRewardCheckpoints")]
+    fn liquidity_reward_checkpoints(&self) -> VecMapper<RewardCheckpoint<Self::Api>>;
+
+    /// only the amount and reward fields are used for liquidity deposits
+    #[storage_mapper("liquidityDeposits")]
+    fn liquidity_deposits(
+        &self,
+        sft_nonce: u64,
+    ) -> MapMapper<ManagedAddress, UserDeposit<Self::Api>>;
+
+    /// the SFT nonces that currently have deposits
+    #[storage_mapper("liquiditySftNonces")]
+    fn liquidity_sft_nonces(&self) -> SetMapper<u64>;
+
+    #[view(getTotalLiquidityDeposits)]
+    #[storage_mapper("totalLiquidityDeposits")]
+    fn total_liquidity_deposits(&self, sft_nonce: u64) -> SingleValueMapper<BigUint>;
+
+    /// number of addresses with a vesting stream
+    #[storage_mapper("vestingStreamsCount")]
+    fn vesting_streams_count(&self) -> SingleValueMapper<u64>;
+
+    /// number of addresses with a pending withdrawal
+    #[storage_mapper("pendingWithdrawalsCount")]
+    fn pending_withdrawals_count(&self) -> SingleValueMapper<u64>;
+
+    #[view(getVestingDurationBlocks)]
+    #[storage_mapper("vestingDurationBlocks")]
+    fn vesting_duration_blocks(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("userVesting")]
+    fn user_vesting(&self, address: &ManagedAddress)
+        -> SingleValueMapper<VestingStream<Self::Api>>;
+
+    #[view(isAutoRestakeEnabled)]
+    #[storage_mapper("autoRestake")]
+    fn auto_restake(&self, address: &ManagedAddress) -> SingleValueMapper<bool>;
+
+    /// rewards paid in each token, including the compounded and vesting ones
+    #[view(getTotalRewardsPaid)]
+    #[storage_mapper("totalRewardsPaid")]
+    fn total_rewards_paid(&self, token_id: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    #[view(getReferrer)]
+    #[storage_mapper("userReferrer")]
+    fn user_referrer(&self, address: &ManagedAddress) -> SingleValueMapper<ManagedAddress>;
+
+    #[view(getReferralRewards)]
+    #[storage_mapper("referralRewards")]
+    fn referral_rewards(&self, address: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[view(getReferralRewardPercentage)]
+    #[storage_mapper("referralRewardPercentage")]
+    fn referral_reward_percentage(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getWithdrawCooldownBlocks)]
+    #[storage_mapper("withdrawCooldownBlocks")]
+    fn withdraw_cooldown_blocks(&self) -> SingleValueMapper<u64>;
+
+    #[view(getPendingWithdrawal)]
+    #[storage_mapper("pendingWithdrawal")]
+    fn pending_withdrawal(
+        &self,
+        address: &ManagedAddress,
+    ) -> SingleValueMapper<PendingWithdrawal<Self::Api>>;
+
+    #[view(getRoundDurationSeconds)]
+    #[storage_mapper("roundDurationSeconds")]
+    fn round_duration_seconds(&self) -> SingleValueMapper<u64>;
+
+    #[view(getMinDepositAmount)]
+    #[storage_mapper("minDepositAmount")]
+    fn min_deposit_amount(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getMaxDepositPerUser)]
+    #[storage_mapper("maxDepositPerUser")]
+    fn max_deposit_per_user(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getMaxTotalDeposits)]
+    #[storage_mapper("maxTotalDeposits")]
+    fn max_total_deposits(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getTotalDeposits)]
+    #[storage_mapper("totalDeposits")]
+    fn total_deposits(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getEarlyWithdrawPenaltyPercentage)]
+    #[storage_mapper("earlyWithdrawPenaltyPercentage")]
+    fn early_withdraw_penalty_percentage(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getDepositFeePercentage)]
+    #[storage_mapper("depositFeePercentage")]
+    fn deposit_fee_percentage(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getSwapPairAddress)]
+    #[storage_mapper("swapPairAddress")]
+    fn swap_pair_address(&self) -> SingleValueMapper<ManagedAddress>;
+
+    #[view(getSwapOutputTokenId)]
+    #[storage_mapper("swapOutputTokenId")]
+    fn swap_output_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
+
+    #[view(getTreasuryAddress)]
+    #[storage_mapper("treasuryAddress")]
+    fn treasury_address(&self) -> SingleValueMapper<ManagedAddress>;
+
+    #[view(getSuccessorAddress)]
+    #[storage_mapper("successorAddress")]
+    fn successor_address(&self) -> SingleValueMapper<ManagedAddress>;
+
+    #[view(getPredecessorAddress)]
+    #[storage_mapper("predecessorAddress")]
+    fn predecessor_address(&self) -> SingleValueMapper<ManagedAddress>;
+
+    /// only set before the storage migration, see migrateStorage
+    #[storage_mapper("percentageRewardPerBlock")]
+    fn legacy_percentage_reward_per_block(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("legacyDepositsLeft")]
+    fn legacy_deposits_left(&self) -> SingleValueMapper<usize>;
+
+    /// same storage as user_deposits, with the first version's layout
+    #[storage_mapper("userDeposits")]
+    fn legacy_user_deposits(&self) -> MapMapper<ManagedAddress, LegacyUserDeposit<Self::Api>>;
 
     #[storage_mapper("userDeposits")]
-    fn user_deposits(&self) -> MapMapper<Self::Storage, Address, UserDeposit<Self::BigUint>>;
+    fn user_deposits(&self) -> MapMapper<ManagedAddress, UserDeposit<Self::Api>>;
 }
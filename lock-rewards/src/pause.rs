@@ -0,0 +1,39 @@
+numbat_wasm::imports!();
+
+#[numbat_wasm::module]
+pub trait PauseModule:
+    crate::clock::ClockModule
+    + crate::events::EventsModule
+    + crate::storage_version::StorageVersionModule
+{
+    #[only_owner]
+    #[endpoint]
+    fn pause(&self) -> SCResult<()> {
+        self.paused().set(&true);
+        self.pause_event(self.get_current_block_nonce());
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint]
+    fn unpause(&self) -> SCResult<()> {
+        self.require_storage_up_to_date()?;
+
+        self.paused().clear();
+        self.unpause_event(self.get_current_block_nonce());
+
+        Ok(())
+    }
+
+    fn require_not_paused(&self) -> SCResult<()> {
+        require!(!self.paused().get(), "Contract is paused");
+        Ok(())
+    }
+
+    // storage
+
+    #[view(isPaused)]
+    #[storage_mapper("paused")]
+    fn paused(&self) -> SingleValueMapper<bool>;
+}
@@ -0,0 +1,170 @@
+numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+use crate::user_deposit::UserDeposit;
+
+const POSITION_TOKEN_NAME: &[u8] = b"LockRewardsPosition";
+const POSITION_TOKEN_TICKER: &[u8] = b"LRPOS";
+
+/// Snapshot of the position at the time the NFT was created.
+/// The contract only uses the data kept in storage for the NFT nonce.
+#[derive(TypeAbi, TopEncode, TopDecode)]
+pub struct PositionAttributes<M: ManagedTypeApi> {
+    pub amount: BigUint<M>,
+    pub last_claim_block_nonce: u64,
+    pub unlock_block_nonce: u64,
+    pub reward_multiplier: BigUint<M>,
+}
+
+impl<M: ManagedTypeApi> From<&UserDeposit<M>> for PositionAttributes<M> {
+    fn from(user_deposit: &UserDeposit<M>) -> Self {
+        PositionAttributes {
+            amount: user_deposit.amount.clone(),
+            last_claim_block_nonce: user_deposit.last_claim_block_nonce,
+            unlock_block_nonce: user_deposit.unlock_block_nonce,
+            reward_multiplier: user_deposit.reward_multiplier.clone(),
+        }
+    }
+}
+
+#[numbat_wasm::module]
+pub trait PositionTokenModule {
+    #[only_owner]
+    #[payable("REWA")]
+    #[endpoint(issuePositionToken)]
+    fn issue_position_token(&self, #[payment_amount] issue_cost: BigUint) -> SCResult<AsyncCall> {
+        require!(
+            self.position_token_id().is_empty(),
+            "Position token already issued"
+        );
+
+        let token_display_name = ManagedBuffer::from(POSITION_TOKEN_NAME);
+        let token_ticker = ManagedBuffer::from(POSITION_TOKEN_TICKER);
+
+        Ok(self
+            .send()
+            .dcdt_system_sc_proxy()
+            .issue_non_fungible(
+                issue_cost,
+                &token_display_name,
+                &token_ticker,
+                NonFungibleTokenProperties {
+                    can_freeze: true,
+                    can_wipe: true,
+                    can_pause: true,
+                    can_change_owner: true,
+                    can_upgrade: true,
+                    can_add_special_roles: true,
+                },
+            )
+            .async_call()
+            .with_callback(self.callbacks().position_token_issue_callback()))
+    }
+
+    /// returns the nonce of the newly created NFT
+    fn create_and_send_position_token(
+        &self,
+        to: &ManagedAddress,
+        user_deposit: &UserDeposit<Self::Api>,
+    ) -> u64 {
+        let token_id = self.position_token_id().get();
+        let amount = BigUint::from(1u32);
+
+        let mut uris = ManagedVec::new();
+        uris.push(ManagedBuffer::new());
+
+        let new_nonce = self.send().dcdt_nft_create(
+            &token_id,
+            &amount,
+            &ManagedBuffer::from(POSITION_TOKEN_NAME),
+            &BigUint::zero(),
+            &ManagedBuffer::new(),
+            &PositionAttributes::from(user_deposit),
+            &uris,
+        );
+        self.send().direct(to, &token_id, new_nonce, &amount, &[]);
+
+        new_nonce
+    }
+
+    fn burn_position_token(&self, nonce: u64) {
+        self.send()
+            .dcdt_local_burn(&self.position_token_id().get(), nonce, &BigUint::from(1u32));
+    }
+
+    fn require_position_token_issued(&self) -> SCResult<()> {
+        require!(
+            !self.position_token_id().is_empty(),
+            "Position token must be issued first"
+        );
+        Ok(())
+    }
+
+    fn require_position_token(
+        &self,
+        token_id: &TokenIdentifier,
+        nonce: u64,
+        amount: &BigUint,
+    ) -> SCResult<()> {
+        self.require_position_token_issued()?;
+        require!(
+            token_id == &self.position_token_id().get() && amount == &1,
+            "Must pay with one position token"
+        );
+        require!(!self.positions(nonce).is_empty(), "Unknown position");
+        Ok(())
+    }
+
+    fn set_position_token_roles(&self) -> AsyncCall {
+        let own_sc_address = self.blockchain().get_sc_address();
+        let token_id = self.position_token_id().get();
+        let roles = [DcdtLocalRole::NftCreate, DcdtLocalRole::NftBurn];
+
+        self.send()
+            .dcdt_system_sc_proxy()
+            .set_special_roles(&own_sc_address, &token_id, roles.iter().cloned())
+            .async_call()
+    }
+
+    #[callback]
+    fn position_token_issue_callback(
+        &self,
+        #[call_result] result: ManagedAsyncCallResult<TokenIdentifier>,
+    ) -> OptionalResult<AsyncCall> {
+        match result {
+            ManagedAsyncCallResult::Ok(token_id) => {
+                self.position_token_id().set(&token_id);
+
+                OptionalResult::Some(self.set_position_token_roles())
+            }
+            ManagedAsyncCallResult::Err(_) => {
+                let initial_caller = self.blockchain().get_owner_address();
+                let rewa_returned = self.call_value().rewa_value();
+                if rewa_returned > 0 {
+                    self.send()
+                        .direct_rewa(&initial_caller, &rewa_returned, &[]);
+                }
+
+                OptionalResult::None
+            }
+        }
+    }
+
+    // storage
+
+    #[view(getPositionTokenId)]
+    #[storage_mapper("positionTokenId")]
+    fn position_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
+
+    #[storage_mapper("positions")]
+    fn positions(&self, nonce: u64) -> SingleValueMapper<UserDeposit<Self::Api>>;
+
+    /// the address that opened the position, whose per-user cap it counts against
+    #[storage_mapper("positionDepositor")]
+    fn position_depositor(&self, nonce: u64) -> SingleValueMapper<ManagedAddress>;
+
+    /// amount of the positions opened by the user and not withdrawn yet, whoever holds them now
+    #[view(getUserPositionsAmount)]
+    #[storage_mapper("userPositionsAmount")]
+    fn user_positions_amount(&self, address: &ManagedAddress) -> SingleValueMapper<BigUint>;
+}
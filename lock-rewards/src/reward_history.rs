@@ -0,0 +1,88 @@
+numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Clone, Copy)]
+pub enum RewardHistoryAction {
+    Accrued,
+    Claimed,
+    Compounded,
+}
+
+/// Stablecoin rewards of a user's deposit.
+/// `percentage_reward_per_block` is the reward percentage when the entry was recorded.
+/// Consecutive accruals at the same percentage are merged into a single entry,
+/// so a new `Accrued` entry means the rate affecting the user changed.
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct RewardHistoryEntry<M: ManagedTypeApi> {
+    pub block_nonce: u64,
+    pub action: RewardHistoryAction,
+    pub percentage_reward_per_block: BigUint<M>,
+    pub amount: BigUint<M>,
+}
+
+#[numbat_wasm::module]
+pub trait RewardHistoryModule {
+    #[view(getRewardHistoryLength)]
+    fn get_reward_history_length(&self, address: ManagedAddress) -> usize {
+        self.reward_history(&address).len()
+    }
+
+    /// At most `count` entries of the user's reward history, oldest first, starting from `from_index`.
+    /// Only covers the regular deposits, not the positions or the liquidity deposits.
+    /// In timestamp mode, `block_nonce` is a timestamp.
+    #[view(getRewardHistory)]
+    fn get_reward_history(
+        &self,
+        address: ManagedAddress,
+        from_index: usize,
+        count: usize,
+    ) -> MultiResultVec<RewardHistoryEntry<Self::Api>> {
+        let entries: Vec<RewardHistoryEntry<Self::Api>> = self
+            .reward_history(&address)
+            .iter()
+            .skip(from_index)
+            .take(count)
+            .collect();
+
+        entries.into()
+    }
+
+    fn record_reward_history(
+        &self,
+        address: &ManagedAddress,
+        action: RewardHistoryAction,
+        percentage_reward_per_block: &BigUint,
+        amount: &BigUint,
+        block_nonce: u64,
+    ) {
+        if amount == &0 {
+            return;
+        }
+
+        let mut history = self.reward_history(address);
+        let len = history.len();
+        if action == RewardHistoryAction::Accrued && len > 0 {
+            let mut last_entry = history.get(len);
+            if last_entry.action == RewardHistoryAction::Accrued
+                && &last_entry.percentage_reward_per_block == percentage_reward_per_block
+            {
+                last_entry.block_nonce = block_nonce;
+                last_entry.amount += amount;
+                history.set(len, &last_entry);
+                return;
+            }
+        }
+
+        history.push(&RewardHistoryEntry {
+            block_nonce,
+            action,
+            percentage_reward_per_block: percentage_reward_per_block.clone(),
+            amount: amount.clone(),
+        });
+    }
+
+    // storage
+
+    #[storage_mapper("rewardHistory")]
+    fn reward_history(&self, address: &ManagedAddress) -> VecMapper<RewardHistoryEntry<Self::Api>>;
+}
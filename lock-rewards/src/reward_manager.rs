@@ -0,0 +1,45 @@
+numbat_wasm::imports!();
+
+#[numbat_wasm::module]
+pub trait RewardManagerModule: crate::events::EventsModule {
+    /// The reward manager can tune the emissions, next to the owner.
+    /// No argument removes the reward manager.
+    #[only_owner]
+    #[endpoint(setRewardManager)]
+    fn set_reward_manager(
+        &self,
+        #[var_args] opt_address: OptionalArg<ManagedAddress>,
+    ) -> SCResult<()> {
+        let address = match opt_address.into_option() {
+            Some(address) => {
+                require!(!address.is_zero(), "Invalid address");
+                self.reward_manager().set(&address);
+                address
+            }
+            None => {
+                self.reward_manager().clear();
+                ManagedAddress::zero()
+            }
+        };
+        self.reward_manager_changed_event(&address);
+
+        Ok(())
+    }
+
+    fn require_owner_or_reward_manager(&self) -> SCResult<()> {
+        let caller = self.blockchain().get_caller();
+        let is_reward_manager =
+            !self.reward_manager().is_empty() && caller == self.reward_manager().get();
+        require!(
+            is_reward_manager || caller == self.blockchain().get_owner_address(),
+            "only owner or reward manager may call this function"
+        );
+        Ok(())
+    }
+
+    // storage
+
+    #[view(getRewardManager)]
+    #[storage_mapper("rewardManager")]
+    fn reward_manager(&self) -> SingleValueMapper<ManagedAddress>;
+}
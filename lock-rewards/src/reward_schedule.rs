@@ -0,0 +1,64 @@
+numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct RewardScheduleEntry<M: ManagedTypeApi> {
+    pub start_block_nonce: u64,
+    pub percentage_reward_per_block: BigUint<M>,
+}
+
+/// Global rewards-per-share accumulator, saved each time the percentage changes.
+/// `reward_per_share` is the sum of percentage * blocks from the first checkpoint
+/// until `start_block_nonce`, i.e. the rewards earned by one deposited unit, using the precision.
+/// The percentage is active until the next checkpoint starts.
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct RewardCheckpoint<M: ManagedTypeApi> {
+    pub start_block_nonce: u64,
+    pub reward_per_share: BigUint<M>,
+    pub percentage_reward_per_block: BigUint<M>,
+}
+
+impl<M: ManagedTypeApi> RewardCheckpoint<M> {
+    pub fn first(entry: &RewardScheduleEntry<M>) -> Self {
+        RewardCheckpoint {
+            start_block_nonce: entry.start_block_nonce,
+            reward_per_share: BigUint::zero(),
+            percentage_reward_per_block: entry.percentage_reward_per_block.clone(),
+        }
+    }
+
+    /// the checkpoint that continues this one with the entry's percentage
+    pub fn next(&self, entry: &RewardScheduleEntry<M>) -> Self {
+        RewardCheckpoint {
+            start_block_nonce: entry.start_block_nonce,
+            reward_per_share: self.get_reward_per_share(entry.start_block_nonce),
+            percentage_reward_per_block: entry.percentage_reward_per_block.clone(),
+        }
+    }
+
+    pub fn get_reward_per_share(&self, block_nonce: u64) -> BigUint<M> {
+        if block_nonce <= self.start_block_nonce {
+            return self.reward_per_share.clone();
+        }
+
+        let blocks = block_nonce - self.start_block_nonce;
+        self.reward_per_share.clone() + self.percentage_reward_per_block.clone() * blocks.into()
+    }
+}
+
+/// The accumulator values a deposit needs to calculate its rewards since the last claim
+pub struct RewardPerShareSnapshot<M: ManagedTypeApi> {
+    pub at_last_claim: BigUint<M>,
+    pub at_lock_end: BigUint<M>,
+    pub at_current: BigUint<M>,
+}
+
+impl<M: ManagedTypeApi> RewardPerShareSnapshot<M> {
+    pub fn zero() -> Self {
+        RewardPerShareSnapshot {
+            at_last_claim: BigUint::zero(),
+            at_lock_end: BigUint::zero(),
+            at_current: BigUint::zero(),
+        }
+    }
+}
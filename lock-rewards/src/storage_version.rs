@@ -0,0 +1,25 @@
+numbat_wasm::imports!();
+
+/// 0 is the layout of the first deployed version, with the three field UserDeposit
+pub const CURRENT_STORAGE_VERSION: u32 = 1;
+
+#[numbat_wasm::module]
+pub trait StorageVersionModule {
+    fn is_storage_up_to_date(&self) -> bool {
+        self.storage_version().get() == CURRENT_STORAGE_VERSION
+    }
+
+    fn require_storage_up_to_date(&self) -> SCResult<()> {
+        require!(
+            self.is_storage_up_to_date(),
+            "Storage must be migrated first, see migrateStorage"
+        );
+        Ok(())
+    }
+
+    // storage
+
+    #[view(getStorageVersion)]
+    #[storage_mapper("storageVersion")]
+    fn storage_version(&self) -> SingleValueMapper<u32>;
+}
@@ -0,0 +1,19 @@
+numbat_wasm::imports!();
+
+/// Endpoint a successor contract has to implement in order to receive migrated deposits.
+/// The principal is sent as payment, or no payment at all if the user only had rewards.
+/// LockRewards implements it as well, so a newer version can be deployed as the successor.
+#[numbat_wasm::proxy]
+pub trait LockRewardsSuccessor {
+    #[payable("*")]
+    #[endpoint(acceptMigratedDeposit)]
+    fn accept_migrated_deposit(
+        &self,
+        #[payment_token] token_id: TokenIdentifier,
+        #[payment_amount] amount: BigUint,
+        user: ManagedAddress,
+        cummulated_rewards: BigUint,
+        unlock_block_nonce: u64,
+        reward_multiplier: BigUint,
+    );
+}
@@ -0,0 +1,16 @@
+numbat_wasm::imports!();
+
+/// Swap endpoint of the DEX pair used by claimRewardsSwapped.
+/// The output tokens are sent back to the caller of the swap.
+#[numbat_wasm::proxy]
+pub trait DexPair {
+    #[payable("*")]
+    #[endpoint(swapTokensFixedInput)]
+    fn swap_tokens_fixed_input(
+        &self,
+        #[payment_token] token_in: TokenIdentifier,
+        #[payment_amount] amount_in: BigUint,
+        token_out: TokenIdentifier,
+        amount_out_min: BigUint,
+    );
+}
@@ -1,46 +1,200 @@
-use numbat_wasm::api::BigUintApi;
-
+numbat_wasm::imports!();
 numbat_wasm::derive_imports!();
 
-// for consistency, we're using the same precision as the liquidity pool
+use crate::reward_schedule::RewardPerShareSnapshot;
+
+// for consistency, we're using the same precision as the liquidity pool by default
 pub const BASE_PRECISION: u64 = 1_000_000_000;
 
+// bounds for the precision set at init
+pub const MIN_PRECISION: u64 = 1_000;
+pub const MAX_PRECISION: u64 = 1_000_000_000_000_000_000;
+
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct ExtraReward<M: ManagedTypeApi> {
+    pub token_id: TokenIdentifier<M>,
+    pub amount: BigUint<M>,
+}
+
+/// amount taken out of the deposit, that can be sent to the user after the cooldown
+#[derive(TypeAbi, TopEncode, TopDecode)]
+pub struct PendingWithdrawal<M: ManagedTypeApi> {
+    pub amount: BigUint<M>,
+    pub unlock_block_nonce: u64,
+}
+
 #[derive(TypeAbi, TopEncode, TopDecode)]
-pub struct UserDeposit<BigUint: BigUintApi> {
-    pub amount: BigUint,
+pub struct UserDeposit<M: ManagedTypeApi> {
+    pub amount: BigUint<M>,
 
     // updated when savings rate is changed or user withdraws part or all of their deposit
-    pub cummulated_rewards: BigUint,
+    pub cummulated_rewards: BigUint<M>,
 
     pub last_claim_block_nonce: u64,
+
+    // 0 if the deposit was never locked
+    pub unlock_block_nonce: u64,
+
+    // applied on top of the reward percentage until the unlock block, the precision means 1x
+    pub reward_multiplier: BigUint<M>,
+
+    // total amount deducted from early withdrawals
+    pub penalties_paid: BigUint<M>,
+
+    // rewards accumulated in tokens other than the stablecoin
+    pub extra_rewards: Vec<ExtraReward<M>>,
+
+    // rewards accumulated in the governance token
+    pub governance_rewards: BigUint<M>,
 }
 
-impl<BigUint: BigUintApi> Default for UserDeposit<BigUint> {
-    fn default() -> Self {
+impl<M: ManagedTypeApi> UserDeposit<M> {
+    /// an empty deposit, with a 1x multiplier for the contract's precision
+    pub fn new(precision: BigUint<M>) -> Self {
         UserDeposit {
             amount: BigUint::zero(),
             cummulated_rewards: BigUint::zero(),
             last_claim_block_nonce: 0,
+            unlock_block_nonce: 0,
+            reward_multiplier: precision,
+            penalties_paid: BigUint::zero(),
+            extra_rewards: Vec::new(),
+            governance_rewards: BigUint::zero(),
+        }
+    }
+}
+
+/// UserDeposit layout of the first deployed version, only used by the storage migration
+#[derive(TypeAbi, TopEncode, TopDecode)]
+pub struct LegacyUserDeposit<M: ManagedTypeApi> {
+    pub amount: BigUint<M>,
+    pub cummulated_rewards: BigUint<M>,
+    pub last_claim_block_nonce: u64,
+}
+
+/// the first version always used BASE_PRECISION
+impl<M: ManagedTypeApi> From<LegacyUserDeposit<M>> for UserDeposit<M> {
+    fn from(legacy_user_deposit: LegacyUserDeposit<M>) -> Self {
+        UserDeposit {
+            amount: legacy_user_deposit.amount,
+            cummulated_rewards: legacy_user_deposit.cummulated_rewards,
+            last_claim_block_nonce: legacy_user_deposit.last_claim_block_nonce,
+            ..UserDeposit::new(BigUint::from(BASE_PRECISION))
         }
     }
 }
 
-impl<BigUint: BigUintApi> UserDeposit<BigUint> {
+impl<M: ManagedTypeApi> UserDeposit<M> {
+    #[inline(always)]
+    pub fn is_locked(&self, current_block_nonce: u64) -> bool {
+        current_block_nonce < self.unlock_block_nonce
+    }
+
+    /// no amount and no rewards left, so the entry can be removed
+    pub fn is_empty(&self) -> bool {
+        self.amount == 0
+            && self.cummulated_rewards == 0
+            && self.extra_rewards.is_empty()
+            && self.governance_rewards == 0
+    }
+
+    /// the multiplier only applies to the blocks spent inside the lock period
+    pub fn get_lock_end_block_nonce(&self, current_block_nonce: u64) -> u64 {
+        self.unlock_block_nonce
+            .clamp(self.last_claim_block_nonce, current_block_nonce)
+    }
+
+    /// `tier_multiplier` is the boost tier multiplier for the current amount
     pub fn accummulate_rewards(
         &mut self,
         current_block_nonce: u64,
-        percentage_reward_per_block: &BigUint,
+        precision: &BigUint<M>,
+        tier_multiplier: &BigUint<M>,
+        reward_per_share: &RewardPerShareSnapshot<M>,
+        governance_reward_per_share: &RewardPerShareSnapshot<M>,
+        extra_reward_per_share: &[(TokenIdentifier<M>, RewardPerShareSnapshot<M>)],
     ) {
         if self.amount == 0 {
+            self.last_claim_block_nonce = current_block_nonce;
             return;
         }
 
-        let amount_per_block =
-            (self.amount.clone() * percentage_reward_per_block.clone()) / BASE_PRECISION.into();
-        let blocks_waited = current_block_nonce - self.last_claim_block_nonce;
-        let additional_cummulated_rewards = amount_per_block * blocks_waited.into();
+        self.cummulated_rewards +=
+            self.calculate_rewards(precision, tier_multiplier, reward_per_share);
+        self.governance_rewards +=
+            self.calculate_rewards(precision, tier_multiplier, governance_reward_per_share);
+
+        for (token_id, token_reward_per_share) in extra_reward_per_share {
+            let rewards_amount =
+                self.calculate_rewards(precision, tier_multiplier, token_reward_per_share);
+            self.add_extra_rewards(token_id, rewards_amount);
+        }
 
-        self.cummulated_rewards += additional_cummulated_rewards;
         self.last_claim_block_nonce = current_block_nonce;
     }
+
+    /// amount * reward per share earned since the last claim, with the multiplier applied for the locked part
+    /// and the tier multiplier applied on top
+    fn calculate_rewards(
+        &self,
+        precision: &BigUint<M>,
+        tier_multiplier: &BigUint<M>,
+        reward_per_share: &RewardPerShareSnapshot<M>,
+    ) -> BigUint<M> {
+        let locked_reward_per_share =
+            reward_per_share.at_lock_end.clone() - reward_per_share.at_last_claim.clone();
+        let unlocked_reward_per_share =
+            reward_per_share.at_current.clone() - reward_per_share.at_lock_end.clone();
+        let weighted_reward_per_share = (locked_reward_per_share * self.reward_multiplier.clone())
+            / precision.clone()
+            + unlocked_reward_per_share;
+
+        let base_rewards = (self.amount.clone() * weighted_reward_per_share) / precision.clone();
+
+        (base_rewards * tier_multiplier.clone()) / precision.clone()
+    }
+
+    pub fn add_extra_rewards(&mut self, token_id: &TokenIdentifier<M>, amount: BigUint<M>) {
+        if amount == 0 {
+            return;
+        }
+
+        match self
+            .extra_rewards
+            .iter_mut()
+            .find(|extra_reward| &extra_reward.token_id == token_id)
+        {
+            Some(extra_reward) => extra_reward.amount += amount,
+            None => self.extra_rewards.push(ExtraReward {
+                token_id: token_id.clone(),
+                amount,
+            }),
+        }
+    }
+
+    /// removes the entry for the token and returns its amount
+    pub fn take_extra_rewards(&mut self, token_id: &TokenIdentifier<M>) -> BigUint<M> {
+        match self
+            .extra_rewards
+            .iter()
+            .position(|extra_reward| &extra_reward.token_id == token_id)
+        {
+            Some(index) => self.extra_rewards.swap_remove(index).amount,
+            None => BigUint::zero(),
+        }
+    }
+
+    pub fn get_extra_rewards(&self, token_id: &TokenIdentifier<M>) -> BigUint<M> {
+        self.extra_rewards
+            .iter()
+            .find(|extra_reward| &extra_reward.token_id == token_id)
+            .map(|extra_reward| extra_reward.amount.clone())
+            .unwrap_or_else(BigUint::zero)
+    }
+
+    /// Has to be called after rewards were accumulated for the current block
+    pub fn lock(&mut self, unlock_block_nonce: u64, reward_multiplier: BigUint<M>) {
+        self.unlock_block_nonce = unlock_block_nonce;
+        self.reward_multiplier = reward_multiplier;
+    }
 }
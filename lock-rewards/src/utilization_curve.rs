@@ -0,0 +1,48 @@
+numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+/// Throttles the stablecoin rewards once the total deposits go over the target.
+/// `slope` and `min_multiplier` use the contract's precision.
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct UtilizationCurve<M: ManagedTypeApi> {
+    pub target_total_deposits: BigUint<M>,
+    pub slope: BigUint<M>,
+    pub min_multiplier: BigUint<M>,
+}
+
+impl<M: ManagedTypeApi> UtilizationCurve<M> {
+    /// 1x up to the target. Above it, the multiplier goes down by `slope`
+    /// for each 1x of utilization over the target, but never under `min_multiplier`.
+    pub fn get_multiplier(
+        &self,
+        total_deposits: &BigUint<M>,
+        precision: &BigUint<M>,
+    ) -> BigUint<M> {
+        if total_deposits <= &self.target_total_deposits {
+            return precision.clone();
+        }
+
+        let utilization_over_target =
+            ((total_deposits.clone() - self.target_total_deposits.clone()) * precision.clone())
+                / self.target_total_deposits.clone();
+        let reduction = (utilization_over_target * self.slope.clone()) / precision.clone();
+
+        let base_multiplier = precision.clone();
+        if reduction.clone() + self.min_multiplier.clone() >= base_multiplier {
+            self.min_multiplier.clone()
+        } else {
+            base_multiplier - reduction
+        }
+    }
+
+    /// the percentage after applying the multiplier for the total deposits
+    pub fn get_effective_percentage(
+        &self,
+        percentage_reward_per_block: &BigUint<M>,
+        total_deposits: &BigUint<M>,
+        precision: &BigUint<M>,
+    ) -> BigUint<M> {
+        (percentage_reward_per_block.clone() * self.get_multiplier(total_deposits, precision))
+            / precision.clone()
+    }
+}
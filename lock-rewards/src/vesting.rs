@@ -0,0 +1,51 @@
+numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+/// Rewards already minted by the contract, released linearly between the start and end blocks
+#[derive(TypeAbi, TopEncode, TopDecode)]
+pub struct VestingStream<M: ManagedTypeApi> {
+    pub total_amount: BigUint<M>,
+    pub claimed_amount: BigUint<M>,
+    pub start_block_nonce: u64,
+    pub end_block_nonce: u64,
+}
+
+impl<M: ManagedTypeApi> VestingStream<M> {
+    pub fn new(amount: BigUint<M>, start_block_nonce: u64, duration_blocks: u64) -> Self {
+        VestingStream {
+            total_amount: amount,
+            claimed_amount: BigUint::zero(),
+            start_block_nonce,
+            end_block_nonce: start_block_nonce + duration_blocks,
+        }
+    }
+
+    pub fn get_vested_amount(&self, current_block_nonce: u64) -> BigUint<M> {
+        if current_block_nonce >= self.end_block_nonce {
+            return self.total_amount.clone();
+        }
+        if current_block_nonce <= self.start_block_nonce {
+            return BigUint::zero();
+        }
+
+        let blocks_passed = current_block_nonce - self.start_block_nonce;
+        let duration_blocks = self.end_block_nonce - self.start_block_nonce;
+
+        (self.total_amount.clone() * blocks_passed.into()) / duration_blocks.into()
+    }
+
+    /// vested amount that was not claimed yet
+    pub fn get_claimable_amount(&self, current_block_nonce: u64) -> BigUint<M> {
+        self.get_vested_amount(current_block_nonce) - self.claimed_amount.clone()
+    }
+
+    /// amount that is still vesting
+    pub fn get_locked_amount(&self, current_block_nonce: u64) -> BigUint<M> {
+        self.total_amount.clone() - self.get_vested_amount(current_block_nonce)
+    }
+
+    #[inline(always)]
+    pub fn is_fully_claimed(&self) -> bool {
+        self.claimed_amount == self.total_amount
+    }
+}
@@ -0,0 +1,60 @@
+numbat_wasm::imports!();
+
+#[numbat_wasm::module]
+pub trait WhitelistModule: crate::clock::ClockModule + crate::events::EventsModule {
+    /// while enabled, only whitelisted addresses may deposit
+    #[only_owner]
+    #[endpoint(setWhitelistEnabled)]
+    fn set_whitelist_enabled(&self, enabled: bool) -> SCResult<()> {
+        self.whitelist_enabled().set(&enabled);
+        self.whitelist_enabled_event(self.get_current_block_nonce(), enabled);
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint(addToWhitelist)]
+    fn add_to_whitelist(&self, #[var_args] addresses: VarArgs<ManagedAddress>) -> SCResult<()> {
+        for address in addresses.into_vec() {
+            if self.whitelist().insert(address.clone()) {
+                self.whitelist_add_event(&address);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint(removeFromWhitelist)]
+    fn remove_from_whitelist(
+        &self,
+        #[var_args] addresses: VarArgs<ManagedAddress>,
+    ) -> SCResult<()> {
+        for address in addresses.into_vec() {
+            if self.whitelist().remove(&address) {
+                self.whitelist_remove_event(&address);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[view(isWhitelisted)]
+    fn is_whitelisted(&self, address: &ManagedAddress) -> bool {
+        !self.whitelist_enabled().get() || self.whitelist().contains(address)
+    }
+
+    fn require_whitelisted(&self, address: &ManagedAddress) -> SCResult<()> {
+        require!(self.is_whitelisted(address), "Address is not whitelisted");
+        Ok(())
+    }
+
+    // storage
+
+    #[view(isWhitelistEnabled)]
+    #[storage_mapper("whitelistEnabled")]
+    fn whitelist_enabled(&self) -> SingleValueMapper<bool>;
+
+    #[storage_mapper("whitelist")]
+    fn whitelist(&self) -> SetMapper<ManagedAddress>;
+}
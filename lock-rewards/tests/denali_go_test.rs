@@ -0,0 +1,14 @@
+#[test]
+fn deposit_withdraw_go() {
+    numbat_wasm_debug::denali_go("denali/deposit-withdraw.scen.json");
+}
+
+#[test]
+fn emergency_withdraw_go() {
+    numbat_wasm_debug::denali_go("denali/emergency-withdraw.scen.json");
+}
+
+#[test]
+fn vesting_go() {
+    numbat_wasm_debug::denali_go("denali/vesting.scen.json");
+}
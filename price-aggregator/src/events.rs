@@ -0,0 +1,13 @@
+numbat_wasm::imports!();
+
+#[numbat_wasm::module]
+pub trait EventsModule {
+    #[event("newRound")]
+    fn new_round_event(
+        &self,
+        #[indexed] from: &ManagedBuffer,
+        #[indexed] to: &ManagedBuffer,
+        #[indexed] round_id: u32,
+        price: &BigUint,
+    );
+}
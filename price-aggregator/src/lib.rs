@@ -0,0 +1,251 @@
+#![no_std]
+
+numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+pub mod events;
+
+/// One closed round of a pair. `round_id` starts at 1 and increases with every round.
+/// `block_nonce` and `timestamp` are the ones of the block the round was closed in.
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct PriceFeed<M: ManagedTypeApi> {
+    pub round_id: u32,
+    pub from: ManagedBuffer<M>,
+    pub to: ManagedBuffer<M>,
+    pub price: BigUint<M>,
+    pub decimals: u8,
+    pub block_nonce: u64,
+    pub timestamp: u64,
+}
+
+/// Same layout as `price_aggregator_proxy::AggregatorResultAsMultiResult`.
+pub type PriceFeedMultiResult<M> =
+    MultiResult5<u32, ManagedBuffer<M>, ManagedBuffer<M>, BigUint<M>, u8>;
+
+#[numbat_wasm::contract]
+pub trait PriceAggregator: events::EventsModule {
+    /// A round is closed once `submission_count` oracles submitted a price for the pair,
+    /// and its price is the median of the submissions.
+    /// `decimals` is the number of decimals of all the submitted prices.
+    #[init]
+    fn init(
+        &self,
+        submission_count: usize,
+        decimals: u8,
+        #[var_args] oracles: VarArgs<ManagedAddress>,
+    ) -> SCResult<()> {
+        for oracle in oracles.into_vec() {
+            self.oracles().insert(oracle);
+        }
+        self.set_submission_count(submission_count)?;
+        self.decimals().set(&decimals);
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint(addOracles)]
+    fn add_oracles(&self, #[var_args] oracles: VarArgs<ManagedAddress>) -> SCResult<()> {
+        for oracle in oracles.into_vec() {
+            self.oracles().insert(oracle);
+        }
+
+        Ok(())
+    }
+
+    /// The submission count must be lowered first if fewer oracles would remain.
+    #[only_owner]
+    #[endpoint(removeOracles)]
+    fn remove_oracles(&self, #[var_args] oracles: VarArgs<ManagedAddress>) -> SCResult<()> {
+        for oracle in oracles.into_vec() {
+            self.oracles().remove(&oracle);
+        }
+        require!(
+            self.submission_count().get() <= self.oracles().len(),
+            "Not enough oracles left for the submission count"
+        );
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint(setSubmissionCount)]
+    fn set_submission_count(&self, submission_count: usize) -> SCResult<()> {
+        require!(
+            submission_count > 0 && submission_count <= self.oracles().len(),
+            "Invalid submission count"
+        );
+
+        self.submission_count().set(&submission_count);
+
+        Ok(())
+    }
+
+    /// A new submission from the same oracle replaces its previous one in the current round.
+    /// Submissions from removed oracles are ignored.
+    #[endpoint]
+    fn submit(&self, from: ManagedBuffer, to: ManagedBuffer, price: BigUint) -> SCResult<()> {
+        let caller = self.blockchain().get_caller();
+        require!(self.oracles().contains(&caller), "Only oracles may submit");
+        require!(price > 0, "Price must be greater than zero");
+
+        let mut submissions = self.submissions(&from, &to);
+        submissions.insert(caller, price);
+
+        let mut prices = Vec::new();
+        for oracle in self.oracles().iter() {
+            if let Some(price) = submissions.get(&oracle) {
+                prices.push(price);
+            }
+        }
+        if prices.len() < self.submission_count().get() {
+            return Ok(());
+        }
+
+        let submitters: Vec<ManagedAddress> = submissions.keys().collect();
+        for submitter in submitters.iter() {
+            submissions.remove(submitter);
+        }
+
+        let mut rounds = self.rounds(&from, &to);
+        let round_id = rounds.len() as u32 + 1;
+        let median_price = self.calculate_median(prices);
+        rounds.push(&PriceFeed {
+            round_id,
+            from: from.clone(),
+            to: to.clone(),
+            price: median_price.clone(),
+            decimals: self.decimals().get(),
+            block_nonce: self.blockchain().get_block_nonce(),
+            timestamp: self.blockchain().get_block_timestamp(),
+        });
+        self.new_round_event(&from, &to, round_id, &median_price);
+
+        Ok(())
+    }
+
+    #[view(latestPriceFeed)]
+    fn latest_price_feed(
+        &self,
+        from: ManagedBuffer,
+        to: ManagedBuffer,
+    ) -> SCResult<PriceFeedMultiResult<Self::Api>> {
+        match self.get_latest_price_feed(&from, &to) {
+            Some(price_feed) => Ok(self.price_feed_as_multi_result(price_feed)),
+            None => sc_error!("No price for the pair"),
+        }
+    }
+
+    #[view(latestPriceFeedOptional)]
+    fn latest_price_feed_optional(
+        &self,
+        from: ManagedBuffer,
+        to: ManagedBuffer,
+    ) -> OptionalResult<PriceFeedMultiResult<Self::Api>> {
+        match self.get_latest_price_feed(&from, &to) {
+            Some(price_feed) => OptionalResult::Some(self.price_feed_as_multi_result(price_feed)),
+            None => OptionalResult::None,
+        }
+    }
+
+    /// The last round's id, block nonce and timestamp, so consumers can check how old its price is.
+    #[view(latestRoundInfo)]
+    fn latest_round_info(
+        &self,
+        from: ManagedBuffer,
+        to: ManagedBuffer,
+    ) -> OptionalResult<MultiResult3<u32, u64, u64>> {
+        match self.get_latest_price_feed(&from, &to) {
+            Some(price_feed) => OptionalResult::Some(
+                (
+                    price_feed.round_id,
+                    price_feed.block_nonce,
+                    price_feed.timestamp,
+                )
+                    .into(),
+            ),
+            None => OptionalResult::None,
+        }
+    }
+
+    #[view(getOracles)]
+    fn get_oracles(&self) -> MultiResultVec<ManagedAddress> {
+        let oracles: Vec<ManagedAddress> = self.oracles().iter().collect();
+
+        oracles.into()
+    }
+
+    #[view(getRoundCount)]
+    fn get_round_count(&self, from: ManagedBuffer, to: ManagedBuffer) -> usize {
+        self.rounds(&from, &to).len()
+    }
+
+    fn get_latest_price_feed(
+        &self,
+        from: &ManagedBuffer,
+        to: &ManagedBuffer,
+    ) -> Option<PriceFeed<Self::Api>> {
+        let rounds = self.rounds(from, to);
+        let len = rounds.len();
+        if len == 0 {
+            return None;
+        }
+
+        Some(rounds.get(len))
+    }
+
+    fn price_feed_as_multi_result(
+        &self,
+        price_feed: PriceFeed<Self::Api>,
+    ) -> PriceFeedMultiResult<Self::Api> {
+        (
+            price_feed.round_id,
+            price_feed.from,
+            price_feed.to,
+            price_feed.price,
+            price_feed.decimals,
+        )
+            .into()
+    }
+
+    // the average of the two middle prices for an even count
+    fn calculate_median(&self, prices: Vec<BigUint>) -> BigUint {
+        // insertion sort, there is at most one price per oracle
+        let mut sorted_prices: Vec<BigUint> = Vec::with_capacity(prices.len());
+        for price in prices {
+            let position = sorted_prices.iter().take_while(|p| *p <= &price).count();
+            sorted_prices.insert(position, price);
+        }
+
+        let middle = sorted_prices.len() / 2;
+        if sorted_prices.len() % 2 == 1 {
+            return sorted_prices[middle].clone();
+        }
+
+        (&sorted_prices[middle - 1] + &sorted_prices[middle]) / BigUint::from(2u32)
+    }
+
+    // storage
+
+    #[storage_mapper("oracles")]
+    fn oracles(&self) -> SetMapper<ManagedAddress>;
+
+    #[view(getSubmissionCount)]
+    #[storage_mapper("submissionCount")]
+    fn submission_count(&self) -> SingleValueMapper<usize>;
+
+    #[view(getDecimals)]
+    #[storage_mapper("decimals")]
+    fn decimals(&self) -> SingleValueMapper<u8>;
+
+    // the current round's submissions, by oracle
+    #[storage_mapper("submissions")]
+    fn submissions(
+        &self,
+        from: &ManagedBuffer,
+        to: &ManagedBuffer,
+    ) -> MapMapper<ManagedAddress, BigUint>;
+
+    #[storage_mapper("rounds")]
+    fn rounds(&self, from: &ManagedBuffer, to: &ManagedBuffer) -> VecMapper<PriceFeed<Self::Api>>;
+}
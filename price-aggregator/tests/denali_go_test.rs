@@ -0,0 +1,19 @@
+#[test]
+fn deploy_go() {
+    numbat_wasm_debug::denali_go("denali/deploy.scen.json");
+}
+
+#[test]
+fn submit_go() {
+    numbat_wasm_debug::denali_go("denali/submit.scen.json");
+}
+
+#[test]
+fn median_go() {
+    numbat_wasm_debug::denali_go("denali/median.scen.json");
+}
+
+#[test]
+fn staleness_go() {
+    numbat_wasm_debug::denali_go("denali/staleness.scen.json");
+}
@@ -0,0 +1,4 @@
+#![no_std]
+
+pub use numbat_wasm_output::*;
+pub use price_aggregator::*;
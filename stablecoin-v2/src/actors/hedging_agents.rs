@@ -34,6 +34,7 @@ impl<M: ManagedTypeApi> HedgingPosition<M> {
 pub trait HedgingAgentsModule:
     crate::fees::FeesModule
     + crate::hedging_token::HedgingTokenModule
+    + crate::liquidity_providers::LiquidityProvidersModule
     + crate::liquidity_token::LiquidityTokenModule
     + crate::math::MathModule
     + crate::pools::PoolsModule
@@ -50,6 +51,8 @@ pub trait HedgingAgentsModule:
         max_oracle_value: BigUint,
     ) -> SCResult<()> {
         self.require_collateral_in_whitelist(&payment_token)?;
+        self.require_collateral_not_deprecated(&payment_token)?;
+        self.require_collateral_not_paused(&payment_token)?;
 
         let collateral_value_in_dollars = self.get_collateral_value_in_dollars(&payment_token)?;
         require!(
@@ -75,12 +78,18 @@ pub trait HedgingAgentsModule:
         );
 
         let collateral_precision = self.get_collateral_precision(&payment_token);
-        let amount_to_cover_in_stablecoin = self.multiply(
-            &collateral_value_in_dollars,
+        let amount_to_cover_in_stablecoin = self.value_in_stablecoin(
             &amount_to_cover,
+            &collateral_value_in_dollars,
             &collateral_precision,
         );
         pool.total_covered_value_in_stablecoin += amount_to_cover_in_stablecoin;
+        let minted_amount = self.stablecoin_minted_for_collateral(&payment_token).get();
+        self.require_under_max_stablecoin_supply(
+            &payment_token,
+            &minted_amount,
+            &pool.total_covered_value_in_stablecoin,
+        )?;
 
         let transaction_fees_percentage =
             self.get_hedging_position_open_transaction_fees_percentage(&payment_token);
@@ -102,6 +111,8 @@ pub trait HedgingAgentsModule:
 
         self.accumulated_tx_fees(&payment_token)
             .update(|accumulated_fees| *accumulated_fees += fees_amount_in_collateral);
+        self.total_hedger_deposits(&payment_token)
+            .update(|total_deposits| *total_deposits += &hedging_position.deposit_amount);
 
         let caller = self.blockchain().get_caller();
         let nft_nonce = self.create_hedging_token();
@@ -132,6 +143,7 @@ pub trait HedgingAgentsModule:
             first_transfer.token_identifier == hedging_token_id,
             "First token should be the hedging NFT"
         );
+        self.require_collateral_not_paused(&second_transfer.token_identifier)?;
 
         let nft_nonce = first_transfer.token_nonce;
         self.hedging_position(nft_nonce).update(|hedging_pos| {
@@ -149,6 +161,8 @@ pub trait HedgingAgentsModule:
         self.update_pool(&second_transfer.token_identifier, |pool| {
             pool.collateral_reserves += &second_transfer.amount;
         });
+        self.total_hedger_deposits(&second_transfer.token_identifier)
+            .update(|total_deposits| *total_deposits += &second_transfer.amount);
 
         // return the nft
         let caller = self.blockchain().get_caller();
@@ -183,6 +197,7 @@ pub trait HedgingAgentsModule:
 
             Ok(hedging_pos.collateral_id.clone())
         })?;
+        self.require_collateral_not_paused(&collateral_id)?;
         self.update_pool(&payment_token, |pool| {
             require!(
                 amount_to_remove <= pool.collateral_reserves,
@@ -193,6 +208,8 @@ pub trait HedgingAgentsModule:
 
             Ok(())
         })?;
+        self.total_hedger_deposits(&collateral_id)
+            .update(|total_deposits| *total_deposits -= &amount_to_remove);
 
         let caller = self.blockchain().get_caller();
         self.send()
@@ -220,9 +237,11 @@ pub trait HedgingAgentsModule:
         self.require_not_liquidated(payment_nonce)?;
 
         let hedging_position = self.hedging_position(payment_nonce).get();
+        self.require_collateral_not_paused(&hedging_position.collateral_id)?;
+
         let withdraw_split = match hedging_position.withdraw_amount_after_force_close {
             Some(withdraw_amount) => self
-                .calculate_withdraw_amounts_split(&hedging_position.collateral_id, withdraw_amount),
+                .calculate_withdraw_amounts_split(&hedging_position.collateral_id, withdraw_amount)?,
             None => {
                 self.close_position(&hedging_position)?;
 
@@ -233,7 +252,7 @@ pub trait HedgingAgentsModule:
                 let split = self.calculate_withdraw_amounts_split(
                     &hedging_position.collateral_id,
                     withdraw_amount.clone(),
-                );
+                )?;
 
                 split
             }
@@ -243,6 +262,8 @@ pub trait HedgingAgentsModule:
             pool.collateral_reserves -= &withdraw_split.collateral_amount;
         });
 
+        self.total_hedger_deposits(&hedging_position.collateral_id)
+            .update(|total_deposits| *total_deposits -= &hedging_position.deposit_amount);
         self.hedging_position(payment_nonce).clear();
         self.burn_hedging_token(payment_nonce);
 
@@ -267,6 +288,21 @@ pub trait HedgingAgentsModule:
         Ok(())
     }
 
+    // remaps positions opened on a migrated collateral to its new token, anyone may call it
+    #[endpoint(migrateHedgingPositions)]
+    fn migrate_hedging_positions(&self, #[var_args] nft_nonces: VarArgs<u64>) {
+        for nft_nonce in nft_nonces.into_vec() {
+            if self.hedging_position(nft_nonce).is_empty() {
+                continue;
+            }
+
+            self.hedging_position(nft_nonce).update(|hedging_pos| {
+                hedging_pos.collateral_id =
+                    self.resolve_migrated_collateral(&hedging_pos.collateral_id);
+            });
+        }
+    }
+
     // private
 
     // deduplicates code for close, force-close and liquidate
@@ -284,9 +320,9 @@ pub trait HedgingAgentsModule:
         );
 
         let collateral_precision = self.get_collateral_precision(&hedging_position.collateral_id);
-        let amount_to_cover_in_stablecoin = self.multiply(
-            &hedging_position.oracle_value_at_deposit_time,
+        let amount_to_cover_in_stablecoin = self.value_in_stablecoin(
             &hedging_position.covered_amount,
+            &hedging_position.oracle_value_at_deposit_time,
             &collateral_precision,
         );
         pool.total_covered_value_in_stablecoin -= amount_to_cover_in_stablecoin;
@@ -363,23 +399,23 @@ pub trait HedgingAgentsModule:
         &self,
         collateral_id: &TokenIdentifier,
         full_withdraw_amount: BigUint,
-    ) -> HedgerRewardAmountsTokensPair<Self::Api> {
+    ) -> SCResult<HedgerRewardAmountsTokensPair<Self::Api>> {
         let reserves = self.get_pool_reserves(collateral_id);
         if full_withdraw_amount <= reserves {
-            HedgerRewardAmountsTokensPair {
+            Ok(HedgerRewardAmountsTokensPair {
                 collateral_amount: full_withdraw_amount,
                 liq_tokens_amount: BigUint::zero(),
-            }
+            })
         } else {
             let collateral_amount_over_reserves = &full_withdraw_amount - &reserves;
             let collateral_precision = self.get_collateral_precision(collateral_id);
             let amount_in_liq_tokens =
-                self.collateral_to_liq_tokens(collateral_id, &collateral_amount_over_reserves, &collateral_precision);
+                self.collateral_to_liq_tokens_with_rewards(collateral_id, &collateral_amount_over_reserves, &collateral_precision)?;
 
-            HedgerRewardAmountsTokensPair {
+            Ok(HedgerRewardAmountsTokensPair {
                 collateral_amount: reserves,
                 liq_tokens_amount: amount_in_liq_tokens,
-            }
+            })
         }
     }
 
@@ -425,6 +461,11 @@ pub trait HedgingAgentsModule:
     #[storage_mapper("hedgingPosition")]
     fn hedging_position(&self, nft_nonce: u64) -> SingleValueMapper<HedgingPosition<Self::Api>>;
 
+    // sum of the open positions' deposits, owed to the hedgers
+    #[view(getTotalHedgerDeposits)]
+    #[storage_mapper("totalHedgerDeposits")]
+    fn total_hedger_deposits(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+
     #[view(getMinHedgingPeriodSeconds)]
     #[storage_mapper("minHedgingPeriodSeconds")]
     fn min_hedging_period_seconds(&self) -> SingleValueMapper<u64>;
@@ -1,28 +1,37 @@
 numbat_wasm::imports!();
 
-use crate::{fees::CurrentFeeConfiguration, hedging_agents::HedgingPosition, math::ONE};
+use crate::{
+    dex_pair_proxy, fees::CurrentFeeConfiguration, hedging_agents::HedgingPosition, math::ONE,
+};
 
 #[numbat_wasm::module]
 pub trait KeepersModule:
-    crate::fees::FeesModule
+    crate::events::EventsModule
+    + crate::fees::FeesModule
     + crate::hedging_agents::HedgingAgentsModule
     + crate::hedging_token::HedgingTokenModule
+    + crate::limit_orders::LimitOrdersModule
     + crate::liquidity_providers::LiquidityProvidersModule
     + crate::liquidity_token::LiquidityTokenModule
     + crate::math::MathModule
     + crate::pools::PoolsModule
     + price_aggregator_proxy::PriceAggregatorModule
+    + crate::referrals::ReferralsModule
+    + crate::stablecoin_token::StablecoinTokenModule
+    + crate::stable_seekers::StableSeekers
     + crate::token_common::TokenCommonModule
 {
     #[endpoint(rebalancePool)]
     fn rebalance_pool(&self, collateral_id: TokenIdentifier) -> SCResult<()> {
         self.require_collateral_in_whitelist(&collateral_id)?;
+        self.require_collateral_not_paused(&collateral_id)?;
 
         let collateral_value_in_dollars = self.get_collateral_value_in_dollars(&collateral_id)?;
         let collateral_precision = self.get_collateral_precision(&collateral_id);
+        self.check_pool_imbalance(&collateral_id, &collateral_value_in_dollars);
 
         self.update_pool(&collateral_id, |pool| {
-            let pool_value_in_dollars = self.multiply(
+            let pool_value_in_dollars = self.value_in_stablecoin(
                 &pool.collateral_amount,
                 &collateral_value_in_dollars,
                 &collateral_precision,
@@ -31,7 +40,7 @@ pub trait KeepersModule:
             // collateral value increased, so we move the extra to reserves
             if pool_value_in_dollars > pool.stablecoin_amount {
                 let extra_collateral_in_dollars = &pool_value_in_dollars - &pool.stablecoin_amount;
-                let extra_collateral_amount = self.divide(
+                let extra_collateral_amount = self.amount_from_stablecoin(
                     &extra_collateral_in_dollars,
                     &collateral_value_in_dollars,
                     &collateral_precision,
@@ -43,7 +52,7 @@ pub trait KeepersModule:
             else {
                 let missing_collateral_in_dollars =
                     &pool.stablecoin_amount - &pool_value_in_dollars;
-                let missing_collateral_amount = self.divide(
+                let missing_collateral_amount = self.amount_from_stablecoin(
                     &missing_collateral_in_dollars,
                     &collateral_value_in_dollars,
                     &collateral_precision,
@@ -77,34 +86,371 @@ pub trait KeepersModule:
             });
     }
 
+    // the fees are split between the liquidity providers, the treasury, the fee recipients
+    // and the reserves
     #[endpoint(splitFees)]
     fn split_fees(&self, collateral_id: TokenIdentifier) -> SCResult<()> {
-        let liq_provider_fee_reward_percentage = self
-            .liq_provider_fee_reward_percentage(&collateral_id)
-            .get();
+        self.split_accumulated_fees(&collateral_id, false)
+    }
+
+    // Same as splitFees, but the liquidity providers', the treasury's and the fee recipients' parts
+    // are first swapped into stablecoins through the collateral's fee swap pair, and paid in
+    // stablecoins. The liquidity providers receive theirs when removing liquidity. The swap must
+    // return the fees' oracle value, less the collateral's fee swap max slippage.
+    // The reserves' part stays in the collateral backing them.
+    #[endpoint(splitFeesSwapped)]
+    fn split_fees_swapped(&self, collateral_id: TokenIdentifier) -> SCResult<()> {
+        require!(
+            !self.fee_swap_pair_address(&collateral_id).is_empty(),
+            "Fee swap pair not set"
+        );
+
+        self.split_accumulated_fees(&collateral_id, true)
+    }
 
-        let accumulated_fees = self.accumulated_tx_fees(&collateral_id).get();
-        let liq_provider_reward =
+    fn split_accumulated_fees(
+        &self,
+        collateral_id: &TokenIdentifier,
+        swap_fees: bool,
+    ) -> SCResult<()> {
+        let liq_provider_fee_reward_percentage =
+            self.liq_provider_fee_reward_percentage(collateral_id).get();
+        let treasury_fee_percentage = self.treasury_fee_percentage(collateral_id).get();
+
+        let accumulated_fees = self.accumulated_tx_fees(collateral_id).get();
+        let mut liq_provider_reward =
             self.calculate_percentage_of(&liq_provider_fee_reward_percentage, &accumulated_fees);
-        let leftover = &accumulated_fees - &liq_provider_reward;
+        let treasury_fees =
+            self.calculate_percentage_of(&treasury_fee_percentage, &accumulated_fees);
+
+        let mut recipients = Vec::new();
+        let mut external_fees = treasury_fees.clone();
+        for (recipient, fee_percentage) in self.fee_recipients(collateral_id).iter() {
+            let recipient_fees = self.calculate_percentage_of(&fee_percentage, &accumulated_fees);
+            if recipient_fees > 0 {
+                external_fees += &recipient_fees;
+                recipients.push((recipient, recipient_fees));
+            }
+        }
+        let leftover = &accumulated_fees - &liq_provider_reward - &external_fees;
+
+        let sft_nonce = self.liq_sft_nonce_for_collateral(collateral_id).get();
+        if swap_fees {
+            require!(
+                treasury_fees == 0 || !self.treasury_address().is_empty(),
+                "Treasury address not set"
+            );
+
+            // without liquidity tokens in circulation, nobody could claim the stablecoins
+            let liq_tokens_amount = self.liq_token_amount_in_circulation(sft_nonce).get();
+            let swapped_liq_provider_reward = if sft_nonce > 0 && liq_tokens_amount > 0 {
+                liq_provider_reward.clone()
+            } else {
+                BigUint::zero()
+            };
+            liq_provider_reward -= &swapped_liq_provider_reward;
+
+            let fees_to_swap = &external_fees + &swapped_liq_provider_reward;
+            if fees_to_swap > 0 {
+                let stablecoin_amount =
+                    self.swap_fees_to_stablecoin(collateral_id, &fees_to_swap)?;
+                let liq_provider_stablecoin_amount =
+                    &stablecoin_amount * &swapped_liq_provider_reward / &fees_to_swap;
+                self.stablecoin_rewards_for_liq_token(sft_nonce)
+                    .update(|rewards| *rewards += &liq_provider_stablecoin_amount);
+
+                self.send_swapped_fees(
+                    collateral_id,
+                    &treasury_fees,
+                    recipients,
+                    &external_fees,
+                    &(stablecoin_amount - liq_provider_stablecoin_amount),
+                );
+            }
+        } else {
+            if treasury_fees > 0 {
+                self.send_treasury_fees(collateral_id, &treasury_fees)?;
+            }
+            for (recipient, recipient_fees) in recipients {
+                self.send()
+                    .direct(&recipient, collateral_id, 0, &recipient_fees, &[]);
+                self.fee_recipient_payment_event(collateral_id, &recipient, &recipient_fees);
+            }
+        }
 
-        let sft_nonce = self.liq_sft_nonce_for_collateral(&collateral_id).get();
         self.collateral_amount_for_liq_token(sft_nonce)
             .update(|amt| *amt += liq_provider_reward);
-        self.update_pool(&collateral_id, |pool| {
-            pool.collateral_reserves += leftover;
+        self.update_pool(collateral_id, |pool| {
+            pool.collateral_reserves += &leftover;
         });
+        self.add_protocol_owned_reserves(collateral_id, &leftover);
 
-        self.accumulated_tx_fees(&collateral_id).clear();
+        self.accumulated_tx_fees(collateral_id).clear();
 
         Ok(())
     }
 
+    // Pays each external part of the swapped fees pro rata.
+    // The rounding dust goes to the treasury, or to the last recipient without a treasury part.
+    fn send_swapped_fees(
+        &self,
+        collateral_id: &TokenIdentifier,
+        treasury_fees: &BigUint,
+        recipients: Vec<(ManagedAddress, BigUint)>,
+        external_fees: &BigUint,
+        stablecoin_amount: &BigUint,
+    ) {
+        let mut remaining_amount = stablecoin_amount.clone();
+        let nr_recipients = recipients.len();
+        for (i, (recipient, recipient_fees)) in recipients.into_iter().enumerate() {
+            let recipient_amount = if *treasury_fees == 0 && i + 1 == nr_recipients {
+                remaining_amount.clone()
+            } else {
+                stablecoin_amount * &recipient_fees / external_fees
+            };
+            remaining_amount -= &recipient_amount;
+
+            self.send_stablecoin(&recipient, &recipient_amount);
+            self.fee_recipient_payment_event(collateral_id, &recipient, &recipient_amount);
+        }
+        if *treasury_fees > 0 {
+            let treasury_address = self.treasury_address().get();
+            self.send_stablecoin(&treasury_address, &remaining_amount);
+            self.treasury_fees_event(collateral_id, &treasury_address, true, &remaining_amount);
+        }
+    }
+
+    // The min amount out is the fees' oracle value less the max slippage, so the swap can't be
+    // sandwiched. The pair sends the stablecoins back to this contract, measured by the balance
+    // difference.
+    fn swap_fees_to_stablecoin(
+        &self,
+        collateral_id: &TokenIdentifier,
+        fees_amount: &BigUint,
+    ) -> SCResult<BigUint> {
+        let collateral_value_in_dollars = self.get_collateral_value_in_dollars(collateral_id)?;
+        let fees_value_in_stablecoin = self.value_in_stablecoin(
+            fees_amount,
+            &collateral_value_in_dollars,
+            &self.get_collateral_precision(collateral_id),
+        );
+        let max_slippage_percentage = self.fee_swap_max_slippage_percentage(collateral_id).get();
+        let amount_out_min = &fees_value_in_stablecoin
+            - &self.calculate_percentage_of(&max_slippage_percentage, &fees_value_in_stablecoin);
+
+        let stablecoin_token_id = self.stablecoin_token_id().get();
+        let balance_before = self.blockchain().get_sc_balance(&stablecoin_token_id, 0);
+
+        self.dex_pair_proxy(self.fee_swap_pair_address(collateral_id).get())
+            .swap_tokens_fixed_input(
+                collateral_id.clone(),
+                fees_amount.clone(),
+                stablecoin_token_id.clone(),
+                amount_out_min.clone(),
+            )
+            .execute_on_dest_context();
+
+        let balance_after = self.blockchain().get_sc_balance(&stablecoin_token_id, 0);
+        require!(balance_after > balance_before, "Fees swap failed");
+        let stablecoin_amount = balance_after - balance_before;
+        require!(stablecoin_amount >= amount_out_min, "Below min amount");
+        self.fees_swapped_event(collateral_id, fees_amount, &stablecoin_amount);
+
+        Ok(stablecoin_amount)
+    }
+
+    // Stablecoins are minted against the fees as if the treasury sold them to the pool,
+    // without paying transaction fees.
+    fn send_treasury_fees(
+        &self,
+        collateral_id: &TokenIdentifier,
+        treasury_fees: &BigUint,
+    ) -> SCResult<()> {
+        require!(
+            !self.treasury_address().is_empty(),
+            "Treasury address not set"
+        );
+
+        let treasury_address = self.treasury_address().get();
+        let paid_in_stablecoin = self.treasury_fees_in_stablecoin(collateral_id).get();
+        if !paid_in_stablecoin {
+            self.send()
+                .direct(&treasury_address, collateral_id, 0, treasury_fees, &[]);
+            self.treasury_fees_event(collateral_id, &treasury_address, false, treasury_fees);
+
+            return Ok(());
+        }
+
+        let collateral_value_in_dollars = self.get_collateral_value_in_dollars(collateral_id)?;
+        let stablecoin_amount = self.value_in_stablecoin(
+            treasury_fees,
+            &collateral_value_in_dollars,
+            &self.get_collateral_precision(collateral_id),
+        );
+        self.update_pool(collateral_id, |pool| {
+            pool.collateral_amount += treasury_fees;
+            pool.stablecoin_amount += &stablecoin_amount;
+        });
+        // the mint limits are left to the users, so they can't block the fees from being split
+        self.add_stablecoin_minted_for_collateral(collateral_id, &stablecoin_amount)?;
+        self.mint_and_send_stablecoin(&treasury_address, &stablecoin_amount)?;
+        self.treasury_fees_event(collateral_id, &treasury_address, true, &stablecoin_amount);
+
+        Ok(())
+    }
+
+    // Trips the collateral's price circuit breaker if the oracle price deviates too much
+    // from the last recorded one. Returns whether it was tripped.
+    #[endpoint(checkPriceDeviation)]
+    fn check_price_deviation(&self, collateral_id: TokenIdentifier) -> SCResult<bool> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        let price = self.fetch_collateral_value_in_dollars(&collateral_id)?;
+        if !self.exceeds_max_price_deviation(&collateral_id, &price) {
+            return Ok(false);
+        }
+
+        self.price_circuit_breaker_tripped(&collateral_id)
+            .set(&true);
+        let last_price = self
+            .get_last_observed_price(&collateral_id)
+            .unwrap_or_default();
+        self.price_circuit_breaker_event(&collateral_id, &last_price, &price);
+
+        Ok(true)
+    }
+
+    // Emits an alert if the oracle price is outside the collateral's sanity band.
+    // Returns whether the price is in the band.
+    #[endpoint(checkPriceBand)]
+    fn check_price_band(&self, collateral_id: TokenIdentifier) -> SCResult<bool> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        let price = self.fetch_collateral_value_in_dollars(&collateral_id)?;
+        if self.is_price_in_band(&collateral_id, &price) {
+            return Ok(true);
+        }
+
+        self.price_out_of_band_event(&collateral_id, &price);
+
+        Ok(false)
+    }
+
+    // Checks that the pool's collateral and reserves cover both the stablecoins
+    // and the hedgers' deposits, and pauses the collateral if they don't.
+    // Returns whether the invariant holds.
+    #[endpoint(checkInvariants)]
+    fn check_invariants(&self, collateral_id: TokenIdentifier) -> SCResult<bool> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        let collateral_value_in_dollars = self.get_collateral_value_in_dollars(&collateral_id)?;
+        let collateral_precision = self.get_collateral_precision(&collateral_id);
+        let pool = self.get_pool(&collateral_id);
+
+        let assets_value_in_dollars = self.value_in_stablecoin(
+            &(&pool.collateral_amount + &pool.collateral_reserves),
+            &collateral_value_in_dollars,
+            &collateral_precision,
+        );
+        let hedger_deposits_in_dollars = self.value_in_stablecoin(
+            &self.total_hedger_deposits(&collateral_id).get(),
+            &collateral_value_in_dollars,
+            &collateral_precision,
+        );
+        let liabilities_value_in_dollars = &pool.stablecoin_amount + &hedger_deposits_in_dollars;
+
+        if assets_value_in_dollars >= liabilities_value_in_dollars {
+            return Ok(true);
+        }
+
+        self.collateral_paused(&collateral_id).set(&true);
+        self.solvency_violation_event(
+            &collateral_id,
+            &assets_value_in_dollars,
+            &liabilities_value_in_dollars,
+        );
+
+        Ok(false)
+    }
+
+    // Moves donations and rounding dust into the reserves.
+    // Hedgers' deposits and liquidity are already part of the reserves.
+    #[endpoint(sweepExcessBalance)]
+    fn sweep_excess_balance(&self, collateral_id: TokenIdentifier) -> SCResult<BigUint> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        let pool = self.get_pool(&collateral_id);
+        let tracked_amount = &pool.collateral_amount
+            + &pool.collateral_reserves
+            + self.accumulated_tx_fees(&collateral_id).get()
+            + self.total_referral_fees(&collateral_id).get()
+            + self.mint_orders_escrow(&collateral_id).get();
+        let balance = self.blockchain().get_sc_balance(&collateral_id, 0);
+        if balance <= tracked_amount {
+            return Ok(BigUint::zero());
+        }
+
+        let excess_amount = balance - tracked_amount;
+        self.update_pool(&collateral_id, |pool| {
+            pool.collateral_reserves += &excess_amount;
+        });
+        self.add_protocol_owned_reserves(&collateral_id, &excess_amount);
+        self.sweep_excess_balance_event(&collateral_id, &excess_amount);
+
+        Ok(excess_amount)
+    }
+
+    // sends the reserves above the target reserve ratio to the treasury,
+    // at most the protocol owned part, so hedgers' and liquidity providers' collateral stays
+    #[endpoint(skimReserves)]
+    fn skim_reserves(&self, collateral_id: TokenIdentifier) -> SCResult<BigUint> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+        self.require_collateral_not_paused(&collateral_id)?;
+        require!(
+            !self.treasury_address().is_empty(),
+            "Treasury address not set"
+        );
+
+        let target_reserve_ratio = self.target_reserve_ratio(&collateral_id).get();
+        require!(target_reserve_ratio > 0, "Target reserve ratio not set");
+
+        let protocol_owned_reserves = self.get_protocol_owned_reserves(&collateral_id);
+        let skim_amount = self.update_pool(&collateral_id, |pool| {
+            let target_reserves =
+                self.calculate_percentage_of(&target_reserve_ratio, &pool.collateral_amount);
+            if pool.collateral_reserves <= target_reserves {
+                return BigUint::zero();
+            }
+
+            let excess_reserves = &pool.collateral_reserves - &target_reserves;
+            let skim_amount = if excess_reserves > protocol_owned_reserves {
+                protocol_owned_reserves
+            } else {
+                excess_reserves
+            };
+            pool.collateral_reserves -= &skim_amount;
+
+            skim_amount
+        });
+        if skim_amount > 0 {
+            self.remove_protocol_owned_reserves(&collateral_id, &skim_amount);
+
+            let treasury_address = self.treasury_address().get();
+            self.send()
+                .direct(&treasury_address, &collateral_id, 0, &skim_amount, &[]);
+            self.skim_reserves_event(&collateral_id, &treasury_address, &skim_amount);
+        }
+
+        Ok(skim_amount)
+    }
+
     #[endpoint(forceCloseHedgingPosition)]
     fn force_close_hedging_position(&self, nft_nonce: u64) -> SCResult<()> {
         self.require_not_liquidated(nft_nonce)?;
 
         let mut hedging_position = self.hedging_position(nft_nonce).get();
+        self.require_collateral_not_paused(&hedging_position.collateral_id)?;
+
         let pool = self.get_pool(&hedging_position.collateral_id);
 
         let limit_hedge_amount = self.calculate_limit_hedge_amount(&pool.collateral_amount);
@@ -128,6 +474,7 @@ pub trait KeepersModule:
 
         let hedging_position = self.hedging_position(nft_nonce).get();
         self.require_not_closed(&hedging_position)?;
+        self.require_collateral_not_paused(&hedging_position.collateral_id)?;
 
         let margin_ratio = self.calculate_margin_ratio(&hedging_position)?;
         let hedging_maintenance_ratio = self
@@ -139,6 +486,8 @@ pub trait KeepersModule:
         );
 
         self.close_position(&hedging_position)?;
+        self.total_hedger_deposits(&hedging_position.collateral_id)
+            .update(|total_deposits| *total_deposits -= &hedging_position.deposit_amount);
         self.hedging_position(nft_nonce).clear();
 
         Ok(())
@@ -173,4 +522,9 @@ pub trait KeepersModule:
 
         Ok(result)
     }
+
+    // proxies
+
+    #[proxy]
+    fn dex_pair_proxy(&self, sc_address: ManagedAddress) -> dex_pair_proxy::Proxy<Self::Api>;
 }
@@ -0,0 +1,224 @@
+numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+use crate::math::PERCENTAGE_PRECISION;
+
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Clone, Copy)]
+pub enum LimitOrderType {
+    // collateral paid, executed once the collateral mint price is at least the target price
+    Mint,
+    // stablecoins paid, executed once the collateral price is at most the target price
+    Redeem,
+}
+
+#[derive(TypeAbi, TopEncode, TopDecode)]
+pub struct LimitOrder<M: ManagedTypeApi> {
+    pub owner: ManagedAddress<M>,
+    pub order_type: LimitOrderType,
+    pub collateral_id: TokenIdentifier<M>,
+    pub payment_amount: BigUint<M>,
+    pub target_price: BigUint<M>,
+}
+
+#[numbat_wasm::module]
+pub trait LimitOrdersModule:
+    crate::events::EventsModule
+    + crate::fees::FeesModule
+    + crate::math::MathModule
+    + crate::pools::PoolsModule
+    + price_aggregator_proxy::PriceAggregatorModule
+    + crate::referrals::ReferralsModule
+    + crate::rewa_wrapper::RewaWrapperModule
+    + crate::stablecoin_token::StablecoinTokenModule
+    + crate::stable_seekers::StableSeekers
+    + crate::token_common::TokenCommonModule
+{
+    // the part of the order's payment that goes to the keeper executing it
+    #[only_owner]
+    #[endpoint(setLimitOrderTipPercentage)]
+    fn set_limit_order_tip_percentage(&self, tip_percentage: BigUint) -> SCResult<()> {
+        require!(
+            tip_percentage < PERCENTAGE_PRECISION,
+            "Invalid tip percentage"
+        );
+
+        self.limit_order_tip_percentage().set(&tip_percentage);
+
+        Ok(())
+    }
+
+    #[payable("*")]
+    #[endpoint(placeMintOrder)]
+    fn place_mint_order(
+        &self,
+        #[payment_token] payment_token: TokenIdentifier,
+        #[payment_amount] payment_amount: BigUint,
+        target_price: BigUint,
+    ) -> SCResult<u64> {
+        self.require_collateral_in_whitelist(&payment_token)?;
+        self.require_collateral_not_deprecated(&payment_token)?;
+        require!(payment_amount > 0, "Invalid payment amount");
+
+        self.mint_orders_escrow(&payment_token)
+            .update(|escrow| *escrow += &payment_amount);
+
+        Ok(self.store_limit_order(LimitOrder {
+            owner: self.blockchain().get_caller(),
+            order_type: LimitOrderType::Mint,
+            collateral_id: payment_token,
+            payment_amount,
+            target_price,
+        }))
+    }
+
+    #[payable("*")]
+    #[endpoint(placeRedeemOrder)]
+    fn place_redeem_order(
+        &self,
+        #[payment_token] payment_token: TokenIdentifier,
+        #[payment_amount] payment_amount: BigUint,
+        collateral_id: TokenIdentifier,
+        target_price: BigUint,
+    ) -> SCResult<u64> {
+        require!(
+            payment_token == self.stablecoin_token_id().get(),
+            "May only pay with stablecoins"
+        );
+        self.require_collateral_in_whitelist(&collateral_id)?;
+        require!(payment_amount > 0, "Invalid payment amount");
+
+        Ok(self.store_limit_order(LimitOrder {
+            owner: self.blockchain().get_caller(),
+            order_type: LimitOrderType::Redeem,
+            collateral_id,
+            payment_amount,
+            target_price,
+        }))
+    }
+
+    // Executes the order if the price it's filled at crossed the target price.
+    // Mint orders are filled at the mint price, the lower of the spot price and the TWAP.
+    // The caller receives the tip, in the order's payment token.
+    #[endpoint(executeLimitOrder)]
+    fn execute_limit_order(&self, order_id: u64) -> SCResult<()> {
+        require!(
+            !self.limit_orders(order_id).is_empty(),
+            "Unknown limit order"
+        );
+
+        let mut order = self.limit_orders(order_id).get();
+        order.collateral_id = self.resolve_migrated_collateral(&order.collateral_id);
+        self.require_collateral_in_whitelist(&order.collateral_id)?;
+
+        let tip_percentage = self.limit_order_tip_percentage().get();
+        let tip_amount = self.calculate_percentage_of(&tip_percentage, &order.payment_amount);
+        let swap_amount = &order.payment_amount - &tip_amount;
+
+        let payment_token = match order.order_type {
+            LimitOrderType::Mint => {
+                self.mint_orders_escrow(&order.collateral_id)
+                    .update(|escrow| *escrow -= &order.payment_amount);
+                let (stablecoin_amount, mint_price) = self.add_collateral_to_pool(
+                    &order.owner,
+                    &order.collateral_id,
+                    &swap_amount,
+                    &None,
+                )?;
+                require!(mint_price >= order.target_price, "Target price not reached");
+                self.add_minted_amount(&stablecoin_amount)?;
+                self.mint_and_send_stablecoin(&order.owner, &stablecoin_amount)?;
+
+                order.collateral_id.clone()
+            }
+            LimitOrderType::Redeem => {
+                let (collateral_amount, fees_amount_in_collateral, price) =
+                    self.calculate_redeem_quote(&order.owner, &order.collateral_id, &swap_amount)?;
+                require!(price <= order.target_price, "Target price not reached");
+                self.require_min_redeem_amount(&order.collateral_id, &collateral_amount)?;
+                self.redeem_from_pool(
+                    &order.owner,
+                    &order.collateral_id,
+                    &swap_amount,
+                    &collateral_amount,
+                    fees_amount_in_collateral,
+                    price,
+                    &None,
+                )?;
+                self.send_collateral(
+                    &order.owner,
+                    &order.collateral_id,
+                    &collateral_amount,
+                    false,
+                );
+
+                self.stablecoin_token_id().get()
+            }
+        };
+
+        self.limit_orders(order_id).clear();
+        if tip_amount > 0 {
+            let caller = self.blockchain().get_caller();
+            self.send()
+                .direct(&caller, &payment_token, 0, &tip_amount, &[]);
+        }
+
+        Ok(())
+    }
+
+    #[endpoint(cancelLimitOrder)]
+    fn cancel_limit_order(&self, order_id: u64) -> SCResult<()> {
+        require!(
+            !self.limit_orders(order_id).is_empty(),
+            "Unknown limit order"
+        );
+
+        let order = self.limit_orders(order_id).get();
+        let caller = self.blockchain().get_caller();
+        require!(caller == order.owner, "Only the order owner may cancel");
+
+        self.limit_orders(order_id).clear();
+        let payment_token = match order.order_type {
+            LimitOrderType::Mint => {
+                let collateral_id = self.resolve_migrated_collateral(&order.collateral_id);
+                self.mint_orders_escrow(&collateral_id)
+                    .update(|escrow| *escrow -= &order.payment_amount);
+
+                collateral_id
+            }
+            LimitOrderType::Redeem => self.stablecoin_token_id().get(),
+        };
+        self.send()
+            .direct(&caller, &payment_token, 0, &order.payment_amount, &[]);
+
+        Ok(())
+    }
+
+    // returns the new order's id
+    fn store_limit_order(&self, order: LimitOrder<Self::Api>) -> u64 {
+        let order_id = self.last_limit_order_id().update(|id| {
+            *id += 1;
+            *id
+        });
+        self.limit_orders(order_id).set(&order);
+
+        order_id
+    }
+
+    // storage
+
+    #[view(getLimitOrderTipPercentage)]
+    #[storage_mapper("limitOrderTipPercentage")]
+    fn limit_order_tip_percentage(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("lastLimitOrderId")]
+    fn last_limit_order_id(&self) -> SingleValueMapper<u64>;
+
+    #[view(getLimitOrder)]
+    #[storage_mapper("limitOrders")]
+    fn limit_orders(&self, order_id: u64) -> SingleValueMapper<LimitOrder<Self::Api>>;
+
+    // collateral held for the open mint orders
+    #[view(getMintOrdersEscrow)]
+    #[storage_mapper("mintOrdersEscrow")]
+    fn mint_orders_escrow(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+}
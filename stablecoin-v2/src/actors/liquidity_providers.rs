@@ -9,6 +9,7 @@ pub trait LiquidityProvidersModule:
     + crate::math::MathModule
     + crate::pools::PoolsModule
     + price_aggregator_proxy::PriceAggregatorModule
+    + crate::stablecoin_token::StablecoinTokenModule
     + crate::token_common::TokenCommonModule
 {
     #[payable("*")]
@@ -19,14 +20,19 @@ pub trait LiquidityProvidersModule:
         #[payment_amount] payment_amount: BigUint,
     ) -> SCResult<()> {
         self.require_collateral_in_whitelist(&payment_token)?;
+        self.require_collateral_not_deprecated(&payment_token)?;
+        self.require_collateral_not_paused(&payment_token)?;
 
         self.update_pool(&payment_token, |pool| {
             pool.collateral_reserves += &payment_amount;
         });
 
         let collateral_precision = self.get_collateral_precision(&payment_token);
-        let amount_in_liq_tokens =
-            self.collateral_to_liq_tokens(&payment_token, &payment_amount, &collateral_precision);
+        let amount_in_liq_tokens = self.collateral_to_liq_tokens_with_rewards(
+            &payment_token,
+            &payment_amount,
+            &collateral_precision,
+        )?;
         let sft_nonce = self.create_or_mint_liq_tokens(&payment_token, &amount_in_liq_tokens);
 
         self.collateral_amount_for_liq_token(sft_nonce)
@@ -53,6 +59,8 @@ pub trait LiquidityProvidersModule:
         );
 
         let collateral_id = self.collateral_for_liq_sft_nonce(payment_nonce).get();
+        self.require_collateral_not_paused(&collateral_id)?;
+
         let collateral_precision = self.get_collateral_precision(&collateral_id);
         let amount_in_collateral =
             self.liq_tokens_to_collateral(&collateral_id, &payment_amount, &collateral_precision);
@@ -75,12 +83,21 @@ pub trait LiquidityProvidersModule:
             Ok(())
         })?;
 
+        let caller = self.blockchain().get_caller();
+        let stablecoin_rewards = self.stablecoin_rewards_for_liq_token(payment_nonce).get();
+        if stablecoin_rewards > 0 {
+            let liq_tokens_amount = self.liq_token_amount_in_circulation(payment_nonce).get();
+            let stablecoin_reward = &stablecoin_rewards * &payment_amount / &liq_tokens_amount;
+            self.stablecoin_rewards_for_liq_token(payment_nonce)
+                .set(&(stablecoin_rewards - &stablecoin_reward));
+            self.send_stablecoin(&caller, &stablecoin_reward);
+        }
+
         self.burn_liq_tokens(payment_nonce, &payment_amount);
         // have to deduct amount without slippage here to mantain the liq token price
         self.collateral_amount_for_liq_token(payment_nonce)
             .update(|collateral_amount| *collateral_amount -= &amount_in_collateral);
 
-        let caller = self.blockchain().get_caller();
         self.send().direct(
             &caller,
             &collateral_id,
@@ -92,6 +109,37 @@ pub trait LiquidityProvidersModule:
         Ok(())
     }
 
+    // The stablecoin rewards are part of the liquidity tokens' value, so new liquidity tokens
+    // are priced with them, and get no share of the rewards paid before they were minted.
+    fn collateral_to_liq_tokens_with_rewards(
+        &self,
+        collateral_id: &TokenIdentifier,
+        collateral_amount: &BigUint,
+        collateral_precision: &BigUint,
+    ) -> SCResult<BigUint> {
+        let sft_nonce = self.liq_sft_nonce_for_collateral(collateral_id).get();
+        let stablecoin_rewards = self.stablecoin_rewards_for_liq_token(sft_nonce).get();
+        if sft_nonce == 0 || stablecoin_rewards == 0 {
+            return Ok(self.collateral_to_liq_tokens(
+                collateral_id,
+                collateral_amount,
+                collateral_precision,
+            ));
+        }
+
+        let collateral_value_in_dollars = self.get_collateral_value_in_dollars(collateral_id)?;
+        let rewards_in_collateral = self.amount_from_stablecoin(
+            &stablecoin_rewards,
+            &collateral_value_in_dollars,
+            collateral_precision,
+        );
+        let liq_tokens_value_in_collateral =
+            self.collateral_amount_for_liq_token(sft_nonce).get() + rewards_in_collateral;
+        let liq_tokens_amount = self.liq_token_amount_in_circulation(sft_nonce).get();
+
+        Ok(collateral_amount * &liq_tokens_amount / &liq_tokens_value_in_collateral)
+    }
+
     #[view(getLiquidityTokenValueInCollateral)]
     fn get_liquidity_token_value_in_collateral_view(
         &self,
@@ -101,6 +149,39 @@ pub trait LiquidityProvidersModule:
         self.get_liq_token_value_in_collateral(&collateral_id, &collateral_precision)
     }
 
+    // The value of one liquidity SFT unit, scaled by the collateral's precision,
+    // in collateral and in dollars. Zero if no liquidity tokens are in circulation.
+    #[view(getLiquidityTokenVirtualPrice)]
+    fn get_liquidity_token_virtual_price(
+        &self,
+        collateral_id: TokenIdentifier,
+    ) -> SCResult<MultiResult2<BigUint, BigUint>> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        let sft_nonce = self.liq_sft_nonce_for_collateral(&collateral_id).get();
+        let liq_tokens_amount = self.liq_token_amount_in_circulation(sft_nonce).get();
+        if sft_nonce == 0 || liq_tokens_amount == 0 {
+            return Ok((BigUint::zero(), BigUint::zero()).into());
+        }
+
+        let collateral_precision = self.get_collateral_precision(&collateral_id);
+        let collateral_amount = self.collateral_amount_for_liq_token(sft_nonce).get();
+        let value_in_collateral = self.divide(
+            &collateral_amount,
+            &liq_tokens_amount,
+            &collateral_precision,
+        );
+
+        let collateral_value_in_dollars = self.peek_collateral_value_in_dollars(&collateral_id)?;
+        let value_in_dollars = self.value_in_stablecoin(
+            &value_in_collateral,
+            &collateral_value_in_dollars,
+            &collateral_precision,
+        );
+
+        Ok((value_in_collateral, value_in_dollars).into())
+    }
+
     #[view(getSlippagePercentage)]
     fn calculate_slippage_percentage(&self, collateral_id: &TokenIdentifier) -> BigUint {
         let hedging_ratio = self.get_current_hedging_ratio(collateral_id);
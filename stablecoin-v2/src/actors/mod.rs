@@ -1,4 +1,5 @@
 pub mod hedging_agents;
 pub mod keepers;
+pub mod limit_orders;
 pub mod liquidity_providers;
 pub mod stable_seekers;
@@ -1,14 +1,68 @@
 numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+use crate::events::SwapEventData;
+
+const MAX_BASIS_POINTS: u64 = 10_000;
+
+// stablecoins held by the contract until the pool has enough collateral to fill the request
+#[derive(TypeAbi, TopEncode, TopDecode)]
+pub struct RedemptionRequest<M: ManagedTypeApi> {
+    pub caller: ManagedAddress<M>,
+    pub receiver: ManagedAddress<M>,
+    pub collateral_id: TokenIdentifier<M>,
+    pub stablecoin_amount: BigUint<M>,
+    pub min_amount_out: BigUint<M>,
+    pub unwrap_rewa: bool,
+    pub referrer: Option<ManagedAddress<M>>,
+}
 
 #[numbat_wasm::module]
 pub trait StableSeekers:
-    crate::fees::FeesModule
+    crate::events::EventsModule
+    + crate::fees::FeesModule
     + crate::math::MathModule
     + crate::pools::PoolsModule
     + price_aggregator_proxy::PriceAggregatorModule
+    + crate::referrals::ReferralsModule
+    + crate::rewa_wrapper::RewaWrapperModule
     + crate::stablecoin_token::StablecoinTokenModule
     + crate::token_common::TokenCommonModule
 {
+    // zero means no limit
+    #[only_owner]
+    #[endpoint(setMintRateLimits)]
+    fn set_mint_rate_limits(&self, max_mint_per_block: BigUint, max_mint_per_epoch: BigUint) {
+        self.max_mint_per_block().set(&max_mint_per_block);
+        self.max_mint_per_epoch().set(&max_mint_per_epoch);
+    }
+
+    // both amounts are in collateral, the paid amount for mints and the received amount for redeems.
+    // Zero means no minimum.
+    #[only_owner]
+    #[endpoint(setMinTradeAmounts)]
+    fn set_min_trade_amounts(
+        &self,
+        collateral_id: TokenIdentifier,
+        min_mint_amount: BigUint,
+        min_redeem_amount: BigUint,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        self.min_mint_amount(&collateral_id).set(&min_mint_amount);
+        self.min_redeem_amount(&collateral_id)
+            .set(&min_redeem_amount);
+
+        Ok(())
+    }
+
+    // REWA payments are wrapped first.
+    // The optional referrer receives a part of the transaction fees.
+    // The optional (expected price, max slippage in basis points) pair reverts the swap
+    // if the mint price used, the lower of the spot price and the TWAP, is lower than the expected
+    // price by more than the slippage.
+    // The stablecoins are sent to the optional receiver, or to the caller by default.
+    // Each option may be left out with an empty argument, so any of them can be set on its own.
     #[payable("*")]
     #[endpoint(sellCollateral)]
     fn sell_collateral(
@@ -16,31 +70,76 @@ pub trait StableSeekers:
         #[payment_token] payment_token: TokenIdentifier,
         #[payment_amount] payment_amount: BigUint,
         min_amount_out: BigUint,
+        deadline: u64,
+        #[var_args] opt_referrer: OptionalArg<Option<ManagedAddress>>,
+        #[var_args] opt_max_slippage: OptionalArg<Option<(BigUint, u64)>>,
+        #[var_args] opt_receiver: OptionalArg<Option<ManagedAddress>>,
     ) -> SCResult<()> {
-        self.require_collateral_in_whitelist(&payment_token)?;
+        self.require_deadline_not_passed(deadline)?;
+        let collateral_id = self.resolve_rewa_collateral(payment_token.clone())?;
+        self.require_collateral_in_whitelist(&collateral_id)?;
 
-        let collateral_value_in_dollars = self.get_collateral_value_in_dollars(&payment_token)?;
-        let transaction_fees_percentage = self.get_mint_transaction_fees_percentage(&payment_token);
-        let fees_amount_in_collateral =
-            self.calculate_percentage_of(&transaction_fees_percentage, &payment_amount);
-        let collateral_amount = &payment_amount - &fees_amount_in_collateral;
+        let caller = self.blockchain().get_caller();
+        let opt_referrer = opt_referrer.into_option().flatten();
+        self.require_valid_referrer(&opt_referrer, &caller)?;
 
-        let stablecoin_amount = &collateral_value_in_dollars * &collateral_amount;
+        if payment_token.is_rewa() {
+            self.wrap_rewa(&payment_amount);
+        }
+
+        let (stablecoin_amount, price) =
+            self.add_collateral_to_pool(&caller, &collateral_id, &payment_amount, &opt_referrer)?;
         require!(stablecoin_amount >= min_amount_out, "Below min amount");
+        if let Some((expected_price, max_slippage_bps)) = opt_max_slippage.into_option().flatten() {
+            let min_price = self.apply_slippage(&expected_price, max_slippage_bps, false)?;
+            require!(price >= min_price, "Price slippage exceeded");
+        }
+        self.add_minted_amount(&stablecoin_amount)?;
 
-        self.update_pool(&payment_token, |pool| {
-            pool.collateral_amount += &collateral_amount;
-            pool.stablecoin_amount += &stablecoin_amount;
-        });
-        self.accumulated_tx_fees(&payment_token)
-            .update(|accumulated_fees| *accumulated_fees += fees_amount_in_collateral);
+        let receiver = opt_receiver.into_option().flatten().unwrap_or(caller);
+        self.mint_and_send_stablecoin(&receiver, &stablecoin_amount)
+    }
+
+    // each payment is added to its collateral's pool, and the combined stablecoin amount is minted
+    #[payable("*")]
+    #[endpoint(sellCollateralMulti)]
+    fn sell_collateral_multi(&self, min_amount_out: BigUint, deadline: u64) -> SCResult<()> {
+        self.require_deadline_not_passed(deadline)?;
+
+        let transfers = self.call_value().all_dcdt_transfers();
+        require!(!transfers.is_empty(), "No payments");
 
         let caller = self.blockchain().get_caller();
-        self.mint_and_send_stablecoin(&caller, &stablecoin_amount);
+        let mut stablecoin_amount = BigUint::zero();
+        for transfer in transfers.into_iter() {
+            require!(transfer.token_nonce == 0, "Invalid payment token");
+            self.require_collateral_in_whitelist(&transfer.token_identifier)?;
 
-        Ok(())
+            let (transfer_stablecoin_amount, _) = self.add_collateral_to_pool(
+                &caller,
+                &transfer.token_identifier,
+                &transfer.amount,
+                &None,
+            )?;
+            stablecoin_amount += transfer_stablecoin_amount;
+        }
+        require!(stablecoin_amount >= min_amount_out, "Below min amount");
+        self.add_minted_amount(&stablecoin_amount)?;
+
+        self.mint_and_send_stablecoin(&caller, &stablecoin_amount)
     }
 
+    // if the pool doesn't have enough collateral, the stablecoins are kept
+    // and a redemption request is queued instead, unless partial fill is set.
+    // Buying REWA unwraps the wrapped REWA collateral.
+    // The optional referrer receives a part of the transaction fees.
+    // The optional (expected price, max slippage in basis points) pair reverts the swap
+    // if the collateral price used is higher than the expected price by more than the slippage.
+    // The collateral is sent to the optional receiver, or to the caller by default.
+    // With the optional partial fill flag set, the pool's remaining collateral is bought
+    // instead of queueing a request, and the unspent stablecoins are refunded to the caller.
+    // The min amount out is then scaled down to the share of the payment that was spent.
+    // Each option may be left out with an empty argument, so any of them can be set on its own.
     #[payable("*")]
     #[endpoint(buyCollateral)]
     fn buy_collateral(
@@ -49,47 +148,610 @@ pub trait StableSeekers:
         #[payment_amount] payment_amount: BigUint,
         collateral_id: TokenIdentifier,
         min_amount_out: BigUint,
+        deadline: u64,
+        #[var_args] opt_referrer: OptionalArg<Option<ManagedAddress>>,
+        #[var_args] opt_max_slippage: OptionalArg<Option<(BigUint, u64)>>,
+        #[var_args] opt_receiver: OptionalArg<Option<ManagedAddress>>,
+        #[var_args] opt_partial_fill: OptionalArg<bool>,
     ) -> SCResult<()> {
+        self.require_deadline_not_passed(deadline)?;
+
         let stablecoin_token_id = self.stablecoin_token_id().get();
         require!(
             payment_token == stablecoin_token_id,
             "May only pay with stablecoins"
         );
+        let unwrap_rewa = collateral_id.is_rewa();
+        let collateral_id = self.resolve_rewa_collateral(collateral_id)?;
         self.require_collateral_in_whitelist(&collateral_id)?;
 
-        let collateral_value_in_dollars = self.get_collateral_value_in_dollars(&collateral_id)?;
-        let total_value_in_collateral = &payment_amount / &collateral_value_in_dollars;
-        let transaction_fees_percentage = self.get_burn_transaction_fees_percentage(&collateral_id);
+        let caller = self.blockchain().get_caller();
+        let (mut collateral_amount, mut fees_amount_in_collateral, price) =
+            self.calculate_redeem_quote(&caller, &collateral_id, &payment_amount)?;
+        require!(collateral_amount >= min_amount_out, "Below min amount");
+        self.require_min_redeem_amount(&collateral_id, &collateral_amount)?;
+
+        if let Some((expected_price, max_slippage_bps)) = opt_max_slippage.into_option().flatten() {
+            let max_price = self.apply_slippage(&expected_price, max_slippage_bps, true)?;
+            require!(price <= max_price, "Price slippage exceeded");
+        }
+
+        let opt_referrer = opt_referrer.into_option().flatten();
+        self.require_valid_referrer(&opt_referrer, &caller)?;
+        let receiver = opt_receiver
+            .into_option()
+            .flatten()
+            .unwrap_or_else(|| caller.clone());
+        let partial_fill = opt_partial_fill.into_option().unwrap_or_default();
+
+        let mut stablecoin_amount = payment_amount.clone();
+        let pool_collateral_amount = self.get_pool_collateral_amount(&collateral_id);
+        if pool_collateral_amount < collateral_amount {
+            if !partial_fill {
+                self.queue_redemption_request(RedemptionRequest {
+                    caller,
+                    receiver,
+                    collateral_id,
+                    stablecoin_amount: payment_amount,
+                    min_amount_out,
+                    unwrap_rewa,
+                    referrer: opt_referrer,
+                })?;
+
+                return Ok(());
+            }
+
+            require!(pool_collateral_amount > 0, "Insufficient funds for swap");
+
+            // smaller trades don't pay higher fees, so the stablecoins needed are at most proportional
+            stablecoin_amount = &payment_amount * &pool_collateral_amount / &collateral_amount;
+            let (partial_collateral_amount, partial_fees_amount, _) =
+                self.calculate_redeem_quote(&caller, &collateral_id, &stablecoin_amount)?;
+            collateral_amount = if partial_collateral_amount > pool_collateral_amount {
+                pool_collateral_amount
+            } else {
+                partial_collateral_amount
+            };
+            fees_amount_in_collateral = partial_fees_amount;
+            // the min amount applies to the filled share of the payment
+            let min_partial_amount_out = &min_amount_out * &stablecoin_amount / &payment_amount;
+            require!(
+                collateral_amount >= min_partial_amount_out,
+                "Below min amount"
+            );
+            self.require_min_redeem_amount(&collateral_id, &collateral_amount)?;
+
+            self.send_stablecoin(&caller, &(&payment_amount - &stablecoin_amount));
+        }
+
+        self.redeem_from_pool(
+            &caller,
+            &collateral_id,
+            &stablecoin_amount,
+            &collateral_amount,
+            fees_amount_in_collateral,
+            price,
+            &opt_referrer,
+        )?;
+        self.send_collateral(&receiver, &collateral_id, &collateral_amount, unwrap_rewa);
+
+        Ok(())
+    }
+
+    // Fills the queued requests in order, at the current price, until one can't be covered by the pool.
+    // Requests that would now receive less than their min amount are refunded.
+    // Returns the number of filled requests.
+    #[endpoint(fillRedemptionRequests)]
+    fn fill_redemption_requests(
+        &self,
+        collateral_id: TokenIdentifier,
+        max_requests: usize,
+    ) -> SCResult<usize> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+        self.require_collateral_not_paused(&collateral_id)?;
+
+        let request_ids: Vec<u64> = self
+            .redemption_queue(&collateral_id)
+            .iter()
+            .take(max_requests)
+            .collect();
+        let mut nr_filled = 0;
+        for request_id in request_ids {
+            let request = self.redemption_requests(request_id).get();
+            let (collateral_amount, fees_amount_in_collateral, price) = self
+                .calculate_redeem_quote(
+                    &request.caller,
+                    &collateral_id,
+                    &request.stablecoin_amount,
+                )?;
+
+            if collateral_amount < request.min_amount_out {
+                self.remove_redemption_request(&collateral_id, request_id);
+                self.send_stablecoin(&request.caller, &request.stablecoin_amount);
+                continue;
+            }
+            if self.get_pool_collateral_amount(&collateral_id) < collateral_amount {
+                break;
+            }
+
+            self.redeem_from_pool(
+                &request.caller,
+                &collateral_id,
+                &request.stablecoin_amount,
+                &collateral_amount,
+                fees_amount_in_collateral,
+                price,
+                &request.referrer,
+            )?;
+            self.remove_redemption_request(&collateral_id, request_id);
+            self.send_collateral(
+                &request.receiver,
+                &collateral_id,
+                &collateral_amount,
+                request.unwrap_rewa,
+            );
+
+            nr_filled += 1;
+        }
+
+        Ok(nr_filled)
+    }
+
+    #[endpoint(cancelRedemptionRequest)]
+    fn cancel_redemption_request(&self, request_id: u64) -> SCResult<()> {
+        require!(
+            !self.redemption_requests(request_id).is_empty(),
+            "Unknown redemption request"
+        );
+
+        let request = self.redemption_requests(request_id).get();
+        let caller = self.blockchain().get_caller();
+        require!(caller == request.caller, "Only the requester may cancel");
+
+        self.remove_redemption_request(&request.collateral_id, request_id);
+        self.send_stablecoin(&caller, &request.stablecoin_amount);
+
+        Ok(())
+    }
+
+    #[view(getRedemptionQueue)]
+    fn get_redemption_queue(&self, collateral_id: TokenIdentifier) -> MultiResultVec<u64> {
+        let request_ids: Vec<u64> = self.redemption_queue(&collateral_id).iter().collect();
+
+        request_ids.into()
+    }
+
+    // sells the paid collateral for stablecoins and buys the other collateral with them,
+    // both legs' fees are charged. The stablecoins are minted and burned within the call,
+    // so the swap goes through the same pool accounting and mint limits as the two separate trades.
+    #[payable("*")]
+    #[endpoint(swapCollateral)]
+    fn swap_collateral(
+        &self,
+        #[payment_token] payment_token: TokenIdentifier,
+        #[payment_amount] from_amount: BigUint,
+        to_collateral_id: TokenIdentifier,
+        min_out: BigUint,
+        deadline: u64,
+    ) -> SCResult<()> {
+        self.require_deadline_not_passed(deadline)?;
+        require!(
+            payment_token != to_collateral_id,
+            "Cannot swap to the same collateral"
+        );
+        self.require_collateral_in_whitelist(&payment_token)?;
+        self.require_collateral_in_whitelist(&to_collateral_id)?;
+
+        let caller = self.blockchain().get_caller();
+        let (stablecoin_amount, _) =
+            self.add_collateral_to_pool(&caller, &payment_token, &from_amount, &None)?;
+        self.add_minted_amount(&stablecoin_amount)?;
+        self.mint_stablecoin(&stablecoin_amount)?;
+
+        let (to_collateral_amount, to_fees_amount, to_price) =
+            self.calculate_redeem_quote(&caller, &to_collateral_id, &stablecoin_amount)?;
+        require!(to_collateral_amount >= min_out, "Below min amount");
+        self.require_min_redeem_amount(&to_collateral_id, &to_collateral_amount)?;
+
+        self.redeem_from_pool(
+            &caller,
+            &to_collateral_id,
+            &stablecoin_amount,
+            &to_collateral_amount,
+            to_fees_amount,
+            to_price,
+            &None,
+        )?;
+        self.send()
+            .direct(&caller, &to_collateral_id, 0, &to_collateral_amount, &[]);
+
+        Ok(())
+    }
+
+    /// Expected result of sellCollateral for the given collateral amount, before slippage:
+    /// (stablecoin amount received, fees in collateral, collateral value in dollars)
+    /// The optional payer's fee discount is applied.
+    #[view(getMintQuote)]
+    fn get_mint_quote(
+        &self,
+        collateral_id: TokenIdentifier,
+        amount: BigUint,
+        #[var_args] opt_payer: OptionalArg<ManagedAddress>,
+    ) -> SCResult<MultiResult3<BigUint, BigUint, BigUint>> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        let payer = opt_payer.into_option().unwrap_or_else(ManagedAddress::zero);
+        let spot_value_in_dollars = self.peek_collateral_value_in_dollars(&collateral_id)?;
+        Ok(self
+            .calculate_mint_quote_at_price(&payer, &collateral_id, &amount, spot_value_in_dollars)
+            .into())
+    }
+
+    /// Expected result of buyCollateral for the given stablecoin amount, before slippage:
+    /// (collateral amount received, fees in collateral, collateral value in dollars)
+    /// The optional payer's fee discount is applied.
+    #[view(getRedeemQuote)]
+    fn get_redeem_quote(
+        &self,
+        collateral_id: TokenIdentifier,
+        stablecoin_amount: BigUint,
+        #[var_args] opt_payer: OptionalArg<ManagedAddress>,
+    ) -> SCResult<MultiResult3<BigUint, BigUint, BigUint>> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        let payer = opt_payer.into_option().unwrap_or_else(ManagedAddress::zero);
+        let collateral_value_in_dollars = self.peek_collateral_value_in_dollars(&collateral_id)?;
+        Ok(self
+            .calculate_redeem_quote_at_price(
+                &payer,
+                &collateral_id,
+                &stablecoin_amount,
+                collateral_value_in_dollars,
+            )
+            .into())
+    }
+
+    // the fees are computed from the current hedging ratio instead of the last updateFeesPercentage,
+    // so swaps never execute against stale fees. The payer's fee discount is applied last.
+    fn calculate_mint_quote(
+        &self,
+        payer: &ManagedAddress,
+        collateral_id: &TokenIdentifier,
+        payment_amount: &BigUint,
+    ) -> SCResult<(BigUint, BigUint, BigUint)> {
+        let spot_value_in_dollars = self.get_collateral_value_in_dollars(collateral_id)?;
+
+        Ok(self.calculate_mint_quote_at_price(
+            payer,
+            collateral_id,
+            payment_amount,
+            spot_value_in_dollars,
+        ))
+    }
+
+    fn calculate_mint_quote_at_price(
+        &self,
+        payer: &ManagedAddress,
+        collateral_id: &TokenIdentifier,
+        payment_amount: &BigUint,
+        spot_value_in_dollars: BigUint,
+    ) -> (BigUint, BigUint, BigUint) {
+        let collateral_value_in_dollars = self.get_mint_price(collateral_id, spot_value_in_dollars);
+        let transaction_fees_percentage = self.add_price_impact_fees_percentage(
+            collateral_id,
+            self.calculate_mint_transaction_fees_percentage(collateral_id),
+            payment_amount,
+        );
+        let transaction_fees_percentage =
+            self.apply_fee_discount(payer, transaction_fees_percentage);
+        let fees_amount_in_collateral =
+            self.calculate_percentage_of(&transaction_fees_percentage, payment_amount);
+        let collateral_amount = payment_amount - &fees_amount_in_collateral;
+
+        let collateral_precision = self.get_collateral_precision(collateral_id);
+        let stablecoin_amount = self.value_in_stablecoin(
+            &collateral_amount,
+            &collateral_value_in_dollars,
+            &collateral_precision,
+        );
+
+        (
+            stablecoin_amount,
+            fees_amount_in_collateral,
+            collateral_value_in_dollars,
+        )
+    }
+
+    fn calculate_redeem_quote(
+        &self,
+        payer: &ManagedAddress,
+        collateral_id: &TokenIdentifier,
+        stablecoin_amount: &BigUint,
+    ) -> SCResult<(BigUint, BigUint, BigUint)> {
+        let collateral_value_in_dollars = self.get_collateral_value_in_dollars(collateral_id)?;
+
+        Ok(self.calculate_redeem_quote_at_price(
+            payer,
+            collateral_id,
+            stablecoin_amount,
+            collateral_value_in_dollars,
+        ))
+    }
+
+    fn calculate_redeem_quote_at_price(
+        &self,
+        payer: &ManagedAddress,
+        collateral_id: &TokenIdentifier,
+        stablecoin_amount: &BigUint,
+        collateral_value_in_dollars: BigUint,
+    ) -> (BigUint, BigUint, BigUint) {
+        let collateral_precision = self.get_collateral_precision(collateral_id);
+        let total_value_in_collateral = self.amount_from_stablecoin(
+            stablecoin_amount,
+            &collateral_value_in_dollars,
+            &collateral_precision,
+        );
+        let transaction_fees_percentage = self.add_price_impact_fees_percentage(
+            collateral_id,
+            self.calculate_burn_transaction_fees_percentage(collateral_id),
+            &total_value_in_collateral,
+        );
+        let transaction_fees_percentage =
+            self.apply_fee_discount(payer, transaction_fees_percentage);
         let fees_amount_in_collateral =
             self.calculate_percentage_of(&transaction_fees_percentage, &total_value_in_collateral);
 
         let collateral_amount = &total_value_in_collateral - &fees_amount_in_collateral;
-        require!(collateral_amount >= min_amount_out, "Below min amount");
 
-        self.update_pool(&collateral_id, |pool| {
+        (
+            collateral_amount,
+            fees_amount_in_collateral,
+            collateral_value_in_dollars,
+        )
+    }
+
+    // returns the stablecoin amount to be minted for the payment, and the mint price it was computed with
+    fn add_collateral_to_pool(
+        &self,
+        caller: &ManagedAddress,
+        collateral_id: &TokenIdentifier,
+        payment_amount: &BigUint,
+        opt_referrer: &Option<ManagedAddress>,
+    ) -> SCResult<(BigUint, BigUint)> {
+        self.require_collateral_not_deprecated(collateral_id)?;
+        self.require_collateral_not_paused(collateral_id)?;
+        self.require_min_mint_amount(collateral_id, payment_amount)?;
+
+        let (stablecoin_amount, fees_amount_in_collateral, price) =
+            self.calculate_mint_quote(caller, collateral_id, payment_amount)?;
+        let collateral_amount = payment_amount - &fees_amount_in_collateral;
+        self.sell_collateral_event(
+            caller,
+            collateral_id,
+            &SwapEventData {
+                amount_in: payment_amount.clone(),
+                amount_out: stablecoin_amount.clone(),
+                fee_amount: fees_amount_in_collateral.clone(),
+                price: price.clone(),
+            },
+        );
+
+        self.update_pool(collateral_id, |pool| {
+            pool.collateral_amount += &collateral_amount;
+            pool.stablecoin_amount += &stablecoin_amount;
+        });
+        self.add_stablecoin_minted_for_collateral(collateral_id, &stablecoin_amount)?;
+        self.check_pool_imbalance(collateral_id, &price);
+        let fees_amount_in_collateral =
+            self.take_referral_fees(collateral_id, fees_amount_in_collateral, opt_referrer);
+        self.accumulated_tx_fees(collateral_id)
+            .update(|accumulated_fees| *accumulated_fees += fees_amount_in_collateral);
+
+        Ok((stablecoin_amount, price))
+    }
+
+    fn redeem_from_pool(
+        &self,
+        caller: &ManagedAddress,
+        collateral_id: &TokenIdentifier,
+        stablecoin_amount: &BigUint,
+        collateral_amount: &BigUint,
+        fees_amount_in_collateral: BigUint,
+        price: BigUint,
+        opt_referrer: &Option<ManagedAddress>,
+    ) -> SCResult<()> {
+        self.require_collateral_not_paused(collateral_id)?;
+
+        self.buy_collateral_event(
+            caller,
+            collateral_id,
+            &SwapEventData {
+                amount_in: stablecoin_amount.clone(),
+                amount_out: collateral_amount.clone(),
+                fee_amount: fees_amount_in_collateral.clone(),
+                price: price.clone(),
+            },
+        );
+
+        self.update_pool(collateral_id, |pool| {
             require!(
-                pool.collateral_amount >= collateral_amount,
+                &pool.collateral_amount >= collateral_amount,
                 "Insufficient funds for swap"
             );
             require!(
-                pool.stablecoin_amount >= payment_amount,
+                &pool.stablecoin_amount >= stablecoin_amount,
                 "Too many stablecoins paid"
             );
 
-            pool.collateral_amount -= &collateral_amount;
-            pool.stablecoin_amount -= &payment_amount;
+            pool.collateral_amount -= collateral_amount;
+            pool.stablecoin_amount -= stablecoin_amount;
 
             Ok(())
         })?;
-        self.accumulated_tx_fees(&collateral_id)
+        self.remove_stablecoin_minted_for_collateral(collateral_id, stablecoin_amount);
+        self.check_pool_imbalance(collateral_id, &price);
+        let fees_amount_in_collateral =
+            self.take_referral_fees(collateral_id, fees_amount_in_collateral, opt_referrer);
+        self.accumulated_tx_fees(collateral_id)
             .update(|accumulated_fees| *accumulated_fees += fees_amount_in_collateral);
 
-        self.burn_stablecoin(&payment_amount);
+        self.burn_stablecoin(stablecoin_amount);
 
-        let caller = self.blockchain().get_caller();
-        self.send()
-            .direct(&caller, &collateral_id, 0, &collateral_amount, &[]);
+        Ok(())
+    }
+
+    // deprecated collateral is only wound down, so no new collateral would come in to fill the request
+    fn queue_redemption_request(&self, request: RedemptionRequest<Self::Api>) -> SCResult<()> {
+        self.require_collateral_not_deprecated(&request.collateral_id)?;
+        self.require_collateral_not_paused(&request.collateral_id)?;
+
+        let request_id = self.last_redemption_request_id().update(|id| {
+            *id += 1;
+            *id
+        });
+
+        self.redemption_queue(&request.collateral_id)
+            .insert(request_id);
+        self.redemption_requests(request_id).set(&request);
 
         Ok(())
     }
+
+    fn remove_redemption_request(&self, collateral_id: &TokenIdentifier, request_id: u64) {
+        self.redemption_queue(collateral_id).remove(&request_id);
+        self.redemption_requests(request_id).clear();
+    }
+
+    // circuit breaker against oracle manipulation,
+    // the counters start from zero on each new block and epoch
+    fn add_minted_amount(&self, amount: &BigUint) -> SCResult<()> {
+        let current_block_nonce = self.blockchain().get_block_nonce();
+        let mut new_minted_in_block = amount.clone();
+        if self.last_mint_block_nonce().get() == current_block_nonce {
+            new_minted_in_block += &self.minted_in_block().get();
+        }
+
+        let current_epoch = self.blockchain().get_block_epoch();
+        let mut new_minted_in_epoch = amount.clone();
+        if self.last_mint_epoch().get() == current_epoch {
+            new_minted_in_epoch += &self.minted_in_epoch().get();
+        }
+
+        let max_mint_per_block = self.max_mint_per_block().get();
+        require!(
+            max_mint_per_block == 0 || new_minted_in_block <= max_mint_per_block,
+            "Mint limit per block exceeded"
+        );
+        let max_mint_per_epoch = self.max_mint_per_epoch().get();
+        require!(
+            max_mint_per_epoch == 0 || new_minted_in_epoch <= max_mint_per_epoch,
+            "Mint limit per epoch exceeded"
+        );
+
+        self.last_mint_block_nonce().set(&current_block_nonce);
+        self.minted_in_block().set(&new_minted_in_block);
+        self.last_mint_epoch().set(&current_epoch);
+        self.minted_in_epoch().set(&new_minted_in_epoch);
+
+        Ok(())
+    }
+
+    // the expected price moved up or down by the slippage
+    fn apply_slippage(
+        &self,
+        expected_price: &BigUint,
+        max_slippage_bps: u64,
+        upwards: bool,
+    ) -> SCResult<BigUint> {
+        require!(
+            max_slippage_bps <= MAX_BASIS_POINTS,
+            "Invalid slippage basis points"
+        );
+
+        let slippage_amount = expected_price * max_slippage_bps / MAX_BASIS_POINTS;
+        if upwards {
+            Ok(expected_price + &slippage_amount)
+        } else {
+            Ok(expected_price - &slippage_amount)
+        }
+    }
+
+    fn require_min_mint_amount(
+        &self,
+        collateral_id: &TokenIdentifier,
+        payment_amount: &BigUint,
+    ) -> SCResult<()> {
+        require!(
+            payment_amount >= &self.min_mint_amount(collateral_id).get(),
+            "Below min mint amount"
+        );
+        Ok(())
+    }
+
+    fn require_min_redeem_amount(
+        &self,
+        collateral_id: &TokenIdentifier,
+        collateral_amount: &BigUint,
+    ) -> SCResult<()> {
+        require!(
+            collateral_amount >= &self.min_redeem_amount(collateral_id).get(),
+            "Below min redeem amount"
+        );
+        Ok(())
+    }
+
+    // deadline is a block timestamp, so a pending transaction can't be executed at a stale price
+    fn require_deadline_not_passed(&self, deadline: u64) -> SCResult<()> {
+        require!(
+            self.blockchain().get_block_timestamp() <= deadline,
+            "Deadline passed"
+        );
+        Ok(())
+    }
+
+    // storage
+
+    #[view(getMaxMintPerBlock)]
+    #[storage_mapper("maxMintPerBlock")]
+    fn max_mint_per_block(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getMaxMintPerEpoch)]
+    #[storage_mapper("maxMintPerEpoch")]
+    fn max_mint_per_epoch(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getMinMintAmount)]
+    #[storage_mapper("minMintAmount")]
+    fn min_mint_amount(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    #[view(getMinRedeemAmount)]
+    #[storage_mapper("minRedeemAmount")]
+    fn min_redeem_amount(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("lastMintBlockNonce")]
+    fn last_mint_block_nonce(&self) -> SingleValueMapper<u64>;
+
+    // amount minted in the last mint block
+    #[storage_mapper("mintedInBlock")]
+    fn minted_in_block(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("lastMintEpoch")]
+    fn last_mint_epoch(&self) -> SingleValueMapper<u64>;
+
+    // amount minted in the last mint epoch
+    #[storage_mapper("mintedInEpoch")]
+    fn minted_in_epoch(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("lastRedemptionRequestId")]
+    fn last_redemption_request_id(&self) -> SingleValueMapper<u64>;
+
+    #[view(getRedemptionRequest)]
+    #[storage_mapper("redemptionRequests")]
+    fn redemption_requests(
+        &self,
+        request_id: u64,
+    ) -> SingleValueMapper<RedemptionRequest<Self::Api>>;
+
+    // request ids, in the order they were made
+    #[storage_mapper("redemptionQueue")]
+    fn redemption_queue(&self, collateral_id: &TokenIdentifier) -> SetMapper<u64>;
 }
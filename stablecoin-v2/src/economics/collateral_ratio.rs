@@ -0,0 +1,86 @@
+numbat_wasm::imports!();
+
+use crate::math::PERCENTAGE_PRECISION;
+
+#[numbat_wasm::module]
+pub trait CollateralRatioModule:
+    crate::math::MathModule
+    + crate::pools::PoolsModule
+    + price_aggregator_proxy::PriceAggregatorModule
+    + crate::stablecoin_token::StablecoinTokenModule
+    + crate::token_common::TokenCommonModule
+{
+    // the share of the collateral's value counted towards the global collateral ratio
+    #[only_owner]
+    #[endpoint(setCollateralRatioWeight)]
+    fn set_collateral_ratio_weight(
+        &self,
+        collateral_id: TokenIdentifier,
+        weight: BigUint,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+        require!(weight <= PERCENTAGE_PRECISION, "Invalid weight");
+
+        self.collateral_ratio_weight(&collateral_id).set(&weight);
+
+        Ok(())
+    }
+
+    #[endpoint(updateGlobalCollateralRatio)]
+    fn update_global_collateral_ratio(&self) -> SCResult<BigUint> {
+        let global_collateral_ratio = self.calculate_global_collateral_ratio()?;
+        self.global_collateral_ratio().set(&global_collateral_ratio);
+
+        Ok(global_collateral_ratio)
+    }
+
+    // weighted value of all the pools' collateral and reserves, over the stablecoin supply
+    // zero if there are no stablecoins in circulation
+    fn calculate_global_collateral_ratio(&self) -> SCResult<BigUint> {
+        let total_supply = self.stablecoin_total_circulating_supply().get();
+        if total_supply == 0 {
+            return Ok(BigUint::zero());
+        }
+
+        let mut total_weighted_value = BigUint::zero();
+        for collateral_id in self.whitelisted_collaterals().iter() {
+            let pool = self.get_pool(&collateral_id);
+            let collateral_value_in_dollars =
+                self.get_collateral_value_in_dollars(&collateral_id)?;
+            let collateral_precision = self.get_collateral_precision(&collateral_id);
+            let pool_value_in_dollars = self.value_in_stablecoin(
+                &(&pool.collateral_amount + &pool.collateral_reserves),
+                &collateral_value_in_dollars,
+                &collateral_precision,
+            );
+
+            let weight = self.get_collateral_ratio_weight(&collateral_id);
+            total_weighted_value += self.calculate_percentage_of(&weight, &pool_value_in_dollars);
+        }
+
+        Ok(self.calculate_ratio(&total_weighted_value, &total_supply))
+    }
+
+    // collaterals without a weight count fully
+    fn get_collateral_ratio_weight(&self, collateral_id: &TokenIdentifier) -> BigUint {
+        if self.collateral_ratio_weight(collateral_id).is_empty() {
+            return BigUint::from(PERCENTAGE_PRECISION);
+        }
+
+        self.collateral_ratio_weight(collateral_id).get()
+    }
+
+    // storage
+
+    #[view(getCollateralRatioWeight)]
+    #[storage_mapper("collateralRatioWeight")]
+    fn collateral_ratio_weight(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<BigUint>;
+
+    // last value computed by updateGlobalCollateralRatio
+    #[view(getGlobalCollateralRatio)]
+    #[storage_mapper("globalCollateralRatio")]
+    fn global_collateral_ratio(&self) -> SingleValueMapper<BigUint>;
+}
@@ -0,0 +1,15 @@
+numbat_wasm::imports!();
+
+// the output tokens are sent back to the caller of the swap
+#[numbat_wasm::proxy]
+pub trait DexPair {
+    #[payable("*")]
+    #[endpoint(swapTokensFixedInput)]
+    fn swap_tokens_fixed_input(
+        &self,
+        #[payment_token] token_in: TokenIdentifier,
+        #[payment_amount] amount_in: BigUint,
+        token_out: TokenIdentifier,
+        amount_out_min: BigUint,
+    );
+}
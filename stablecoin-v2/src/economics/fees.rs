@@ -1,7 +1,11 @@
 numbat_wasm::imports!();
 numbat_wasm::derive_imports!();
 
-use crate::math::ONE;
+use crate::math::{ONE, PERCENTAGE_PRECISION};
+
+pub const MAX_FEES_PERCENTAGE: u64 = PERCENTAGE_PRECISION / 20; // 5%
+pub const MAX_FEE_CURVE_SLOPE: u64 = 10 * ONE;
+pub const MAX_FEE_RECIPIENTS: usize = 10;
 
 #[derive(TopEncode, TopDecode)]
 pub struct CurrentFeeConfiguration<M: ManagedTypeApi> {
@@ -14,6 +18,167 @@ pub struct CurrentFeeConfiguration<M: ManagedTypeApi> {
 pub trait FeesModule:
     crate::math::MathModule + crate::pools::PoolsModule + price_aggregator_proxy::PriceAggregatorModule
 {
+    // the extra fees percentage paid for trading the whole pool's collateral at once
+    #[only_owner]
+    #[endpoint(setPriceImpactFeeSlope)]
+    fn set_price_impact_fee_slope(
+        &self,
+        collateral_id: TokenIdentifier,
+        price_impact_fee_slope: BigUint,
+    ) -> SCResult<()> {
+        require!(
+            price_impact_fee_slope <= PERCENTAGE_PRECISION,
+            "Invalid price impact fee slope"
+        );
+
+        self.price_impact_fee_slope(&collateral_id)
+            .set(&price_impact_fee_slope);
+
+        Ok(())
+    }
+
+    // the mint fees go from max, with nothing covered, down to min, at the target hedging ratio
+    #[only_owner]
+    #[endpoint(setMintFeesPercentage)]
+    fn set_mint_fees_percentage(
+        &self,
+        collateral_id: TokenIdentifier,
+        min_fees_percentage: BigUint,
+        max_fees_percentage: BigUint,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+        self.require_valid_fees_percentages(&min_fees_percentage, &max_fees_percentage)?;
+
+        self.min_max_fees_percentage(&collateral_id)
+            .set(&(min_fees_percentage.clone(), max_fees_percentage.clone()));
+        self.mint_fees_percentage_event(&collateral_id, &min_fees_percentage, &max_fees_percentage);
+
+        Ok(())
+    }
+
+    // the burn fees go from min, with nothing covered, up to max, at the target hedging ratio
+    #[only_owner]
+    #[endpoint(setBurnFeesPercentage)]
+    fn set_burn_fees_percentage(
+        &self,
+        collateral_id: TokenIdentifier,
+        min_fees_percentage: BigUint,
+        max_fees_percentage: BigUint,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+        self.require_valid_fees_percentages(&min_fees_percentage, &max_fees_percentage)?;
+
+        self.min_max_burn_fees_percentage(&collateral_id)
+            .set(&(min_fees_percentage.clone(), max_fees_percentage.clone()));
+        self.burn_fees_percentage_event(&collateral_id, &min_fees_percentage, &max_fees_percentage);
+
+        Ok(())
+    }
+
+    // how fast the fees move with the hedging ratio, ONE being linear up to the target
+    #[only_owner]
+    #[endpoint(setFeeCurveSlope)]
+    fn set_fee_curve_slope(
+        &self,
+        collateral_id: TokenIdentifier,
+        fee_curve_slope: BigUint,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+        require!(
+            fee_curve_slope > 0 && fee_curve_slope <= MAX_FEE_CURVE_SLOPE,
+            "Invalid fee curve slope"
+        );
+
+        self.fee_curve_slope(&collateral_id).set(&fee_curve_slope);
+        self.fee_curve_slope_event(&collateral_id, &fee_curve_slope);
+
+        Ok(())
+    }
+
+    // DEX pair swapping the collateral's fees into stablecoins in splitFeesSwapped.
+    // No pair address removes it.
+    #[only_owner]
+    #[endpoint(setFeeSwapPair)]
+    fn set_fee_swap_pair(
+        &self,
+        collateral_id: TokenIdentifier,
+        #[var_args] opt_pair_address: OptionalArg<ManagedAddress>,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        match opt_pair_address.into_option() {
+            Some(pair_address) => {
+                require!(
+                    self.blockchain().is_smart_contract(&pair_address),
+                    "Invalid pair address"
+                );
+
+                self.fee_swap_pair_address(&collateral_id)
+                    .set(&pair_address);
+            }
+            None => self.fee_swap_pair_address(&collateral_id).clear(),
+        }
+
+        Ok(())
+    }
+
+    // Max percentage the fees swap may return below the fees' oracle value in splitFeesSwapped.
+    // Zero requires the full oracle value.
+    #[only_owner]
+    #[endpoint(setFeeSwapMaxSlippage)]
+    fn set_fee_swap_max_slippage(
+        &self,
+        collateral_id: TokenIdentifier,
+        max_slippage_percentage: BigUint,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+        require!(
+            max_slippage_percentage <= PERCENTAGE_PRECISION,
+            "Invalid max slippage"
+        );
+
+        self.fee_swap_max_slippage_percentage(&collateral_id)
+            .set(&max_slippage_percentage);
+
+        Ok(())
+    }
+
+    // Part of the transaction fees waived for the address, PERCENTAGE_PRECISION being fully exempt.
+    // Meant for market makers and protocol contracts.
+    #[only_owner]
+    #[endpoint(setFeeDiscount)]
+    fn set_fee_discount(&self, address: ManagedAddress, fee_discount: BigUint) -> SCResult<()> {
+        require!(
+            fee_discount > 0 && fee_discount <= PERCENTAGE_PRECISION,
+            "Invalid fee discount"
+        );
+
+        self.fee_discount(&address).set(&fee_discount);
+        self.fee_discount_event(&address, &fee_discount);
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint(removeFeeDiscount)]
+    fn remove_fee_discount(&self, address: ManagedAddress) {
+        self.fee_discount(&address).clear();
+        self.fee_discount_event(&address, &BigUint::zero());
+    }
+
+    fn require_valid_fees_percentages(
+        &self,
+        min_fees_percentage: &BigUint,
+        max_fees_percentage: &BigUint,
+    ) -> SCResult<()> {
+        require!(
+            min_fees_percentage <= max_fees_percentage
+                && max_fees_percentage <= &MAX_FEES_PERCENTAGE,
+            "Invalid fees percentages"
+        );
+        Ok(())
+    }
+
     #[view(getCurrentHedgingRatio)]
     fn get_current_hedging_ratio(&self, collateral_id: &TokenIdentifier) -> BigUint {
         self.current_fee_configuration(collateral_id)
@@ -73,9 +238,13 @@ pub trait FeesModule:
         }
 
         let percentage_diff = &max_fees_percentage - &min_fees_percentage;
+        let fee_curve_ratio = self.calculate_fee_curve_ratio(collateral_id, &current_hedging_ratio);
+        if fee_curve_ratio >= one {
+            return min_fees_percentage;
+        }
 
-        // max - (max - min) * hedging_ratio
-        max_fees_percentage - self.multiply(&current_hedging_ratio, &percentage_diff, &one)
+        // max - (max - min) * hedging_ratio * slope
+        max_fees_percentage - self.multiply(&fee_curve_ratio, &percentage_diff, &one)
     }
 
     // burn fees decrease as coverage ratio decreases
@@ -86,7 +255,7 @@ pub trait FeesModule:
         let target_hedging_ratio = self.target_hedging_ratio().get();
         let current_hedging_ratio = self.calculate_current_hedging_ratio(collateral_id);
         let (min_fees_percentage, max_fees_percentage) =
-            self.min_max_fees_percentage(collateral_id).get();
+            self.get_min_max_burn_fees_percentage(collateral_id);
         let one = BigUint::from(ONE);
 
         if current_hedging_ratio == 0 {
@@ -97,14 +266,114 @@ pub trait FeesModule:
         }
 
         let percentage_diff = &max_fees_percentage - &min_fees_percentage;
+        let fee_curve_ratio = self.calculate_fee_curve_ratio(collateral_id, &current_hedging_ratio);
+        if fee_curve_ratio >= one {
+            return max_fees_percentage;
+        }
+
+        // min + (max - min) * hedging_ratio * slope
+        min_fees_percentage + self.multiply(&fee_curve_ratio, &percentage_diff, &one)
+    }
+
+    // collaterals without a slope use the hedging ratio as is
+    fn calculate_fee_curve_ratio(
+        &self,
+        collateral_id: &TokenIdentifier,
+        hedging_ratio: &BigUint,
+    ) -> BigUint {
+        let fee_curve_slope_mapper = self.fee_curve_slope(collateral_id);
+        if fee_curve_slope_mapper.is_empty() {
+            return hedging_ratio.clone();
+        }
+
+        self.multiply(
+            hedging_ratio,
+            &fee_curve_slope_mapper.get(),
+            &BigUint::from(ONE),
+        )
+    }
+
+    // collaterals without burn fees use the mint fees range
+    fn get_min_max_burn_fees_percentage(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> (BigUint, BigUint) {
+        let burn_fees_mapper = self.min_max_burn_fees_percentage(collateral_id);
+        if burn_fees_mapper.is_empty() {
+            return self.min_max_fees_percentage(collateral_id).get();
+        }
+
+        burn_fees_mapper.get()
+    }
+
+    // larger trades relative to the pool pay progressively more, up to 100%
+    fn add_price_impact_fees_percentage(
+        &self,
+        collateral_id: &TokenIdentifier,
+        fees_percentage: BigUint,
+        trade_collateral_amount: &BigUint,
+    ) -> BigUint {
+        let price_impact_fee_slope = self.price_impact_fee_slope(collateral_id).get();
+        if price_impact_fee_slope == 0 {
+            return fees_percentage;
+        }
+
+        let pool_collateral_amount = self.get_pool_collateral_amount(collateral_id);
+        let price_impact_fees_percentage = if trade_collateral_amount >= &pool_collateral_amount {
+            price_impact_fee_slope
+        } else {
+            price_impact_fee_slope * trade_collateral_amount / pool_collateral_amount
+        };
+
+        let total_fees_percentage = fees_percentage + price_impact_fees_percentage;
+        if total_fees_percentage > PERCENTAGE_PRECISION {
+            BigUint::from(PERCENTAGE_PRECISION)
+        } else {
+            total_fees_percentage
+        }
+    }
+
+    fn apply_fee_discount(&self, payer: &ManagedAddress, fees_percentage: BigUint) -> BigUint {
+        let fee_discount = self.fee_discount(payer).get();
+        if fee_discount == 0 {
+            return fees_percentage;
+        }
+
+        let discount_percentage = self.calculate_percentage_of(&fee_discount, &fees_percentage);
+        fees_percentage - discount_percentage
+    }
+
+    #[view(getFeeRecipients)]
+    fn get_fee_recipients(
+        &self,
+        collateral_id: TokenIdentifier,
+    ) -> MultiResultVec<MultiResult2<ManagedAddress, BigUint>> {
+        let fee_recipients: Vec<MultiResult2<ManagedAddress, BigUint>> = self
+            .fee_recipients(&collateral_id)
+            .iter()
+            .map(|fee_recipient| fee_recipient.into())
+            .collect();
 
-        // min + (max - min) * hedging_ratio
-        min_fees_percentage + self.multiply(&current_hedging_ratio, &percentage_diff, &one)
+        fee_recipients.into()
+    }
+
+    fn get_total_fee_recipients_percentage(&self, collateral_id: &TokenIdentifier) -> BigUint {
+        let mut total_fee_percentage = BigUint::zero();
+        for fee_percentage in self.fee_recipients(collateral_id).values() {
+            total_fee_percentage += fee_percentage;
+        }
+
+        total_fee_percentage
     }
 
     fn calculate_current_hedging_ratio(&self, collateral_id: &TokenIdentifier) -> BigUint {
         let pool = self.get_pool(collateral_id);
         let target_hedge_amount = self.calculate_target_hedge_amount(&pool.collateral_amount);
+        // empty pool, nothing to hedge yet
+        if target_hedge_amount == 0 {
+            return BigUint::zero();
+        }
+
         self.calculate_ratio(
             &pool.total_covered_value_in_stablecoin,
             &target_hedge_amount,
@@ -129,6 +398,16 @@ pub trait FeesModule:
         collateral_id: &TokenIdentifier,
     ) -> SingleValueMapper<(BigUint, BigUint)>;
 
+    #[storage_mapper("minMaxBurnFeesPercentage")]
+    fn min_max_burn_fees_percentage(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<(BigUint, BigUint)>;
+
+    #[view(getFeeCurveSlope)]
+    #[storage_mapper("feeCurveSlope")]
+    fn fee_curve_slope(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+
     #[storage_mapper("currentFeeConfiguration")]
     fn current_fee_configuration(
         &self,
@@ -138,6 +417,49 @@ pub trait FeesModule:
     #[storage_mapper("accumulatedTxFees")]
     fn accumulated_tx_fees(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<BigUint>;
 
+    #[view(getPriceImpactFeeSlope)]
+    #[storage_mapper("priceImpactFeeSlope")]
+    fn price_impact_fee_slope(&self, collateral_id: &TokenIdentifier)
+        -> SingleValueMapper<BigUint>;
+
+    #[view(getFeeDiscount)]
+    #[storage_mapper("feeDiscount")]
+    fn fee_discount(&self, address: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    // the treasury's part of the accumulated transaction fees in splitFees
+    #[view(getTreasuryFeePercentage)]
+    #[storage_mapper("treasuryFeePercentage")]
+    fn treasury_fee_percentage(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<BigUint>;
+
+    #[view(getTreasuryFeesInStablecoin)]
+    #[storage_mapper("treasuryFeesInStablecoin")]
+    fn treasury_fees_in_stablecoin(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<bool>;
+
+    // each recipient's part of the accumulated transaction fees in splitFees
+    #[storage_mapper("feeRecipients")]
+    fn fee_recipients(&self, collateral_id: &TokenIdentifier)
+        -> MapMapper<ManagedAddress, BigUint>;
+
+    #[view(getFeeSwapPairAddress)]
+    #[storage_mapper("feeSwapPairAddress")]
+    fn fee_swap_pair_address(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<ManagedAddress>;
+
+    #[view(getFeeSwapMaxSlippagePercentage)]
+    #[storage_mapper("feeSwapMaxSlippagePercentage")]
+    fn fee_swap_max_slippage_percentage(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<BigUint>;
+
     #[view(getTargetHedgingRatio)]
     #[storage_mapper("targetHedgingRatio")]
     fn target_hedging_ratio(&self) -> SingleValueMapper<BigUint>;
@@ -1,5 +1,7 @@
 numbat_wasm::imports!();
 
+use crate::stablecoin_token::STABLE_COIN_PRECISION;
+
 pub const PERCENTAGE_PRECISION: u64 = 1_000_000_000; // 100%
 pub const ONE: u64 = PERCENTAGE_PRECISION / 100;
 
@@ -19,6 +21,25 @@ pub trait MathModule {
         &(first * result_precision) / second
     }
 
+    // prices are in ONE precision, stablecoin amounts in STABLE_COIN_PRECISION
+    fn value_in_stablecoin(
+        &self,
+        amount: &BigUint,
+        price: &BigUint,
+        amount_precision: &BigUint,
+    ) -> BigUint {
+        &(amount * price) * STABLE_COIN_PRECISION / &(amount_precision * ONE)
+    }
+
+    fn amount_from_stablecoin(
+        &self,
+        stablecoin_amount: &BigUint,
+        price: &BigUint,
+        amount_precision: &BigUint,
+    ) -> BigUint {
+        &(stablecoin_amount * amount_precision) * ONE / &(price * STABLE_COIN_PRECISION)
+    }
+
     #[inline(always)]
     fn calculate_ratio(&self, first: &BigUint, second: &BigUint) -> BigUint {
         &(first * ONE) / second
@@ -1,3 +1,10 @@
+pub mod collateral_ratio;
+pub mod dex_pair_proxy;
 pub mod fees;
 pub mod math;
+pub mod pool_info;
 pub mod pools;
+pub mod price_round_proxy;
+pub mod referrals;
+pub mod signed_prices;
+pub mod twap;
@@ -0,0 +1,144 @@
+numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct PoolInfo<M: ManagedTypeApi> {
+    pub collateral_amount: BigUint<M>,
+    pub collateral_reserves: BigUint<M>,
+    pub stablecoin_amount: BigUint<M>,
+    pub total_collateral_covered: BigUint<M>,
+    pub total_covered_value_in_stablecoin: BigUint<M>,
+    pub min_fees_percentage: BigUint<M>,
+    pub max_fees_percentage: BigUint<M>,
+    pub mint_fee_percentage: BigUint<M>,
+    pub burn_fee_percentage: BigUint<M>,
+    pub hedging_ratio: BigUint<M>,
+    pub oracle_price: Option<BigUint<M>>,
+}
+
+// `last_price` is the last price the contract used, at `last_price_timestamp`.
+// The round fields are the main price aggregator's last round for the collateral's pair,
+// zero if it has no round for the pair. `is_fresh` tells if the oracles the collateral
+// is priced with, sources, main or fallback aggregator, have a fresh price.
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct OracleHeartbeat<M: ManagedTypeApi> {
+    pub collateral_id: TokenIdentifier<M>,
+    pub last_price: Option<BigUint<M>>,
+    pub last_price_timestamp: u64,
+    pub round_id: u32,
+    pub round_block_nonce: u64,
+    pub is_fresh: bool,
+}
+
+#[numbat_wasm::module]
+pub trait PoolInfoModule:
+    crate::fees::FeesModule
+    + crate::math::MathModule
+    + crate::pools::PoolsModule
+    + price_aggregator_proxy::PriceAggregatorModule
+{
+    // fee percentages and hedging ratio are computed from the current pool state.
+    // oracle price is the price mints and redeems would use now, None if there is no valid one
+    #[view(getPoolInfo)]
+    fn get_pool_info(&self, collateral_id: TokenIdentifier) -> SCResult<PoolInfo<Self::Api>> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        Ok(self.build_pool_info(&collateral_id))
+    }
+
+    // at most `count` pools, in whitelisting order, starting from `from_index`
+    #[view(getAllPools)]
+    fn get_all_pools(
+        &self,
+        from_index: usize,
+        count: usize,
+    ) -> MultiResultVec<MultiResult2<TokenIdentifier, PoolInfo<Self::Api>>> {
+        let pools: Vec<MultiResult2<TokenIdentifier, PoolInfo<Self::Api>>> = self
+            .whitelisted_collaterals()
+            .iter()
+            .skip(from_index)
+            .take(count)
+            .map(|collateral_id| {
+                let pool_info = self.build_pool_info(&collateral_id);
+                (collateral_id, pool_info).into()
+            })
+            .collect();
+
+        pools.into()
+    }
+
+    #[view(getOracleHeartbeats)]
+    fn get_oracle_heartbeats(&self) -> MultiResultVec<OracleHeartbeat<Self::Api>> {
+        let heartbeats: Vec<OracleHeartbeat<Self::Api>> = self
+            .whitelisted_collaterals()
+            .iter()
+            .map(|collateral_id| self.build_oracle_heartbeat(collateral_id))
+            .collect();
+
+        heartbeats.into()
+    }
+
+    fn build_oracle_heartbeat(&self, collateral_id: TokenIdentifier) -> OracleHeartbeat<Self::Api> {
+        let (last_price, last_price_timestamp) =
+            match self.get_last_price_observation(&collateral_id) {
+                Some(observation) => (Some(observation.price), observation.timestamp),
+                None => (None, 0),
+            };
+
+        // the main aggregator is only known to expose round details while it prices the collateral
+        // with the staleness check enabled
+        let reads_main_aggregator_rounds = self.max_price_age_blocks(&collateral_id).get() > 0
+            && self.price_sources(&collateral_id).is_empty();
+        let (round_id, round_block_nonce) = if reads_main_aggregator_rounds {
+            self.get_main_aggregator_round(&collateral_id)
+        } else {
+            (0, 0)
+        };
+
+        let is_fresh = self.fetch_oracle_price(&collateral_id).ok().is_some();
+
+        OracleHeartbeat {
+            collateral_id,
+            last_price,
+            last_price_timestamp,
+            round_id,
+            round_block_nonce,
+            is_fresh,
+        }
+    }
+
+    fn get_main_aggregator_round(&self, collateral_id: &TokenIdentifier) -> (u32, u64) {
+        let (from_ticker, to_ticker) = self.get_collateral_ticker_pair(collateral_id);
+        let result: OptionalArg<MultiArg3<u32, u64, u64>> = self
+            .price_round_proxy(self.price_aggregator_address().get())
+            .latest_round_info(from_ticker, to_ticker)
+            .execute_on_dest_context();
+        match result.into_option() {
+            Some(round_info) => {
+                let (round_id, round_block_nonce, _) = round_info.into_tuple();
+                (round_id, round_block_nonce)
+            }
+            None => (0, 0),
+        }
+    }
+
+    fn build_pool_info(&self, collateral_id: &TokenIdentifier) -> PoolInfo<Self::Api> {
+        let pool = self.get_pool(collateral_id);
+        let (min_fees_percentage, max_fees_percentage) =
+            self.min_max_fees_percentage(collateral_id).get();
+
+        PoolInfo {
+            collateral_amount: pool.collateral_amount,
+            collateral_reserves: pool.collateral_reserves,
+            stablecoin_amount: pool.stablecoin_amount,
+            total_collateral_covered: pool.total_collateral_covered,
+            total_covered_value_in_stablecoin: pool.total_covered_value_in_stablecoin,
+            min_fees_percentage,
+            max_fees_percentage,
+            mint_fee_percentage: self.calculate_mint_transaction_fees_percentage(collateral_id),
+            burn_fee_percentage: self.calculate_burn_transaction_fees_percentage(collateral_id),
+            hedging_ratio: self.calculate_current_hedging_ratio(collateral_id),
+            oracle_price: self.peek_collateral_value_in_dollars(collateral_id).ok(),
+        }
+    }
+}
@@ -1,7 +1,12 @@
 numbat_wasm::imports!();
 numbat_wasm::derive_imports!();
 
-use price_aggregator_proxy::DOLLAR_TICKER;
+use price_aggregator_proxy::{AggregatorResult, AggregatorResultAsMultiResult, DOLLAR_TICKER};
+
+use crate::{math::ONE, price_round_proxy, stablecoin_token::STABLE_COIN_NUM_DECIMALS};
+
+pub const MAX_PRICE_SOURCES: usize = 5;
+pub const MAX_EMERGENCY_PRICE_DURATION_BLOCKS: u64 = 14_400;
 
 #[derive(TypeAbi, TopEncode, TopDecode)]
 pub struct Pool<M: ManagedTypeApi> {
@@ -26,7 +31,11 @@ impl<M: ManagedTypeApi> Pool<M> {
 
 #[numbat_wasm::module]
 pub trait PoolsModule:
-    crate::math::MathModule + price_aggregator_proxy::PriceAggregatorModule
+    crate::events::EventsModule
+    + crate::math::MathModule
+    + price_aggregator_proxy::PriceAggregatorModule
+    + crate::signed_prices::SignedPricesModule
+    + crate::twap::TwapModule
 {
     #[inline(always)]
     fn get_pool(&self, collateral_id: &TokenIdentifier) -> Pool<Self::Api> {
@@ -48,6 +57,48 @@ pub trait PoolsModule:
         self.get_pool(collateral_id).collateral_reserves
     }
 
+    // rebalancing may cover losses from the reserves, leaving less than the recorded protocol share
+    fn get_protocol_owned_reserves(&self, collateral_id: &TokenIdentifier) -> BigUint {
+        let protocol_owned_reserves = self.protocol_owned_reserves(collateral_id).get();
+        let reserves = self.get_pool_reserves(collateral_id);
+        if protocol_owned_reserves > reserves {
+            reserves
+        } else {
+            protocol_owned_reserves
+        }
+    }
+
+    // liquidity providers' and hedgers' collateral
+    #[view(getUserOwnedReserves)]
+    fn get_user_owned_reserves(&self, collateral_id: &TokenIdentifier) -> BigUint {
+        self.get_pool_reserves(collateral_id) - self.get_protocol_owned_reserves(collateral_id)
+    }
+
+    #[inline(always)]
+    fn add_protocol_owned_reserves(&self, collateral_id: &TokenIdentifier, amount: &BigUint) {
+        self.protocol_owned_reserves(collateral_id)
+            .update(|protocol_owned_reserves| *protocol_owned_reserves += amount);
+    }
+
+    // returns the part of the amount that was owned by the protocol
+    fn remove_protocol_owned_reserves(
+        &self,
+        collateral_id: &TokenIdentifier,
+        amount: &BigUint,
+    ) -> BigUint {
+        let protocol_owned_reserves = self.get_protocol_owned_reserves(collateral_id);
+        let removed_amount = if amount > &protocol_owned_reserves {
+            protocol_owned_reserves.clone()
+        } else {
+            amount.clone()
+        };
+
+        self.protocol_owned_reserves(collateral_id)
+            .set(&(protocol_owned_reserves - &removed_amount));
+
+        removed_amount
+    }
+
     #[inline(always)]
     fn update_pool<R, F: FnOnce(&mut Pool<Self::Api>) -> R>(
         &self,
@@ -62,19 +113,434 @@ pub trait PoolsModule:
         self.pool_for_collateral(collateral_id).set(pool);
     }
 
+    // Prices deviating too much from the last recorded one are rejected until the guardian
+    // confirms them, or until a keeper trips the circuit breaker through checkPriceDeviation.
+    // An unexpired emergency price set by the guardian takes precedence over the oracles.
     fn get_collateral_value_in_dollars(
         &self,
         collateral_id: &TokenIdentifier,
     ) -> SCResult<BigUint> {
-        let collateral_ticker = self.collateral_ticker(collateral_id).get();
-        self.get_price_for_pair(collateral_ticker, ManagedBuffer::from(DOLLAR_TICKER))
-            .ok_or("Could not get collateral value in dollars")
-            .into()
+        if let Some(emergency_price) = self.get_active_emergency_price(collateral_id) {
+            self.record_price_observation(collateral_id, &emergency_price);
+            return Ok(emergency_price);
+        }
+
+        self.require_price_circuit_breaker_not_tripped(collateral_id)?;
+        let price = self.fetch_collateral_value_in_dollars(collateral_id)?;
+        self.require_valid_oracle_price(collateral_id, &price)?;
+        self.record_price_observation(collateral_id, &price);
+
+        Ok(price)
+    }
+
+    // Same price as get_collateral_value_in_dollars, for the views. Nothing is recorded,
+    // cached or emitted, so querying it doesn't change the state.
+    fn peek_collateral_value_in_dollars(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SCResult<BigUint> {
+        if let Some(emergency_price) = self.get_active_emergency_price(collateral_id) {
+            return Ok(emergency_price);
+        }
+
+        self.require_price_circuit_breaker_not_tripped(collateral_id)?;
+        let (oracle_price, _) = self.fetch_oracle_price(collateral_id)?;
+        let price = self.select_signed_or_oracle_price(collateral_id, oracle_price);
+        self.require_valid_oracle_price(collateral_id, &price)?;
+
+        Ok(price)
+    }
+
+    // A fresh signed price close enough to the oracle price takes precedence over it.
+    fn fetch_collateral_value_in_dollars(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SCResult<BigUint> {
+        let oracle_price = self.fetch_cached_oracle_price(collateral_id)?;
+
+        Ok(self.select_signed_or_oracle_price(collateral_id, oracle_price))
+    }
+
+    // The oracle price is cached for the rest of the block, so flows pricing
+    // the same collateral several times only query the oracles once.
+    // Every oracle configuration setter clears it, so a change applies within the same block.
+    fn fetch_cached_oracle_price(&self, collateral_id: &TokenIdentifier) -> SCResult<BigUint> {
+        let current_block_nonce = self.blockchain().get_block_nonce();
+        let cached_price_mapper = self.cached_price(collateral_id);
+        if !cached_price_mapper.is_empty() {
+            let (block_nonce, price) = cached_price_mapper.get();
+            if block_nonce == current_block_nonce {
+                return Ok(price);
+            }
+        }
+
+        let (price, opt_fallback_address) = self.fetch_oracle_price(collateral_id)?;
+        if let Some(fallback_address) = opt_fallback_address {
+            self.oracle_fallback_event(collateral_id, &fallback_address);
+        }
+        cached_price_mapper.set(&(current_block_nonce, price.clone()));
+
+        Ok(price)
+    }
+
+    // Collaterals with price sources use their median price instead of the main aggregator.
+    // Otherwise, falls back to the collateral's secondary aggregator, if set, when the main one has no fresh price.
+    // Also returns the fallback aggregator's address when its price is used.
+    // A main aggregator failing the call, instead of answering without a price, fails the whole
+    // transaction, as synchronous calls can't be caught. The guardian's emergency price covers that case.
+    fn fetch_oracle_price(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SCResult<(BigUint, Option<ManagedAddress>)> {
+        if !self.price_sources(collateral_id).is_empty() {
+            return Ok((self.get_median_price(collateral_id)?, None));
+        }
+
+        let price_aggregator_address = self.price_aggregator_address().get();
+        if let Some(price) = self.get_fresh_price(collateral_id, &price_aggregator_address) {
+            return Ok((price, None));
+        }
+
+        let fallback_address_mapper = self.fallback_price_aggregator_address(collateral_id);
+        if !fallback_address_mapper.is_empty() {
+            let fallback_address = fallback_address_mapper.get();
+            if let Some(price) = self.get_fresh_price(collateral_id, &fallback_address) {
+                return Ok((price, Some(fallback_address)));
+            }
+        }
+
+        sc_error!("Could not get a fresh collateral value in dollars")
+    }
+
+    // signed prices only refine the oracle price, so a single signer can't move it further
+    // than the max signed price deviation
+    fn select_signed_or_oracle_price(
+        &self,
+        collateral_id: &TokenIdentifier,
+        oracle_price: BigUint,
+    ) -> BigUint {
+        let signed_price = match self.get_fresh_signed_price(collateral_id) {
+            Some(signed_price) => signed_price,
+            None => return oracle_price,
+        };
+        let deviation = if signed_price > oracle_price {
+            &signed_price - &oracle_price
+        } else {
+            &oracle_price - &signed_price
+        };
+        let max_deviation =
+            self.calculate_percentage_of(&self.max_signed_price_deviation().get(), &oracle_price);
+        if deviation > max_deviation {
+            return oracle_price;
+        }
+
+        signed_price
+    }
+
+    fn require_price_circuit_breaker_not_tripped(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SCResult<()> {
+        require!(
+            !self.price_circuit_breaker_tripped(collateral_id).get(),
+            "Price circuit breaker tripped"
+        );
+        Ok(())
+    }
+
+    fn require_valid_oracle_price(
+        &self,
+        collateral_id: &TokenIdentifier,
+        price: &BigUint,
+    ) -> SCResult<()> {
+        self.require_price_in_band(collateral_id, price)?;
+        require!(
+            !self.exceeds_max_price_deviation(collateral_id, price),
+            "Price deviation too high"
+        );
+        Ok(())
+    }
+
+    fn get_active_emergency_price(&self, collateral_id: &TokenIdentifier) -> Option<BigUint> {
+        let emergency_price_mapper = self.emergency_price(collateral_id);
+        if emergency_price_mapper.is_empty() {
+            return None;
+        }
+
+        let (price, expiry_block_nonce) = emergency_price_mapper.get();
+        if self.blockchain().get_block_nonce() >= expiry_block_nonce {
+            return None;
+        }
+
+        Some(price)
+    }
+
+    // collaterals without a price band accept any price
+    fn is_price_in_band(&self, collateral_id: &TokenIdentifier, price: &BigUint) -> bool {
+        let price_band_mapper = self.price_band(collateral_id);
+        if price_band_mapper.is_empty() {
+            return true;
+        }
+
+        let (min_price, max_price) = price_band_mapper.get();
+        price >= &min_price && price <= &max_price
     }
 
+    fn require_price_in_band(
+        &self,
+        collateral_id: &TokenIdentifier,
+        price: &BigUint,
+    ) -> SCResult<()> {
+        require!(
+            self.is_price_in_band(collateral_id, price),
+            "Price outside the sanity band"
+        );
+        Ok(())
+    }
+
+    // compared to the last recorded price, zero max deviation disables the check
+    fn exceeds_max_price_deviation(
+        &self,
+        collateral_id: &TokenIdentifier,
+        price: &BigUint,
+    ) -> bool {
+        let max_price_deviation = self.max_price_deviation(collateral_id).get();
+        if max_price_deviation == 0 {
+            return false;
+        }
+
+        let last_price = match self.get_last_observed_price(collateral_id) {
+            Some(last_price) => last_price,
+            None => return false,
+        };
+        let deviation = if price > &last_price {
+            price - &last_price
+        } else {
+            &last_price - price
+        };
+
+        deviation > self.calculate_percentage_of(&max_price_deviation, &last_price)
+    }
+
+    fn require_guardian_or_owner(&self) -> SCResult<()> {
+        let caller = self.blockchain().get_caller();
+        require!(
+            caller == self.blockchain().get_owner_address()
+                || (!self.guardian_address().is_empty() && caller == self.guardian_address().get()),
+            "Only the guardian or the owner may call this"
+        );
+        Ok(())
+    }
+
+    // None if the aggregator has no price for the collateral, or if its price is stale.
+    // Collaterals with an intermediate ticker are priced through two legs, e.g. TOKEN/REWA x REWA/USD
+    fn get_fresh_price(
+        &self,
+        collateral_id: &TokenIdentifier,
+        aggregator_address: &ManagedAddress,
+    ) -> Option<BigUint> {
+        if aggregator_address.is_zero() {
+            return None;
+        }
+
+        let (from_ticker, to_ticker) = self.get_collateral_ticker_pair(collateral_id);
+        let cross_rate_mapper = self.cross_rate_intermediate(collateral_id);
+        if cross_rate_mapper.is_empty() {
+            return self.get_fresh_leg_price(
+                collateral_id,
+                aggregator_address,
+                from_ticker,
+                to_ticker,
+            );
+        }
+
+        let intermediate_ticker = cross_rate_mapper.get();
+        let first_leg_price = self.get_fresh_leg_price(
+            collateral_id,
+            aggregator_address,
+            from_ticker,
+            intermediate_ticker.clone(),
+        )?;
+        let second_leg_price = self.get_fresh_leg_price(
+            collateral_id,
+            aggregator_address,
+            intermediate_ticker,
+            to_ticker,
+        )?;
+
+        Some(self.multiply(&first_leg_price, &second_leg_price, &BigUint::from(ONE)))
+    }
+
+    fn get_fresh_leg_price(
+        &self,
+        collateral_id: &TokenIdentifier,
+        aggregator_address: &ManagedAddress,
+        from_ticker: ManagedBuffer,
+        to_ticker: ManagedBuffer,
+    ) -> Option<BigUint> {
+        let result: OptionalArg<AggregatorResultAsMultiResult<Self::Api>> = self
+            .aggregator_proxy(aggregator_address.clone())
+            .latest_price_feed_optional(from_ticker.clone(), to_ticker.clone())
+            .execute_on_dest_context();
+        let price_feed = AggregatorResult::from(result.into_option()?);
+        if !self.is_price_round_fresh(
+            collateral_id,
+            aggregator_address,
+            &from_ticker,
+            &to_ticker,
+            price_feed.round_id,
+        ) {
+            return None;
+        }
+
+        Some(self.normalize_price(&price_feed.price, &from_ticker, &to_ticker))
+    }
+
+    // Every aggregator answer goes through here, so the rest of the contract only deals with ONE precision.
+    // Pairs without configured decimals are assumed to be quoted in the stablecoin's decimals.
+    fn normalize_price(
+        &self,
+        price: &BigUint,
+        from_ticker: &ManagedBuffer,
+        to_ticker: &ManagedBuffer,
+    ) -> BigUint {
+        let price_num_decimals_mapper = self.price_num_decimals(from_ticker, to_ticker);
+        let price_num_decimals = if price_num_decimals_mapper.is_empty() {
+            STABLE_COIN_NUM_DECIMALS as u32
+        } else {
+            price_num_decimals_mapper.get()
+        };
+
+        self.divide(
+            price,
+            &self.create_precision_biguint(price_num_decimals),
+            &BigUint::from(ONE),
+        )
+    }
+
+    // A round's age is counted from the block its aggregator closed it in, so only aggregators
+    // exposing latestRoundInfo, like the in-repo one, can be used while the staleness check is enabled.
+    // The oracle setters enforce that through require_price_rounds_readable.
+    fn is_price_round_fresh(
+        &self,
+        collateral_id: &TokenIdentifier,
+        aggregator_address: &ManagedAddress,
+        from_ticker: &ManagedBuffer,
+        to_ticker: &ManagedBuffer,
+        round_id: u32,
+    ) -> bool {
+        let max_price_age_blocks = self.max_price_age_blocks(collateral_id).get();
+        if max_price_age_blocks == 0 {
+            return true;
+        }
+
+        let result: OptionalArg<MultiArg3<u32, u64, u64>> = self
+            .price_round_proxy(aggregator_address.clone())
+            .latest_round_info(from_ticker.clone(), to_ticker.clone())
+            .execute_on_dest_context();
+        let (latest_round_id, round_block_nonce, _) = match result.into_option() {
+            Some(round_info) => round_info.into_tuple(),
+            None => return false,
+        };
+
+        latest_round_id == round_id
+            && self.blockchain().get_block_nonce() - round_block_nonce <= max_price_age_blocks
+    }
+
+    // Queries latestRoundInfo for every pair the collateral's aggregators will be asked for,
+    // so an aggregator without the endpoint fails its configuration instead of every later price read.
+    fn require_price_rounds_readable(&self, collateral_id: &TokenIdentifier) {
+        if self.max_price_age_blocks(collateral_id).get() == 0 {
+            return;
+        }
+
+        let (from_ticker, to_ticker) = self.get_collateral_ticker_pair(collateral_id);
+        let mut pairs = Vec::new();
+        let cross_rate_mapper = self.cross_rate_intermediate(collateral_id);
+        if cross_rate_mapper.is_empty() {
+            pairs.push((from_ticker, to_ticker));
+        } else {
+            let intermediate_ticker = cross_rate_mapper.get();
+            pairs.push((from_ticker, intermediate_ticker.clone()));
+            pairs.push((intermediate_ticker, to_ticker));
+        }
+
+        let mut aggregator_addresses: Vec<ManagedAddress> =
+            self.price_sources(collateral_id).iter().collect();
+        if aggregator_addresses.is_empty() {
+            aggregator_addresses.push(self.price_aggregator_address().get());
+            let fallback_address_mapper = self.fallback_price_aggregator_address(collateral_id);
+            if !fallback_address_mapper.is_empty() {
+                aggregator_addresses.push(fallback_address_mapper.get());
+            }
+        }
+
+        for aggregator_address in aggregator_addresses.iter() {
+            if aggregator_address.is_zero() {
+                continue;
+            }
+
+            for (from, to) in pairs.iter() {
+                let _: OptionalArg<MultiArg3<u32, u64, u64>> = self
+                    .price_round_proxy(aggregator_address.clone())
+                    .latest_round_info(from.clone(), to.clone())
+                    .execute_on_dest_context();
+            }
+        }
+    }
+
+    // Needs a fresh price from more than half of the sources, so with at least three sources a single
+    // stale or outlying feed can't move the price outside the honest range. A source failing the call,
+    // instead of answering without a price, still fails the whole transaction, as synchronous calls
+    // can't be caught, so any one source can halt the pool until it's removed with removePriceSource.
+    fn get_median_price(&self, collateral_id: &TokenIdentifier) -> SCResult<BigUint> {
+        let price_sources = self.price_sources(collateral_id);
+        let mut prices: Vec<BigUint> = Vec::new();
+        for source_address in price_sources.iter() {
+            if let Some(price) = self.get_fresh_price(collateral_id, &source_address) {
+                // insertion sort, there are at most MAX_PRICE_SOURCES prices
+                let position = prices.iter().take_while(|p| *p <= &price).count();
+                prices.insert(position, price);
+            }
+        }
+        require!(
+            prices.len() * 2 > price_sources.len(),
+            "Not enough fresh price sources"
+        );
+
+        let middle = prices.len() / 2;
+        if prices.len() % 2 == 1 {
+            return Ok(prices[middle].clone());
+        }
+
+        Ok((&prices[middle - 1] + &prices[middle]) / BigUint::from(2u32))
+    }
+
+    // the pair the aggregators are queried with, quoted in dollars unless set otherwise
+    fn get_collateral_ticker_pair(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> (ManagedBuffer, ManagedBuffer) {
+        let from_ticker = self.collateral_ticker(collateral_id).get();
+        let quote_ticker_mapper = self.collateral_quote_ticker(collateral_id);
+        let to_ticker = if quote_ticker_mapper.is_empty() {
+            ManagedBuffer::from(DOLLAR_TICKER)
+        } else {
+            quote_ticker_mapper.get()
+        };
+
+        (from_ticker, to_ticker)
+    }
+
+    // collaterals whitelisted before the precision was stored only have their decimals
     fn get_collateral_precision(&self, collateral_id: &TokenIdentifier) -> BigUint {
-        let collateral_num_decimals = self.collateral_num_decimals(collateral_id).get();
-        self.create_precision_biguint(collateral_num_decimals)
+        let collateral_precision_mapper = self.collateral_precision(collateral_id);
+        if collateral_precision_mapper.is_empty() {
+            let collateral_num_decimals = self.collateral_num_decimals(collateral_id).get();
+            return self.create_precision_biguint(collateral_num_decimals);
+        }
+
+        collateral_precision_mapper.get()
     }
 
     #[inline(always)]
@@ -82,6 +548,109 @@ pub trait PoolsModule:
         self.collateral_whitelisted(collateral_id).get()
     }
 
+    // follows the migrations of a re-issued collateral to its current token
+    fn resolve_migrated_collateral(&self, collateral_id: &TokenIdentifier) -> TokenIdentifier {
+        let mut current_id = collateral_id.clone();
+        while !self.collateral_migrated_to(&current_id).is_empty() {
+            current_id = self.collateral_migrated_to(&current_id).get();
+        }
+
+        current_id
+    }
+
+    // zero threshold disables the alert
+    fn check_pool_imbalance(
+        &self,
+        collateral_id: &TokenIdentifier,
+        collateral_value_in_dollars: &BigUint,
+    ) {
+        let imbalance_threshold = self.pool_imbalance_threshold(collateral_id).get();
+        if imbalance_threshold == 0 {
+            return;
+        }
+
+        let pool = self.get_pool(collateral_id);
+        if pool.stablecoin_amount == 0 {
+            return;
+        }
+
+        let collateral_precision = self.get_collateral_precision(collateral_id);
+        let pool_value_in_dollars = self.value_in_stablecoin(
+            &pool.collateral_amount,
+            collateral_value_in_dollars,
+            &collateral_precision,
+        );
+        let deviation = if pool_value_in_dollars > pool.stablecoin_amount {
+            &pool_value_in_dollars - &pool.stablecoin_amount
+        } else {
+            &pool.stablecoin_amount - &pool_value_in_dollars
+        };
+        let max_deviation =
+            self.calculate_percentage_of(&imbalance_threshold, &pool.stablecoin_amount);
+        if deviation > max_deviation {
+            self.pool_imbalance_event(
+                collateral_id,
+                &pool_value_in_dollars,
+                &pool.stablecoin_amount,
+            );
+        }
+    }
+
+    // enforces the collateral's max stablecoin supply on the minted amount
+    fn add_stablecoin_minted_for_collateral(
+        &self,
+        collateral_id: &TokenIdentifier,
+        stablecoin_amount: &BigUint,
+    ) -> SCResult<()> {
+        let minted_amount = self.stablecoin_minted_for_collateral(collateral_id).get();
+        let new_minted_amount = minted_amount + stablecoin_amount;
+        let covered_value_in_stablecoin = self
+            .get_pool(collateral_id)
+            .total_covered_value_in_stablecoin;
+        self.require_under_max_stablecoin_supply(
+            collateral_id,
+            &new_minted_amount,
+            &covered_value_in_stablecoin,
+        )?;
+
+        self.stablecoin_minted_for_collateral(collateral_id)
+            .set(&new_minted_amount);
+
+        Ok(())
+    }
+
+    // stablecoins are fungible, so more may be redeemed against a collateral than were minted with it
+    fn remove_stablecoin_minted_for_collateral(
+        &self,
+        collateral_id: &TokenIdentifier,
+        stablecoin_amount: &BigUint,
+    ) {
+        self.stablecoin_minted_for_collateral(collateral_id)
+            .update(|minted_amount| {
+                if *minted_amount > *stablecoin_amount {
+                    *minted_amount -= stablecoin_amount;
+                } else {
+                    *minted_amount = BigUint::zero();
+                }
+            });
+    }
+
+    // the ceiling covers the minted stablecoins plus the hedged value, zero means no ceiling
+    fn require_under_max_stablecoin_supply(
+        &self,
+        collateral_id: &TokenIdentifier,
+        minted_amount: &BigUint,
+        covered_value_in_stablecoin: &BigUint,
+    ) -> SCResult<()> {
+        let max_stablecoin_supply = self.max_stablecoin_supply(collateral_id).get();
+        let stablecoin_amount = minted_amount + covered_value_in_stablecoin;
+        require!(
+            max_stablecoin_supply == 0 || stablecoin_amount <= max_stablecoin_supply,
+            "Over the collateral's max stablecoin supply"
+        );
+        Ok(())
+    }
+
     fn require_collateral_in_whitelist(&self, collateral_id: &TokenIdentifier) -> SCResult<()> {
         require!(
             self.is_collateral_whitelisted(collateral_id),
@@ -90,12 +659,44 @@ pub trait PoolsModule:
         Ok(())
     }
 
+    // deprecated collateral can't be used to mint stablecoins, open hedging positions, add liquidity
+    // or queue redemption requests
+    fn require_collateral_not_deprecated(&self, collateral_id: &TokenIdentifier) -> SCResult<()> {
+        require!(
+            !self.collateral_deprecated(collateral_id).get(),
+            "collateral is deprecated"
+        );
+        Ok(())
+    }
+
+    // lets the owner isolate a single collateral, e.g. during an oracle incident
+    fn require_collateral_not_paused(&self, collateral_id: &TokenIdentifier) -> SCResult<()> {
+        require!(
+            !self.collateral_paused(collateral_id).get(),
+            "collateral is paused"
+        );
+        Ok(())
+    }
+
     // storage
 
     #[view(isCollateralWhitelisted)]
     #[storage_mapper("collateralWhitelisted")]
     fn collateral_whitelisted(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<bool>;
 
+    // same as collateral_whitelisted, but iterable
+    #[view(getWhitelistedCollaterals)]
+    #[storage_mapper("whitelistedCollaterals")]
+    fn whitelisted_collaterals(&self) -> SetMapper<TokenIdentifier>;
+
+    #[view(isCollateralDeprecated)]
+    #[storage_mapper("collateralDeprecated")]
+    fn collateral_deprecated(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<bool>;
+
+    #[view(isCollateralPaused)]
+    #[storage_mapper("collateralPaused")]
+    fn collateral_paused(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<bool>;
+
     #[view(getCollateralTicker)]
     #[storage_mapper("collateralTicker")]
     fn collateral_ticker(
@@ -103,13 +704,140 @@ pub trait PoolsModule:
         collateral_id: &TokenIdentifier,
     ) -> SingleValueMapper<ManagedBuffer>;
 
+    // the quote the price aggregators return the collateral price in, empty means dollars
+    #[view(getCollateralQuoteTicker)]
+    #[storage_mapper("collateralQuoteTicker")]
+    fn collateral_quote_ticker(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<ManagedBuffer>;
+
+    #[view(getCollateralNumDecimals)]
     #[storage_mapper("collateralNumDecimals")]
     fn collateral_num_decimals(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<u32>;
 
+    // 10^num_decimals, stored when the decimals are read from the token properties
+    #[storage_mapper("collateralPrecision")]
+    fn collateral_precision(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    // the most stablecoins the collateral may back: the ones minted with it plus the hedged value
+    #[view(getMaxStablecoinSupply)]
+    #[storage_mapper("maxStablecoinSupply")]
+    fn max_stablecoin_supply(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    // max deviation of the pool's dollar value from its stablecoin amount, as a percentage
+    #[view(getPoolImbalanceThreshold)]
+    #[storage_mapper("poolImbalanceThreshold")]
+    fn pool_imbalance_threshold(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<BigUint>;
+
+    // collateral_reserves target, as a percentage of the pool's collateral amount
+    #[view(getTargetReserveRatio)]
+    #[storage_mapper("targetReserveRatio")]
+    fn target_reserve_ratio(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    #[view(getCollateralMigratedTo)]
+    #[storage_mapper("collateralMigratedTo")]
+    fn collateral_migrated_to(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<TokenIdentifier>;
+
+    // the part of collateral_reserves owned by the protocol: treasury deposits, fees and swept balances
+    #[view(getProtocolOwnedReserves)]
+    #[storage_mapper("protocolOwnedReserves")]
+    fn protocol_owned_reserves(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<BigUint>;
+
+    // zero disables the staleness check
+    #[view(getMaxPriceAgeBlocks)]
+    #[storage_mapper("maxPriceAgeBlocks")]
+    fn max_price_age_blocks(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<u64>;
+
+    #[view(getFallbackPriceAggregatorAddress)]
+    #[storage_mapper("fallbackPriceAggregatorAddress")]
+    fn fallback_price_aggregator_address(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<ManagedAddress>;
+
+    #[view(getPriceSources)]
+    #[storage_mapper("priceSources")]
+    fn price_sources(&self, collateral_id: &TokenIdentifier) -> SetMapper<ManagedAddress>;
+
+    // ticker of the asset the collateral is priced through
+    #[view(getCrossRateIntermediate)]
+    #[storage_mapper("crossRateIntermediate")]
+    fn cross_rate_intermediate(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<ManagedBuffer>;
+
+    // number of decimals of the aggregator's answers for the pair
+    #[view(getPriceNumDecimals)]
+    #[storage_mapper("priceNumDecimals")]
+    fn price_num_decimals(
+        &self,
+        from_ticker: &ManagedBuffer,
+        to_ticker: &ManagedBuffer,
+    ) -> SingleValueMapper<u32>;
+
+    // block nonce and price of the last oracle lookup
+    #[storage_mapper("cachedPrice")]
+    fn cached_price(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<(u64, BigUint)>;
+
+    // min and max acceptable oracle prices, against misconfigured feeds
+    #[view(getPriceBand)]
+    #[storage_mapper("priceBand")]
+    fn price_band(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<(BigUint, BigUint)>;
+
+    // max deviation from the last recorded price, as a percentage
+    #[view(getMaxPriceDeviation)]
+    #[storage_mapper("maxPriceDeviation")]
+    fn max_price_deviation(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    #[view(isPriceCircuitBreakerTripped)]
+    #[storage_mapper("priceCircuitBreakerTripped")]
+    fn price_circuit_breaker_tripped(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<bool>;
+
+    // price and the block nonce it expires at
+    #[view(getEmergencyPrice)]
+    #[storage_mapper("emergencyPrice")]
+    fn emergency_price(&self, collateral_id: &TokenIdentifier)
+        -> SingleValueMapper<(BigUint, u64)>;
+
+    #[view(getGuardianAddress)]
+    #[storage_mapper("guardianAddress")]
+    fn guardian_address(&self) -> SingleValueMapper<ManagedAddress>;
+
+    #[view(getTreasuryAddress)]
+    #[storage_mapper("treasuryAddress")]
+    fn treasury_address(&self) -> SingleValueMapper<ManagedAddress>;
+
+    // stablecoins minted against the collateral and not yet redeemed against it
+    #[view(getStablecoinMintedForCollateral)]
+    #[storage_mapper("stablecoinMintedForCollateral")]
+    fn stablecoin_minted_for_collateral(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<BigUint>;
+
     #[view(getPoolForCollateral)]
     #[storage_mapper("poolForCollateral")]
     fn pool_for_collateral(
         &self,
         collateral_id: &TokenIdentifier,
     ) -> SingleValueMapper<Pool<Self::Api>>;
+
+    // proxies
+
+    #[proxy]
+    fn price_round_proxy(&self, sc_address: ManagedAddress) -> price_round_proxy::Proxy<Self::Api>;
 }
@@ -0,0 +1,13 @@
+numbat_wasm::imports!();
+
+// round details of the in-repo price aggregator, not exposed by price_aggregator_proxy
+#[numbat_wasm::proxy]
+pub trait PriceRound {
+    // (round id, block nonce, timestamp) of the pair's last round
+    #[view(latestRoundInfo)]
+    fn latest_round_info(
+        &self,
+        from: ManagedBuffer,
+        to: ManagedBuffer,
+    ) -> OptionalResult<MultiResult3<u32, u64, u64>>;
+}
@@ -0,0 +1,95 @@
+numbat_wasm::imports!();
+
+use crate::math::PERCENTAGE_PRECISION;
+
+#[numbat_wasm::module]
+pub trait ReferralsModule:
+    crate::math::MathModule + crate::pools::PoolsModule + price_aggregator_proxy::PriceAggregatorModule
+{
+    // the slice of the swap transaction fees that goes to the referrer
+    #[only_owner]
+    #[endpoint(setReferralFeePercentage)]
+    fn set_referral_fee_percentage(&self, referral_fee_percentage: BigUint) -> SCResult<()> {
+        require!(
+            referral_fee_percentage <= PERCENTAGE_PRECISION,
+            "Invalid referral fee percentage"
+        );
+
+        self.referral_fee_percentage().set(&referral_fee_percentage);
+
+        Ok(())
+    }
+
+    #[endpoint(claimReferralFees)]
+    fn claim_referral_fees(&self, collateral_id: TokenIdentifier) -> SCResult<()> {
+        let caller = self.blockchain().get_caller();
+        let fees_amount = self.referral_fees(&caller, &collateral_id).get();
+        require!(fees_amount > 0, "No referral fees to claim");
+
+        self.referral_fees(&caller, &collateral_id).clear();
+        // fees accrued before a migration are paid in the new token
+        let payout_collateral_id = self.resolve_migrated_collateral(&collateral_id);
+        self.total_referral_fees(&payout_collateral_id)
+            .update(|total_fees| *total_fees -= &fees_amount);
+        self.send()
+            .direct(&caller, &payout_collateral_id, 0, &fees_amount, &[]);
+
+        Ok(())
+    }
+
+    fn require_valid_referrer(
+        &self,
+        opt_referrer: &Option<ManagedAddress>,
+        caller: &ManagedAddress,
+    ) -> SCResult<()> {
+        if let Some(referrer) = opt_referrer {
+            require!(referrer != caller, "Cannot refer yourself");
+        }
+        Ok(())
+    }
+
+    // returns the fees left after the referrer's share
+    fn take_referral_fees(
+        &self,
+        collateral_id: &TokenIdentifier,
+        fees_amount: BigUint,
+        opt_referrer: &Option<ManagedAddress>,
+    ) -> BigUint {
+        let referrer = match opt_referrer {
+            Some(referrer) => referrer,
+            None => return fees_amount,
+        };
+
+        let referral_fee_percentage = self.referral_fee_percentage().get();
+        let referral_amount = self.calculate_percentage_of(&referral_fee_percentage, &fees_amount);
+        if referral_amount == 0 {
+            return fees_amount;
+        }
+
+        self.referral_fees(referrer, collateral_id)
+            .update(|referral_fees| *referral_fees += &referral_amount);
+        self.total_referral_fees(collateral_id)
+            .update(|total_fees| *total_fees += &referral_amount);
+
+        fees_amount - referral_amount
+    }
+
+    // storage
+
+    #[view(getReferralFeePercentage)]
+    #[storage_mapper("referralFeePercentage")]
+    fn referral_fee_percentage(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getReferralFees)]
+    #[storage_mapper("referralFees")]
+    fn referral_fees(
+        &self,
+        referrer: &ManagedAddress,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<BigUint>;
+
+    // unclaimed referral fees of all the referrers
+    #[view(getTotalReferralFees)]
+    #[storage_mapper("totalReferralFees")]
+    fn total_referral_fees(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+}
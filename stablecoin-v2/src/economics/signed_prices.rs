@@ -0,0 +1,160 @@
+numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+use crate::math::PERCENTAGE_PRECISION;
+
+#[derive(TypeAbi, TopEncode, TopDecode)]
+pub struct SignedPrice<M: ManagedTypeApi> {
+    pub price: BigUint<M>,
+    pub timestamp: u64,
+    pub signer: ManagedBuffer<M>,
+}
+
+#[numbat_wasm::module]
+pub trait SignedPricesModule: crate::events::EventsModule {
+    #[only_owner]
+    #[endpoint(addOracleSigner)]
+    fn add_oracle_signer(&self, public_key: ManagedBuffer) -> SCResult<()> {
+        require!(public_key.len() == 32, "Invalid public key");
+
+        self.oracle_signers().insert(public_key);
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint(removeOracleSigner)]
+    fn remove_oracle_signer(&self, public_key: ManagedBuffer) {
+        self.oracle_signers().remove(&public_key);
+    }
+
+    // zero disables the signed prices, so only the aggregators are used
+    #[only_owner]
+    #[endpoint(setMaxSignedPriceAge)]
+    fn set_max_signed_price_age(&self, max_signed_price_age_seconds: u64) {
+        self.max_signed_price_age_seconds()
+            .set(&max_signed_price_age_seconds);
+    }
+
+    // max deviation of a signed price from the oracle price, as a percentage.
+    // Signed prices further away are ignored, zero only accepts the oracle price itself.
+    #[only_owner]
+    #[endpoint(setMaxSignedPriceDeviation)]
+    fn set_max_signed_price_deviation(&self, max_signed_price_deviation: BigUint) -> SCResult<()> {
+        require!(
+            max_signed_price_deviation <= PERCENTAGE_PRECISION,
+            "Invalid max signed price deviation"
+        );
+
+        self.max_signed_price_deviation()
+            .set(&max_signed_price_deviation);
+
+        Ok(())
+    }
+
+    // The signature covers sc_address | collateral_id | price (big endian) | timestamp | nonce,
+    // the two u64 as 8 big endian bytes. The contract address keeps a payload signed for another
+    // deployment from being accepted here. Each signer's nonces must increase, so a payload
+    // can't be replayed, and the price must be newer than the current one.
+    // Anyone may push a signed payload, usually a keeper.
+    #[endpoint(submitSignedPrice)]
+    fn submit_signed_price(
+        &self,
+        collateral_id: TokenIdentifier,
+        price: BigUint,
+        timestamp: u64,
+        nonce: u64,
+        signer: ManagedBuffer,
+        signature: ManagedBuffer,
+    ) -> SCResult<()> {
+        require!(
+            self.oracle_signers().contains(&signer),
+            "Unknown oracle signer"
+        );
+        require!(price > 0, "Price must be greater than zero");
+        require!(
+            timestamp <= self.blockchain().get_block_timestamp(),
+            "Timestamp is in the future"
+        );
+        require!(
+            nonce > self.last_signer_nonce(&signer).get(),
+            "Nonce already used"
+        );
+
+        let signed_price_mapper = self.signed_price(&collateral_id);
+        if !signed_price_mapper.is_empty() {
+            require!(
+                timestamp > signed_price_mapper.get().timestamp,
+                "Older than the current signed price"
+            );
+        }
+
+        let mut message = ManagedBuffer::new();
+        message.append(self.blockchain().get_sc_address().as_managed_buffer());
+        message.append(collateral_id.as_managed_buffer());
+        message.append_bytes(price.to_bytes_be().as_slice());
+        message.append_bytes(&timestamp.to_be_bytes());
+        message.append_bytes(&nonce.to_be_bytes());
+        require!(
+            self.crypto().verify_ed25519(
+                signer.to_boxed_bytes().as_slice(),
+                message.to_boxed_bytes().as_slice(),
+                signature.to_boxed_bytes().as_slice(),
+            ),
+            "Invalid signature"
+        );
+
+        self.last_signer_nonce(&signer).set(&nonce);
+        self.signed_price_event(&collateral_id, &signer, timestamp, &price);
+        signed_price_mapper.set(&SignedPrice {
+            price,
+            timestamp,
+            signer,
+        });
+
+        Ok(())
+    }
+
+    // None if signed prices are disabled, or if the collateral's last one is too old
+    fn get_fresh_signed_price(&self, collateral_id: &TokenIdentifier) -> Option<BigUint> {
+        let max_signed_price_age_seconds = self.max_signed_price_age_seconds().get();
+        let signed_price_mapper = self.signed_price(collateral_id);
+        if max_signed_price_age_seconds == 0 || signed_price_mapper.is_empty() {
+            return None;
+        }
+
+        let signed_price = signed_price_mapper.get();
+        let current_timestamp = self.blockchain().get_block_timestamp();
+        if current_timestamp - signed_price.timestamp > max_signed_price_age_seconds {
+            return None;
+        }
+
+        Some(signed_price.price)
+    }
+
+    // storage
+
+    #[view(getOracleSigners)]
+    #[storage_mapper("oracleSigners")]
+    fn oracle_signers(&self) -> SetMapper<ManagedBuffer>;
+
+    #[view(getLastSignerNonce)]
+    #[storage_mapper("lastSignerNonce")]
+    fn last_signer_nonce(&self, signer: &ManagedBuffer) -> SingleValueMapper<u64>;
+
+    #[view(getMaxSignedPriceAgeSeconds)]
+    #[storage_mapper("maxSignedPriceAgeSeconds")]
+    fn max_signed_price_age_seconds(&self) -> SingleValueMapper<u64>;
+
+    #[view(getMaxSignedPriceDeviation)]
+    #[storage_mapper("maxSignedPriceDeviation")]
+    fn max_signed_price_deviation(&self) -> SingleValueMapper<BigUint>;
+
+    // in ONE precision, like the normalized aggregator prices
+    #[view(getSignedPrice)]
+    #[storage_mapper("signedPrice")]
+    fn signed_price(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<SignedPrice<Self::Api>>;
+}
@@ -0,0 +1,189 @@
+numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+// the observations are a ring buffer, the oldest one is overwritten once it's full
+pub const MAX_PRICE_OBSERVATIONS: usize = 1_000;
+
+// `price_cumulative` is the sum of price * seconds, up to `timestamp`
+#[derive(TypeAbi, TopEncode, TopDecode, NestedEncode, NestedDecode)]
+pub struct PriceObservation<M: ManagedTypeApi> {
+    pub timestamp: u64,
+    pub price: BigUint<M>,
+    pub price_cumulative: BigUint<M>,
+}
+
+#[numbat_wasm::module]
+pub trait TwapModule {
+    // zero disables the TWAP, so mints use the spot price
+    #[only_owner]
+    #[endpoint(setTwapWindow)]
+    fn set_twap_window(&self, collateral_id: TokenIdentifier, twap_window_seconds: u64) {
+        self.twap_window_seconds(&collateral_id)
+            .set(&twap_window_seconds);
+    }
+
+    // time-weighted average over the collateral's window, or over the kept history if shorter
+    #[view(getTwap)]
+    fn get_twap(&self, collateral_id: &TokenIdentifier) -> BigUint {
+        let len = self.price_observations(collateral_id).len();
+        if len == 0 {
+            return BigUint::zero();
+        }
+
+        let current_timestamp = self.blockchain().get_block_timestamp();
+        let last_observation = self.get_price_observation(collateral_id, len);
+        let twap_window_seconds = self.twap_window_seconds(collateral_id).get();
+        let first_timestamp = self.get_price_observation(collateral_id, 1).timestamp;
+        let window_start = if current_timestamp - first_timestamp > twap_window_seconds {
+            current_timestamp - twap_window_seconds
+        } else {
+            first_timestamp
+        };
+        if window_start == current_timestamp {
+            return last_observation.price;
+        }
+
+        // positions start at 1, the oldest one. timestamp(low) <= window_start < timestamp(high)
+        let mut low = 1;
+        let mut high = len + 1;
+        while high - low > 1 {
+            let mid = (low + high) / 2;
+            if self.get_price_observation(collateral_id, mid).timestamp <= window_start {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        let start_observation = self.get_price_observation(collateral_id, low);
+
+        let seconds_to_start = BigUint::from(window_start - start_observation.timestamp);
+        let cumulative_at_start =
+            &start_observation.price_cumulative + &(&start_observation.price * &seconds_to_start);
+        let seconds_to_now = BigUint::from(current_timestamp - last_observation.timestamp);
+        let cumulative_now =
+            &last_observation.price_cumulative + &(&last_observation.price * &seconds_to_now);
+
+        (cumulative_now - cumulative_at_start) / BigUint::from(current_timestamp - window_start)
+    }
+
+    // the lower of the spot price and the TWAP, so a pumped spot price can't mint extra stablecoins
+    fn get_mint_price(&self, collateral_id: &TokenIdentifier, spot_price: BigUint) -> BigUint {
+        if self.twap_window_seconds(collateral_id).get() == 0 {
+            return spot_price;
+        }
+
+        let twap = self.get_twap(collateral_id);
+        if twap > 0 && twap < spot_price {
+            twap
+        } else {
+            spot_price
+        }
+    }
+
+    fn get_last_observed_price(&self, collateral_id: &TokenIdentifier) -> Option<BigUint> {
+        self.get_last_price_observation(collateral_id)
+            .map(|observation| observation.price)
+    }
+
+    fn get_last_price_observation(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> Option<PriceObservation<Self::Api>> {
+        let len = self.price_observations(collateral_id).len();
+        if len == 0 {
+            return None;
+        }
+
+        Some(self.get_price_observation(collateral_id, len))
+    }
+
+    // position 1 is the oldest kept observation, position len the last one
+    fn get_price_observation(
+        &self,
+        collateral_id: &TokenIdentifier,
+        position: usize,
+    ) -> PriceObservation<Self::Api> {
+        self.price_observations(collateral_id)
+            .get(self.get_price_observation_index(collateral_id, position))
+    }
+
+    fn get_price_observation_index(
+        &self,
+        collateral_id: &TokenIdentifier,
+        position: usize,
+    ) -> usize {
+        let len = self.price_observations(collateral_id).len();
+        let oldest_offset = self.price_observations_offset(collateral_id).get();
+
+        (oldest_offset + position - 1) % len + 1
+    }
+
+    // Several prices with the same timestamp keep only the last one.
+    fn record_price_observation(&self, collateral_id: &TokenIdentifier, price: &BigUint) {
+        let mut observations = self.price_observations(collateral_id);
+        let len = observations.len();
+        let current_timestamp = self.blockchain().get_block_timestamp();
+        if len == 0 {
+            observations.push(&PriceObservation {
+                timestamp: current_timestamp,
+                price: price.clone(),
+                price_cumulative: BigUint::zero(),
+            });
+            return;
+        }
+
+        let mut last_observation = self.get_price_observation(collateral_id, len);
+        if last_observation.timestamp == current_timestamp {
+            last_observation.price = price.clone();
+            observations.set(
+                self.get_price_observation_index(collateral_id, len),
+                &last_observation,
+            );
+            return;
+        }
+
+        let time_diff = BigUint::from(current_timestamp - last_observation.timestamp);
+        let price_cumulative =
+            &last_observation.price_cumulative + &(&last_observation.price * &time_diff);
+        let observation = PriceObservation {
+            timestamp: current_timestamp,
+            price: price.clone(),
+            price_cumulative,
+        };
+        if len < MAX_PRICE_OBSERVATIONS {
+            observations.push(&observation);
+            return;
+        }
+
+        // the oldest observation is replaced, and the next one becomes the oldest
+        let oldest_index = self.get_price_observation_index(collateral_id, 1);
+        observations.set(oldest_index, &observation);
+        self.price_observations_offset(collateral_id)
+            .set(&(oldest_index % len));
+    }
+
+    // drops the whole history, for when the collateral is removed
+    fn clear_price_observations(&self, collateral_id: &TokenIdentifier) {
+        self.price_observations(collateral_id).clear();
+        self.price_observations_offset(collateral_id).clear();
+    }
+
+    // storage
+
+    #[view(getTwapWindowSeconds)]
+    #[storage_mapper("twapWindowSeconds")]
+    fn twap_window_seconds(&self, collateral_id: &TokenIdentifier) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("priceObservations")]
+    fn price_observations(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> VecMapper<PriceObservation<Self::Api>>;
+
+    // 0-based index of the oldest observation in price_observations
+    #[storage_mapper("priceObservationsOffset")]
+    fn price_observations_offset(
+        &self,
+        collateral_id: &TokenIdentifier,
+    ) -> SingleValueMapper<usize>;
+}
@@ -0,0 +1,172 @@
+numbat_wasm::imports!();
+numbat_wasm::derive_imports!();
+
+// fee_amount is in collateral, price is the collateral value in dollars used for the swap
+#[derive(TypeAbi, TopEncode)]
+pub struct SwapEventData<M: ManagedTypeApi> {
+    pub amount_in: BigUint<M>,
+    pub amount_out: BigUint<M>,
+    pub fee_amount: BigUint<M>,
+    pub price: BigUint<M>,
+}
+
+#[numbat_wasm::module]
+pub trait EventsModule {
+    // collateral in, stablecoins out
+    #[event("sellCollateral")]
+    fn sell_collateral_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] collateral_id: &TokenIdentifier,
+        swap_data: &SwapEventData<Self::Api>,
+    );
+
+    // stablecoins in, collateral out
+    #[event("buyCollateral")]
+    fn buy_collateral_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] collateral_id: &TokenIdentifier,
+        swap_data: &SwapEventData<Self::Api>,
+    );
+
+    // the pool's dollar value deviates from its stablecoin amount by more than the threshold
+    #[event("poolImbalance")]
+    fn pool_imbalance_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        #[indexed] pool_value_in_dollars: &BigUint,
+        stablecoin_amount: &BigUint,
+    );
+
+    // the main aggregator had no fresh price, so the collateral's fallback aggregator was used
+    #[event("oracleFallback")]
+    fn oracle_fallback_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        fallback_address: &ManagedAddress,
+    );
+
+    // the collateral's token properties couldn't be read, so it was not whitelisted
+    #[event("collateralWhitelistFailed")]
+    fn collateral_whitelist_failed_event(&self, #[indexed] collateral_id: &TokenIdentifier);
+
+    // the new token's decimals differ from the old one's, or couldn't be read, so the payment was refunded
+    #[event("collateralMigrationFailed")]
+    fn collateral_migration_failed_event(
+        &self,
+        #[indexed] old_collateral_id: &TokenIdentifier,
+        #[indexed] new_collateral_id: &TokenIdentifier,
+    );
+
+    // the guardian set a manual price for the collateral, used instead of the oracles until it expires
+    #[event("emergencyPriceSet")]
+    fn emergency_price_set_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        #[indexed] expiry_block_nonce: u64,
+        price: &BigUint,
+    );
+
+    #[event("signedPrice")]
+    fn signed_price_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        #[indexed] signer: &ManagedBuffer,
+        #[indexed] timestamp: u64,
+        price: &BigUint,
+    );
+
+    // the oracle price is outside the collateral's sanity band, operations using it revert
+    #[event("priceOutOfBand")]
+    fn price_out_of_band_event(&self, #[indexed] collateral_id: &TokenIdentifier, price: &BigUint);
+
+    // the price deviated too much from the last recorded one, swaps and liquidations wait for the guardian
+    #[event("priceCircuitBreakerTripped")]
+    fn price_circuit_breaker_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        #[indexed] last_price: &BigUint,
+        new_price: &BigUint,
+    );
+
+    // the collateral was paused by checkInvariants
+    #[event("solvencyViolation")]
+    fn solvency_violation_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        #[indexed] assets_value_in_dollars: &BigUint,
+        liabilities_value_in_dollars: &BigUint,
+    );
+
+    // collateral held by the contract but not tracked anywhere, moved to the reserves
+    #[event("sweepExcessBalance")]
+    fn sweep_excess_balance_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        amount: &BigUint,
+    );
+
+    // reserves above the target ratio, sent to the treasury
+    #[event("skimReserves")]
+    fn skim_reserves_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        #[indexed] treasury_address: &ManagedAddress,
+        amount: &BigUint,
+    );
+
+    #[event("mintFeesPercentage")]
+    fn mint_fees_percentage_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        #[indexed] min_fees_percentage: &BigUint,
+        max_fees_percentage: &BigUint,
+    );
+
+    #[event("burnFeesPercentage")]
+    fn burn_fees_percentage_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        #[indexed] min_fees_percentage: &BigUint,
+        max_fees_percentage: &BigUint,
+    );
+
+    #[event("feeCurveSlope")]
+    fn fee_curve_slope_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        fee_curve_slope: &BigUint,
+    );
+
+    // the amount is in stablecoins if paid_in_stablecoin is set, in the collateral otherwise
+    #[event("treasuryFees")]
+    fn treasury_fees_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        #[indexed] treasury_address: &ManagedAddress,
+        #[indexed] paid_in_stablecoin: bool,
+        amount: &BigUint,
+    );
+
+    #[event("feesSwapped")]
+    fn fees_swapped_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        #[indexed] fees_amount: &BigUint,
+        stablecoin_amount: &BigUint,
+    );
+
+    // the amount is in stablecoins for splitFeesSwapped, in the collateral otherwise
+    #[event("feeRecipientPayment")]
+    fn fee_recipient_payment_event(
+        &self,
+        #[indexed] collateral_id: &TokenIdentifier,
+        #[indexed] recipient: &ManagedAddress,
+        amount: &BigUint,
+    );
+
+    // zero when the discount is removed
+    #[event("feeDiscount")]
+    fn fee_discount_event(&self, #[indexed] address: &ManagedAddress, fee_discount: &BigUint);
+}
@@ -5,6 +5,7 @@ numbat_wasm::derive_imports!();
 
 mod actors;
 mod economics;
+mod events;
 mod tokens;
 
 use actors::*;
@@ -15,18 +16,26 @@ use tokens::*;
 
 #[numbat_wasm::contract]
 pub trait StablecoinV2:
-    fees::FeesModule
+    collateral_ratio::CollateralRatioModule
+    + events::EventsModule
+    + fees::FeesModule
     + hedging_agents::HedgingAgentsModule
     + hedging_token::HedgingTokenModule
     + keepers::KeepersModule
+    + limit_orders::LimitOrdersModule
     + liquidity_providers::LiquidityProvidersModule
     + liquidity_token::LiquidityTokenModule
     + math::MathModule
+    + pool_info::PoolInfoModule
     + pools::PoolsModule
     + price_aggregator_proxy::PriceAggregatorModule
+    + referrals::ReferralsModule
+    + rewa_wrapper::RewaWrapperModule
+    + signed_prices::SignedPricesModule
     + stablecoin_token::StablecoinTokenModule
     + stable_seekers::StableSeekers
     + token_common::TokenCommonModule
+    + twap::TwapModule
 {
     #[init]
     fn init(
@@ -50,16 +59,25 @@ pub trait StablecoinV2:
         self.target_hedging_ratio().set(&target_hedging_ratio);
         self.hedging_ratio_limit().set(&hedging_ratio_limit);
 
+        // init also runs on upgrade, when the pools may already have minted stablecoins
+        for collateral_id in self.whitelisted_collaterals().iter() {
+            let minted_mapper = self.stablecoin_minted_for_collateral(&collateral_id);
+            if minted_mapper.is_empty() && !self.pool_for_collateral(&collateral_id).is_empty() {
+                minted_mapper.set(&self.get_pool(&collateral_id).stablecoin_amount);
+            }
+        }
+
         Ok(())
     }
 
+    // the collateral is only whitelisted in the callback,
+    // once its decimals are read from the token properties
     #[only_owner]
     #[endpoint(addCollateralToWhitelist)]
     fn add_collateral_to_whitelist(
         &self,
         collateral_id: TokenIdentifier,
         collateral_ticker: ManagedBuffer,
-        collateral_num_decimals: u32,
         max_leverage: BigUint,
         min_fees_percentage: BigUint,
         max_fees_percentage: BigUint,
@@ -67,7 +85,7 @@ pub trait StablecoinV2:
         liq_provider_fee_reward_percentage: BigUint,
         min_slippage_percentage: BigUint,
         max_slippage_percentage: BigUint,
-    ) -> SCResult<()> {
+    ) -> SCResult<AsyncCall> {
         require!(
             min_fees_percentage <= max_fees_percentage
                 && max_fees_percentage < math::PERCENTAGE_PRECISION,
@@ -81,8 +99,6 @@ pub trait StablecoinV2:
 
         self.collateral_ticker(&collateral_id)
             .set(&collateral_ticker);
-        self.collateral_num_decimals(&collateral_id)
-            .set(&collateral_num_decimals);
         self.max_leverage(&collateral_id).set(&max_leverage);
         self.min_max_fees_percentage(&collateral_id)
             .set(&(min_fees_percentage, max_fees_percentage));
@@ -92,26 +108,929 @@ pub trait StablecoinV2:
             .set(&liq_provider_fee_reward_percentage);
         self.min_max_slippage_percentage(&collateral_id)
             .set(&(min_slippage_percentage, max_slippage_percentage));
-        self.collateral_whitelisted(&collateral_id).set(&true);
+
+        Ok(self
+            .get_token_properties(&collateral_id)
+            .async_call()
+            .with_callback(
+                self.callbacks()
+                    .collateral_properties_callback(&collateral_id),
+            ))
+    }
+
+    #[callback]
+    fn collateral_properties_callback(
+        &self,
+        collateral_id: &TokenIdentifier,
+        #[call_result] result: ManagedAsyncCallResult<MultiResultVec<ManagedBuffer>>,
+    ) {
+        let opt_num_decimals = match result {
+            ManagedAsyncCallResult::Ok(properties) => self.parse_num_decimals(properties),
+            ManagedAsyncCallResult::Err(_) => None,
+        };
+        let collateral_num_decimals = match opt_num_decimals {
+            Some(num_decimals) => num_decimals,
+            None => {
+                self.clear_unlisted_collateral_settings(collateral_id);
+                self.collateral_whitelist_failed_event(collateral_id);
+                return;
+            }
+        };
+
+        self.collateral_num_decimals(collateral_id)
+            .set(&collateral_num_decimals);
+        self.collateral_precision(collateral_id)
+            .set(&self.create_precision_biguint(collateral_num_decimals));
+        self.collateral_whitelisted(collateral_id).set(&true);
+        self.whitelisted_collaterals().insert(collateral_id.clone());
 
         // preserve the pool info if it was added, removed, and then added again
-        self.pool_for_collateral(&collateral_id)
+        self.pool_for_collateral(collateral_id)
             .set_if_empty(&pools::Pool::new(self.raw_vm_api()));
+    }
+
+    // zero means no ceiling
+    #[only_owner]
+    #[endpoint(setMaxStablecoinSupply)]
+    fn set_max_stablecoin_supply(
+        &self,
+        collateral_id: TokenIdentifier,
+        max_stablecoin_supply: BigUint,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        self.max_stablecoin_supply(&collateral_id)
+            .set(&max_stablecoin_supply);
+
+        Ok(())
+    }
+
+    // for assets whose token ticker doesn't match the oracle symbol,
+    // the quote ticker must be one the contract's prices can be expressed in, usually dollars
+    #[only_owner]
+    #[endpoint(setCollateralTickerPair)]
+    fn set_collateral_ticker_pair(
+        &self,
+        collateral_id: TokenIdentifier,
+        from_ticker: ManagedBuffer,
+        to_ticker: ManagedBuffer,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+        require!(
+            !from_ticker.is_empty() && !to_ticker.is_empty(),
+            "Empty ticker"
+        );
+
+        self.cached_price(&collateral_id).clear();
+        self.collateral_ticker(&collateral_id).set(&from_ticker);
+        self.collateral_quote_ticker(&collateral_id)
+            .set(&to_ticker);
+        self.require_price_rounds_readable(&collateral_id);
+
+        Ok(())
+    }
+
+    // prices the collateral through an intermediate asset, e.g. TOKEN/REWA x REWA/USD,
+    // for collaterals with no direct feed. None prices it directly again
+    #[only_owner]
+    #[endpoint(setCollateralCrossRate)]
+    fn set_collateral_cross_rate(
+        &self,
+        collateral_id: TokenIdentifier,
+        #[var_args] opt_intermediate_ticker: OptionalArg<ManagedBuffer>,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        self.cached_price(&collateral_id).clear();
+        match opt_intermediate_ticker.into_option() {
+            Some(intermediate_ticker) => {
+                require!(!intermediate_ticker.is_empty(), "Empty ticker");
+
+                self.cross_rate_intermediate(&collateral_id)
+                    .set(&intermediate_ticker);
+            }
+            None => self.cross_rate_intermediate(&collateral_id).clear(),
+        }
+        self.require_price_rounds_readable(&collateral_id);
+
+        Ok(())
+    }
+
+    // the number of decimals the aggregators answer with for the pair,
+    // so their prices can be normalized to the contract's precision
+    #[only_owner]
+    #[endpoint(setPriceNumDecimals)]
+    fn set_price_num_decimals(
+        &self,
+        from_ticker: ManagedBuffer,
+        to_ticker: ManagedBuffer,
+        price_num_decimals: u32,
+    ) {
+        self.price_num_decimals(&from_ticker, &to_ticker)
+            .set(&price_num_decimals);
+
+        // the pair may be any leg of any collateral's price
+        for collateral_id in self.whitelisted_collaterals().iter() {
+            self.cached_price(&collateral_id).clear();
+        }
+    }
+
+    // used when the main price aggregator has no fresh price for the collateral, none removes it.
+    // Not used if the main aggregator's call fails, as that fails the whole transaction
+    #[only_owner]
+    #[endpoint(setFallbackPriceAggregator)]
+    fn set_fallback_price_aggregator(
+        &self,
+        collateral_id: TokenIdentifier,
+        #[var_args] opt_aggregator_address: OptionalArg<ManagedAddress>,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        self.cached_price(&collateral_id).clear();
+        match opt_aggregator_address.into_option() {
+            Some(aggregator_address) => {
+                require!(
+                    self.blockchain().is_smart_contract(&aggregator_address),
+                    "Price aggregator address is not a smart contract"
+                );
+
+                self.fallback_price_aggregator_address(&collateral_id)
+                    .set(&aggregator_address);
+            }
+            None => self
+                .fallback_price_aggregator_address(&collateral_id)
+                .clear(),
+        }
+        self.require_price_rounds_readable(&collateral_id);
+
+        Ok(())
+    }
+
+    // once a collateral has price sources, its price is their median and the main
+    // and fallback aggregators are no longer used for it.
+    // Any one source failing its calls halts the collateral's pool until it's removed
+    #[only_owner]
+    #[endpoint(addPriceSource)]
+    fn add_price_source(
+        &self,
+        collateral_id: TokenIdentifier,
+        source_address: ManagedAddress,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+        require!(
+            self.blockchain().is_smart_contract(&source_address),
+            "Price source address is not a smart contract"
+        );
+
+        let mut price_sources = self.price_sources(&collateral_id);
+        require!(
+            price_sources.len() < pools::MAX_PRICE_SOURCES,
+            "Too many price sources"
+        );
+        require!(
+            price_sources.insert(source_address),
+            "Price source already added"
+        );
+        self.cached_price(&collateral_id).clear();
+        self.require_price_rounds_readable(&collateral_id);
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint(removePriceSource)]
+    fn remove_price_source(
+        &self,
+        collateral_id: TokenIdentifier,
+        source_address: ManagedAddress,
+    ) -> SCResult<()> {
+        require!(
+            self.price_sources(&collateral_id).remove(&source_address),
+            "Unknown price source"
+        );
+
+        self.cached_price(&collateral_id).clear();
+        self.require_price_rounds_readable(&collateral_id);
 
         Ok(())
     }
 
+    // zero disables the oracle price staleness check. Enabling it requires every aggregator
+    // pricing the collateral to expose latestRoundInfo, which the staleness check reads
+    #[only_owner]
+    #[endpoint(setMaxPriceAgeBlocks)]
+    fn set_max_price_age_blocks(
+        &self,
+        collateral_id: TokenIdentifier,
+        max_price_age_blocks: u64,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        self.cached_price(&collateral_id).clear();
+        self.max_price_age_blocks(&collateral_id)
+            .set(&max_price_age_blocks);
+        self.require_price_rounds_readable(&collateral_id);
+
+        Ok(())
+    }
+
+    // zero disables the pool imbalance events
+    #[only_owner]
+    #[endpoint(setPoolImbalanceThreshold)]
+    fn set_pool_imbalance_threshold(
+        &self,
+        collateral_id: TokenIdentifier,
+        imbalance_threshold: BigUint,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        self.pool_imbalance_threshold(&collateral_id)
+            .set(&imbalance_threshold);
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint(setGuardianAddress)]
+    fn set_guardian_address(&self, guardian_address: ManagedAddress) {
+        self.guardian_address().set(&guardian_address);
+    }
+
+    // zero disables the price circuit breaker
+    #[only_owner]
+    #[endpoint(setMaxPriceDeviation)]
+    fn set_max_price_deviation(
+        &self,
+        collateral_id: TokenIdentifier,
+        max_price_deviation: BigUint,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        self.max_price_deviation(&collateral_id)
+            .set(&max_price_deviation);
+
+        Ok(())
+    }
+
+    // oracle prices outside [min_price, max_price] are rejected, none removes the band
+    #[only_owner]
+    #[endpoint(setPriceBand)]
+    fn set_price_band(
+        &self,
+        collateral_id: TokenIdentifier,
+        #[var_args] opt_price_band: OptionalArg<MultiArg2<BigUint, BigUint>>,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        match opt_price_band.into_option() {
+            Some(price_band) => {
+                let (min_price, max_price) = price_band.into_tuple();
+                require!(
+                    min_price <= max_price && max_price > 0,
+                    "Invalid price band"
+                );
+
+                self.price_band(&collateral_id)
+                    .set(&(min_price, max_price));
+            }
+            None => self.price_band(&collateral_id).clear(),
+        }
+
+        Ok(())
+    }
+
+    // accepts the current oracle price as the new reference and resets the circuit breaker
+    #[endpoint(confirmCollateralPrice)]
+    fn confirm_collateral_price(&self, collateral_id: TokenIdentifier) -> SCResult<BigUint> {
+        self.require_guardian_or_owner()?;
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        let price = self.fetch_collateral_value_in_dollars(&collateral_id)?;
+        self.require_price_in_band(&collateral_id, &price)?;
+        self.record_price_observation(&collateral_id, &price);
+        self.price_circuit_breaker_tripped(&collateral_id).clear();
+
+        Ok(price)
+    }
+
+    // for when the oracles are down but liquidations must continue,
+    // the price expires after `duration_blocks`
+    #[endpoint(setEmergencyPrice)]
+    fn set_emergency_price(
+        &self,
+        collateral_id: TokenIdentifier,
+        price: BigUint,
+        duration_blocks: u64,
+    ) -> SCResult<()> {
+        self.require_guardian_or_owner()?;
+        self.require_collateral_in_whitelist(&collateral_id)?;
+        require!(price > 0, "Price must be greater than zero");
+        require!(
+            duration_blocks > 0
+                && duration_blocks <= pools::MAX_EMERGENCY_PRICE_DURATION_BLOCKS,
+            "Invalid duration"
+        );
+
+        let expiry_block_nonce = self.blockchain().get_block_nonce() + duration_blocks;
+        self.emergency_price(&collateral_id)
+            .set(&(price.clone(), expiry_block_nonce));
+        self.emergency_price_set_event(&collateral_id, expiry_block_nonce, &price);
+
+        Ok(())
+    }
+
+    #[endpoint(clearEmergencyPrice)]
+    fn clear_emergency_price(&self, collateral_id: TokenIdentifier) -> SCResult<()> {
+        self.require_guardian_or_owner()?;
+
+        self.emergency_price(&collateral_id).clear();
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint(setTreasuryAddress)]
+    fn set_treasury_address(&self, treasury_address: ManagedAddress) {
+        self.treasury_address().set(&treasury_address);
+    }
+
+    // the treasury's part of the collateral's transaction fees, next to the liquidity providers' part,
+    // paid in the collateral or in stablecoins minted against it
+    #[only_owner]
+    #[endpoint(setTreasuryFeeShare)]
+    fn set_treasury_fee_share(
+        &self,
+        collateral_id: TokenIdentifier,
+        treasury_fee_percentage: BigUint,
+        paid_in_stablecoin: bool,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+        let fee_recipients_percentage = self.get_total_fee_recipients_percentage(&collateral_id);
+        self.require_valid_fee_shares(
+            &collateral_id,
+            &treasury_fee_percentage,
+            &fee_recipients_percentage,
+        )?;
+
+        self.treasury_fee_percentage(&collateral_id)
+            .set(&treasury_fee_percentage);
+        self.treasury_fees_in_stablecoin(&collateral_id)
+            .set(&paid_in_stablecoin);
+
+        Ok(())
+    }
+
+    // Replaces the collateral's fee recipients, each with its part of the transaction fees,
+    // e.g. an insurance fund or a buyback contract. Calling it without recipients removes them all.
+    #[only_owner]
+    #[endpoint(setFeeRecipients)]
+    fn set_fee_recipients(
+        &self,
+        collateral_id: TokenIdentifier,
+        #[var_args] recipients: VarArgs<MultiArg2<ManagedAddress, BigUint>>,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+        require!(
+            recipients.len() <= fees::MAX_FEE_RECIPIENTS,
+            "Too many fee recipients"
+        );
+
+        let mut fee_recipients = self.fee_recipients(&collateral_id);
+        fee_recipients.clear();
+        let mut fee_recipients_percentage = BigUint::zero();
+        for recipient in recipients.into_vec() {
+            let (recipient_address, fee_percentage) = recipient.into_tuple();
+            require!(fee_percentage > 0, "Invalid fee percentage");
+
+            fee_recipients_percentage += &fee_percentage;
+            require!(
+                fee_recipients.insert(recipient_address, fee_percentage).is_none(),
+                "Duplicate fee recipient"
+            );
+        }
+        self.require_valid_fee_shares(
+            &collateral_id,
+            &self.treasury_fee_percentage(&collateral_id).get(),
+            &fee_recipients_percentage,
+        )?;
+
+        Ok(())
+    }
+
+    // collateral added to the reserves by the protocol, tracked separately from the users' collateral
+    #[only_owner]
+    #[payable("*")]
+    #[endpoint(depositProtocolCollateral)]
+    fn deposit_protocol_collateral(
+        &self,
+        #[payment_token] payment_token: TokenIdentifier,
+        #[payment_amount] payment_amount: BigUint,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&payment_token)?;
+
+        self.update_pool(&payment_token, |pool| {
+            pool.collateral_reserves += &payment_amount;
+        });
+        self.add_protocol_owned_reserves(&payment_token, &payment_amount);
+
+        Ok(())
+    }
+
+    // only the protocol-owned part of the reserves may be withdrawn, to the treasury
+    #[only_owner]
+    #[endpoint(withdrawProtocolCollateral)]
+    fn withdraw_protocol_collateral(
+        &self,
+        collateral_id: TokenIdentifier,
+        amount: BigUint,
+    ) -> SCResult<()> {
+        require!(
+            !self.treasury_address().is_empty(),
+            "Treasury address not set"
+        );
+        require!(
+            amount <= self.get_protocol_owned_reserves(&collateral_id),
+            "Amount higher than the protocol-owned reserves"
+        );
+
+        self.remove_protocol_owned_reserves(&collateral_id, &amount);
+        self.update_pool(&collateral_id, |pool| {
+            pool.collateral_reserves -= &amount;
+        });
+
+        let treasury_address = self.treasury_address().get();
+        self.send()
+            .direct(&treasury_address, &collateral_id, 0, &amount, &[]);
+
+        Ok(())
+    }
+
+    // zero disables skimming the reserves
+    #[only_owner]
+    #[endpoint(setTargetReserveRatio)]
+    fn set_target_reserve_ratio(
+        &self,
+        collateral_id: TokenIdentifier,
+        target_reserve_ratio: BigUint,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        self.target_reserve_ratio(&collateral_id)
+            .set(&target_reserve_ratio);
+
+        Ok(())
+    }
+
+    // wind-down mode: redemptions, hedging position closes and liquidity removals stay open
+    #[only_owner]
+    #[endpoint(deprecateCollateral)]
+    fn deprecate_collateral(&self, collateral_id: TokenIdentifier) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        self.collateral_deprecated(&collateral_id).set(&true);
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint(undeprecateCollateral)]
+    fn undeprecate_collateral(&self, collateral_id: TokenIdentifier) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        self.collateral_deprecated(&collateral_id).clear();
+
+        Ok(())
+    }
+
+    // swaps, hedging and liquidity operations are blocked for the collateral
+    #[only_owner]
+    #[endpoint(pauseCollateral)]
+    fn pause_collateral(&self, collateral_id: TokenIdentifier) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        self.collateral_paused(&collateral_id).set(&true);
+
+        Ok(())
+    }
+
+    #[only_owner]
+    #[endpoint(unpauseCollateral)]
+    fn unpause_collateral(&self, collateral_id: TokenIdentifier) -> SCResult<()> {
+        self.require_collateral_in_whitelist(&collateral_id)?;
+
+        self.collateral_paused(&collateral_id).clear();
+
+        Ok(())
+    }
+
+    // Moves a paused pool to a re-issued token with the same decimals. The owner pays the
+    // contract's whole balance of the old token in the new one, 1:1. Once the new token's
+    // decimals are read from its properties, the owner receives the old tokens back,
+    // or the payment if they don't match.
+    // Hedging positions are remapped afterwards, through migrateHedgingPositions.
+    #[only_owner]
+    #[payable("*")]
+    #[endpoint(migrateCollateral)]
+    fn migrate_collateral(
+        &self,
+        #[payment_token] new_collateral_id: TokenIdentifier,
+        #[payment_amount] payment_amount: BigUint,
+        old_collateral_id: TokenIdentifier,
+    ) -> SCResult<AsyncCall> {
+        self.require_collateral_migration_allowed(
+            &old_collateral_id,
+            &new_collateral_id,
+            &payment_amount,
+        )?;
+
+        let caller = self.blockchain().get_caller();
+        Ok(self
+            .get_token_properties(&new_collateral_id)
+            .async_call()
+            .with_callback(self.callbacks().migrate_collateral_callback(
+                &old_collateral_id,
+                &new_collateral_id,
+                &payment_amount,
+                &caller,
+            )))
+    }
+
+    // the checks are repeated, as the pools may have changed while the properties were read
+    #[callback]
+    fn migrate_collateral_callback(
+        &self,
+        old_collateral_id: &TokenIdentifier,
+        new_collateral_id: &TokenIdentifier,
+        payment_amount: &BigUint,
+        caller: &ManagedAddress,
+        #[call_result] result: ManagedAsyncCallResult<MultiResultVec<ManagedBuffer>>,
+    ) {
+        let opt_num_decimals = match result {
+            ManagedAsyncCallResult::Ok(properties) => self.parse_num_decimals(properties),
+            ManagedAsyncCallResult::Err(_) => None,
+        };
+        let old_num_decimals = self.collateral_num_decimals(old_collateral_id).get();
+        let is_migration_allowed = self
+            .require_collateral_migration_allowed(
+                old_collateral_id,
+                new_collateral_id,
+                payment_amount,
+            )
+            .ok()
+            .is_some();
+        if !is_migration_allowed || opt_num_decimals != Some(old_num_decimals) {
+            self.send()
+                .direct(caller, new_collateral_id, 0, payment_amount, &[]);
+            self.collateral_migration_failed_event(old_collateral_id, new_collateral_id);
+            return;
+        }
+
+        self.move_collateral_settings(old_collateral_id, new_collateral_id);
+
+        let liq_sft_nonce = self.liq_sft_nonce_for_collateral(old_collateral_id).get();
+        if liq_sft_nonce > 0 {
+            self.liq_sft_nonce_for_collateral(new_collateral_id)
+                .set(&liq_sft_nonce);
+            self.collateral_for_liq_sft_nonce(liq_sft_nonce)
+                .set(new_collateral_id);
+            self.liq_sft_nonce_for_collateral(old_collateral_id).clear();
+        }
+
+        self.whitelisted_collaterals().remove(old_collateral_id);
+        self.whitelisted_collaterals()
+            .insert(new_collateral_id.clone());
+        self.collateral_migrated_to(old_collateral_id)
+            .set(new_collateral_id);
+
+        self.send()
+            .direct(caller, old_collateral_id, 0, payment_amount, &[]);
+    }
+
+    fn require_collateral_migration_allowed(
+        &self,
+        old_collateral_id: &TokenIdentifier,
+        new_collateral_id: &TokenIdentifier,
+        payment_amount: &BigUint,
+    ) -> SCResult<()> {
+        self.require_collateral_in_whitelist(old_collateral_id)?;
+        require!(
+            self.collateral_paused(old_collateral_id).get(),
+            "collateral must be paused first"
+        );
+        require!(
+            !self.is_collateral_whitelisted(new_collateral_id)
+                && self.pool_for_collateral(new_collateral_id).is_empty()
+                && self.collateral_migrated_to(new_collateral_id).is_empty(),
+            "New collateral was already used"
+        );
+        require!(
+            self.redemption_queue(old_collateral_id).is_empty(),
+            "Redemption queue not empty"
+        );
+
+        let old_balance = self.blockchain().get_sc_balance(old_collateral_id, 0);
+        require!(
+            payment_amount == &old_balance,
+            "Payment must match the old collateral balance"
+        );
+
+        Ok(())
+    }
+
+    // The collateral has to be deprecated first, and the contract must not owe any of it anymore:
+    // the pool drained of the stable seekers', hedged and reserve collateral, no liquidity tokens
+    // or hedger deposits left, the fees paid out, and no open orders or redemption requests.
     #[only_owner]
     #[endpoint(removeCollateralFromWhitelist)]
-    fn remove_collateral_from_whitelist(&self, collateral_id: TokenIdentifier) {
+    fn remove_collateral_from_whitelist(&self, collateral_id: TokenIdentifier) -> SCResult<()> {
+        require!(
+            self.collateral_deprecated(&collateral_id).get(),
+            "collateral must be deprecated first"
+        );
+
+        let pool = self.get_pool(&collateral_id);
+        require!(
+            pool.collateral_amount == 0
+                && pool.total_collateral_covered == 0
+                && pool.collateral_reserves == 0,
+            "Pool is not drained"
+        );
+        let liq_sft_nonce = self.liq_sft_nonce_for_collateral(&collateral_id).get();
+        require!(
+            liq_sft_nonce == 0 || self.liq_token_amount_in_circulation(liq_sft_nonce).get() == 0,
+            "Liquidity tokens still in circulation"
+        );
+        require!(
+            self.total_hedger_deposits(&collateral_id).get() == 0,
+            "Hedger deposits not withdrawn"
+        );
+        require!(
+            self.accumulated_tx_fees(&collateral_id).get() == 0
+                && self.total_referral_fees(&collateral_id).get() == 0,
+            "Fees not distributed"
+        );
+        require!(
+            self.mint_orders_escrow(&collateral_id).get() == 0
+                && self.redemption_queue(&collateral_id).is_empty(),
+            "Open orders or redemption requests left"
+        );
+
         self.collateral_ticker(&collateral_id).clear();
+        self.collateral_quote_ticker(&collateral_id).clear();
         self.collateral_num_decimals(&collateral_id).clear();
+        self.collateral_precision(&collateral_id).clear();
         self.max_leverage(&collateral_id).clear();
         self.min_max_fees_percentage(&collateral_id).clear();
+        self.min_max_burn_fees_percentage(&collateral_id).clear();
+        self.fee_curve_slope(&collateral_id).clear();
+        self.price_impact_fee_slope(&collateral_id).clear();
+        self.current_fee_configuration(&collateral_id).clear();
         self.hedging_maintenance_ratio(&collateral_id).clear();
         self.liq_provider_fee_reward_percentage(&collateral_id)
             .clear();
+        self.treasury_fee_percentage(&collateral_id).clear();
+        self.treasury_fees_in_stablecoin(&collateral_id).clear();
+        self.fee_recipients(&collateral_id).clear();
+        self.fee_swap_pair_address(&collateral_id).clear();
+        self.fee_swap_max_slippage_percentage(&collateral_id).clear();
         self.min_max_slippage_percentage(&collateral_id).clear();
+        self.min_mint_amount(&collateral_id).clear();
+        self.min_redeem_amount(&collateral_id).clear();
+        self.max_stablecoin_supply(&collateral_id).clear();
+        self.target_reserve_ratio(&collateral_id).clear();
+        self.pool_imbalance_threshold(&collateral_id).clear();
+        self.max_price_age_blocks(&collateral_id).clear();
+        self.twap_window_seconds(&collateral_id).clear();
+        self.max_price_deviation(&collateral_id).clear();
+        self.price_circuit_breaker_tripped(&collateral_id).clear();
+        self.emergency_price(&collateral_id).clear();
+        self.price_band(&collateral_id).clear();
+        self.signed_price(&collateral_id).clear();
+        self.cached_price(&collateral_id).clear();
+        self.clear_price_observations(&collateral_id);
+        self.fallback_price_aggregator_address(&collateral_id)
+            .clear();
+        self.price_sources(&collateral_id).clear();
+        self.cross_rate_intermediate(&collateral_id).clear();
+        self.collateral_ratio_weight(&collateral_id).clear();
+        self.protocol_owned_reserves(&collateral_id).clear();
+        self.stablecoin_minted_for_collateral(&collateral_id)
+            .clear();
+        self.collateral_deprecated(&collateral_id).clear();
+        self.collateral_paused(&collateral_id).clear();
         self.collateral_whitelisted(&collateral_id).clear();
+        self.whitelisted_collaterals().remove(&collateral_id);
+
+        Ok(())
+    }
+
+    // drops the settings addCollateralToWhitelist stored, unless the collateral was
+    // already whitelisted, so a failed whitelisting leaves nothing half configured
+    fn clear_unlisted_collateral_settings(&self, collateral_id: &TokenIdentifier) {
+        if self.is_collateral_whitelisted(collateral_id) {
+            return;
+        }
+
+        self.collateral_ticker(collateral_id).clear();
+        self.max_leverage(collateral_id).clear();
+        self.min_max_fees_percentage(collateral_id).clear();
+        self.hedging_maintenance_ratio(collateral_id).clear();
+        self.liq_provider_fee_reward_percentage(collateral_id).clear();
+        self.min_max_slippage_percentage(collateral_id).clear();
+    }
+
+    // the liquidity providers, the treasury and the fee recipients can't get more than the fees
+    fn require_valid_fee_shares(
+        &self,
+        collateral_id: &TokenIdentifier,
+        treasury_fee_percentage: &BigUint,
+        fee_recipients_percentage: &BigUint,
+    ) -> SCResult<()> {
+        let liq_provider_fee_reward_percentage = self
+            .liq_provider_fee_reward_percentage(collateral_id)
+            .get();
+        let total_fee_percentage =
+            liq_provider_fee_reward_percentage + treasury_fee_percentage + fee_recipients_percentage;
+        require!(
+            total_fee_percentage <= math::PERCENTAGE_PRECISION,
+            "Fee shares exceed the fees"
+        );
+
+        Ok(())
+    }
+
+    // every per-collateral key is handled here, either moved to the new collateral or dropped
+    fn move_collateral_settings(&self, old_id: &TokenIdentifier, new_id: &TokenIdentifier) {
+        self.move_setting(
+            self.collateral_ticker(old_id),
+            self.collateral_ticker(new_id),
+        );
+        self.move_setting(
+            self.collateral_quote_ticker(old_id),
+            self.collateral_quote_ticker(new_id),
+        );
+        self.move_setting(
+            self.collateral_num_decimals(old_id),
+            self.collateral_num_decimals(new_id),
+        );
+        self.collateral_precision(new_id)
+            .set(&self.get_collateral_precision(old_id));
+        self.collateral_precision(old_id).clear();
+        self.move_setting(self.max_leverage(old_id), self.max_leverage(new_id));
+        self.move_setting(
+            self.min_max_fees_percentage(old_id),
+            self.min_max_fees_percentage(new_id),
+        );
+        self.move_setting(
+            self.min_max_burn_fees_percentage(old_id),
+            self.min_max_burn_fees_percentage(new_id),
+        );
+        self.move_setting(self.fee_curve_slope(old_id), self.fee_curve_slope(new_id));
+        self.move_setting(
+            self.price_impact_fee_slope(old_id),
+            self.price_impact_fee_slope(new_id),
+        );
+        self.move_setting(
+            self.current_fee_configuration(old_id),
+            self.current_fee_configuration(new_id),
+        );
+        self.move_setting(
+            self.hedging_maintenance_ratio(old_id),
+            self.hedging_maintenance_ratio(new_id),
+        );
+        self.move_setting(
+            self.liq_provider_fee_reward_percentage(old_id),
+            self.liq_provider_fee_reward_percentage(new_id),
+        );
+        self.move_setting(
+            self.treasury_fee_percentage(old_id),
+            self.treasury_fee_percentage(new_id),
+        );
+        self.move_setting(
+            self.treasury_fees_in_stablecoin(old_id),
+            self.treasury_fees_in_stablecoin(new_id),
+        );
+        self.move_setting(
+            self.fee_swap_pair_address(old_id),
+            self.fee_swap_pair_address(new_id),
+        );
+        self.move_setting(
+            self.fee_swap_max_slippage_percentage(old_id),
+            self.fee_swap_max_slippage_percentage(new_id),
+        );
+        self.move_setting(
+            self.min_max_slippage_percentage(old_id),
+            self.min_max_slippage_percentage(new_id),
+        );
+        self.move_setting(self.min_mint_amount(old_id), self.min_mint_amount(new_id));
+        self.move_setting(
+            self.min_redeem_amount(old_id),
+            self.min_redeem_amount(new_id),
+        );
+        self.move_setting(
+            self.max_stablecoin_supply(old_id),
+            self.max_stablecoin_supply(new_id),
+        );
+        self.move_setting(
+            self.target_reserve_ratio(old_id),
+            self.target_reserve_ratio(new_id),
+        );
+        self.move_setting(
+            self.pool_imbalance_threshold(old_id),
+            self.pool_imbalance_threshold(new_id),
+        );
+        self.move_setting(
+            self.collateral_ratio_weight(old_id),
+            self.collateral_ratio_weight(new_id),
+        );
+        self.move_setting(
+            self.collateral_deprecated(old_id),
+            self.collateral_deprecated(new_id),
+        );
+        self.move_setting(
+            self.pool_for_collateral(old_id),
+            self.pool_for_collateral(new_id),
+        );
+        self.move_setting(
+            self.accumulated_tx_fees(old_id),
+            self.accumulated_tx_fees(new_id),
+        );
+        self.move_setting(
+            self.total_hedger_deposits(old_id),
+            self.total_hedger_deposits(new_id),
+        );
+        self.move_setting(
+            self.stablecoin_minted_for_collateral(old_id),
+            self.stablecoin_minted_for_collateral(new_id),
+        );
+        self.move_setting(
+            self.total_referral_fees(old_id),
+            self.total_referral_fees(new_id),
+        );
+        self.move_setting(
+            self.protocol_owned_reserves(old_id),
+            self.protocol_owned_reserves(new_id),
+        );
+        self.move_setting(
+            self.mint_orders_escrow(old_id),
+            self.mint_orders_escrow(new_id),
+        );
+        self.move_setting(
+            self.max_price_age_blocks(old_id),
+            self.max_price_age_blocks(new_id),
+        );
+        self.move_setting(
+            self.twap_window_seconds(old_id),
+            self.twap_window_seconds(new_id),
+        );
+        self.move_setting(
+            self.max_price_deviation(old_id),
+            self.max_price_deviation(new_id),
+        );
+        self.move_setting(
+            self.price_circuit_breaker_tripped(old_id),
+            self.price_circuit_breaker_tripped(new_id),
+        );
+        self.move_setting(self.price_band(old_id), self.price_band(new_id));
+        self.move_setting(self.emergency_price(old_id), self.emergency_price(new_id));
+        self.move_setting(self.signed_price(old_id), self.signed_price(new_id));
+        self.move_setting(
+            self.fallback_price_aggregator_address(old_id),
+            self.fallback_price_aggregator_address(new_id),
+        );
+        self.move_setting(
+            self.cross_rate_intermediate(old_id),
+            self.cross_rate_intermediate(new_id),
+        );
+        for (recipient, fee_percentage) in self.fee_recipients(old_id).iter() {
+            self.fee_recipients(new_id)
+                .insert(recipient, fee_percentage);
+        }
+        self.fee_recipients(old_id).clear();
+        for source_address in self.price_sources(old_id).iter() {
+            self.price_sources(new_id).insert(source_address);
+        }
+        self.price_sources(old_id).clear();
+
+        // stays paused, so the owner can check the new pool before unpausing
+        self.collateral_paused(new_id).set(&true);
+        self.collateral_paused(old_id).clear();
+        self.collateral_whitelisted(new_id).set(&true);
+        self.collateral_whitelisted(old_id).clear();
+
+        // the new token's price history starts over
+        self.cached_price(old_id).clear();
+        self.clear_price_observations(old_id);
+    }
+    // copies the old key's value, if set, and clears the old key
+    fn move_setting<T: TopEncode + TopDecode>(
+        &self,
+        old_mapper: SingleValueMapper<Self::Api, T>,
+        new_mapper: SingleValueMapper<Self::Api, T>,
+    ) {
+        if !old_mapper.is_empty() {
+            new_mapper.set(&old_mapper.get());
+            old_mapper.clear();
+        }
     }
 }
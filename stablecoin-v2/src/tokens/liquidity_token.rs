@@ -184,4 +184,9 @@ pub trait LiquidityTokenModule:
 
     #[storage_mapper("collateralAmountForLiqToken")]
     fn collateral_amount_for_liq_token(&self, sft_nonce: u64) -> SingleValueMapper<BigUint>;
+
+    // the liquidity providers' swapped fees, paid out pro rata in removeLiquidity
+    #[view(getStablecoinRewardsForLiquidityToken)]
+    #[storage_mapper("stablecoinRewardsForLiqToken")]
+    fn stablecoin_rewards_for_liq_token(&self, sft_nonce: u64) -> SingleValueMapper<BigUint>;
 }
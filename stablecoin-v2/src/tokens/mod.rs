@@ -1,4 +1,6 @@
 pub mod hedging_token;
 pub mod liquidity_token;
+pub mod rewa_wrapper;
+pub mod rewa_wrapper_proxy;
 pub mod stablecoin_token;
 pub mod token_common;
@@ -0,0 +1,85 @@
+numbat_wasm::imports!();
+
+use super::rewa_wrapper_proxy;
+
+#[numbat_wasm::module]
+pub trait RewaWrapperModule {
+    // the wrapped REWA token also has to be whitelisted as collateral
+    #[only_owner]
+    #[endpoint(setRewaWrapper)]
+    fn set_rewa_wrapper(
+        &self,
+        wrapper_address: ManagedAddress,
+        wrapped_rewa_token_id: TokenIdentifier,
+    ) -> SCResult<()> {
+        require!(
+            self.blockchain().is_smart_contract(&wrapper_address),
+            "Wrapper address is not a smart contract"
+        );
+        require!(
+            wrapped_rewa_token_id.is_valid_dcdt_identifier(),
+            "Invalid wrapped REWA token ID"
+        );
+
+        self.rewa_wrapper_address().set(&wrapper_address);
+        self.wrapped_rewa_token_id().set(&wrapped_rewa_token_id);
+
+        Ok(())
+    }
+
+    // REWA is replaced by the wrapped REWA collateral, other tokens are returned as they are
+    fn resolve_rewa_collateral(&self, token_id: TokenIdentifier) -> SCResult<TokenIdentifier> {
+        if !token_id.is_rewa() {
+            return Ok(token_id);
+        }
+
+        require!(
+            !self.rewa_wrapper_address().is_empty(),
+            "REWA wrapper not set"
+        );
+        Ok(self.wrapped_rewa_token_id().get())
+    }
+
+    // the wrapped tokens are received by this contract
+    fn wrap_rewa(&self, amount: &BigUint) {
+        self.rewa_wrapper_proxy(self.rewa_wrapper_address().get())
+            .wrap_rewa(amount.clone())
+            .execute_on_dest_context();
+    }
+
+    fn send_collateral(
+        &self,
+        to: &ManagedAddress,
+        collateral_id: &TokenIdentifier,
+        amount: &BigUint,
+        unwrap_rewa: bool,
+    ) {
+        if !unwrap_rewa {
+            self.send().direct(to, collateral_id, 0, amount, &[]);
+            return;
+        }
+
+        self.rewa_wrapper_proxy(self.rewa_wrapper_address().get())
+            .unwrap_rewa(collateral_id.clone(), amount.clone())
+            .execute_on_dest_context();
+        self.send().direct_rewa(to, amount, &[]);
+    }
+
+    // proxies
+
+    #[proxy]
+    fn rewa_wrapper_proxy(
+        &self,
+        sc_address: ManagedAddress,
+    ) -> rewa_wrapper_proxy::Proxy<Self::Api>;
+
+    // storage
+
+    #[view(getRewaWrapperAddress)]
+    #[storage_mapper("rewaWrapperAddress")]
+    fn rewa_wrapper_address(&self) -> SingleValueMapper<ManagedAddress>;
+
+    #[view(getWrappedRewaTokenId)]
+    #[storage_mapper("wrappedRewaTokenId")]
+    fn wrapped_rewa_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
+}
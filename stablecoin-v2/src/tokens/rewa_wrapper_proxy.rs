@@ -0,0 +1,17 @@
+numbat_wasm::imports!();
+
+// the wrapped tokens are sent back to the caller, 1:1 with the REWA amount
+#[numbat_wasm::proxy]
+pub trait RewaWrapper {
+    #[payable("REWA")]
+    #[endpoint(wrapRewa)]
+    fn wrap_rewa(&self, #[payment_amount] payment_amount: BigUint);
+
+    #[payable("*")]
+    #[endpoint(unwrapRewa)]
+    fn unwrap_rewa(
+        &self,
+        #[payment_token] payment_token: TokenIdentifier,
+        #[payment_amount] payment_amount: BigUint,
+    );
+}
@@ -2,8 +2,8 @@ numbat_wasm::imports!();
 
 const STABLE_COIN_NAME: &[u8] = b"StableCoin";
 const STABLE_COIN_TICKER: &[u8] = b"STCOIN";
-const STABLE_COIN_NUM_DECIMALS: usize = 6;
-// pub const STABLE_COIN_PRECISION: u64 = 1_000_000;
+pub const STABLE_COIN_NUM_DECIMALS: usize = 6;
+pub const STABLE_COIN_PRECISION: u64 = 1_000_000;
 
 #[numbat_wasm::module]
 pub trait StablecoinTokenModule: crate::token_common::TokenCommonModule {
@@ -53,12 +53,29 @@ pub trait StablecoinTokenModule: crate::token_common::TokenCommonModule {
         self.set_local_roles(&token_id, &roles)
     }
 
-    fn mint_stablecoin(&self, amount: &BigUint) {
+    // zero means no cap
+    #[only_owner]
+    #[endpoint(setMaxTotalStablecoinSupply)]
+    fn set_max_total_stablecoin_supply(&self, max_total_supply: BigUint) {
+        self.max_total_stablecoin_supply().set(&max_total_supply);
+    }
+
+    // every stablecoin mint goes through here, so the global cap is enforced in one place
+    fn mint_stablecoin(&self, amount: &BigUint) -> SCResult<()> {
+        let new_total_supply = self.stablecoin_total_circulating_supply().get() + amount;
+        let max_total_supply = self.max_total_stablecoin_supply().get();
+        require!(
+            max_total_supply == 0 || new_total_supply <= max_total_supply,
+            "Over the max total stablecoin supply"
+        );
+
         self.send()
             .dcdt_local_mint(&self.stablecoin_token_id().get(), 0, amount);
 
         self.stablecoin_total_circulating_supply()
-            .update(|total| *total += amount);
+            .set(&new_total_supply);
+
+        Ok(())
     }
 
     fn burn_stablecoin(&self, amount: &BigUint) {
@@ -74,9 +91,11 @@ pub trait StablecoinTokenModule: crate::token_common::TokenCommonModule {
             .direct(to, &self.stablecoin_token_id().get(), 0, amount, &[]);
     }
 
-    fn mint_and_send_stablecoin(&self, to: &ManagedAddress, amount: &BigUint) {
-        self.mint_stablecoin(amount);
+    fn mint_and_send_stablecoin(&self, to: &ManagedAddress, amount: &BigUint) -> SCResult<()> {
+        self.mint_stablecoin(amount)?;
         self.send_stablecoin(to, amount);
+
+        Ok(())
     }
 
     #[callback]
@@ -107,4 +126,8 @@ pub trait StablecoinTokenModule: crate::token_common::TokenCommonModule {
     #[view(getStablecoinTotalCirculatingSupply)]
     #[storage_mapper("stablecoinTotalCirculatingSupply")]
     fn stablecoin_total_circulating_supply(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getMaxTotalStablecoinSupply)]
+    #[storage_mapper("maxTotalStablecoinSupply")]
+    fn max_total_stablecoin_supply(&self) -> SingleValueMapper<BigUint>;
 }
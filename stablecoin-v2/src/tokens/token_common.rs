@@ -1,5 +1,12 @@
 numbat_wasm::imports!();
 
+const GET_TOKEN_PROPERTIES_ENDPOINT_NAME: &[u8] = b"getTokenProperties";
+const NUM_DECIMALS_PREFIX: &[u8] = b"NumDecimals-";
+const DCDT_SYSTEM_SC_ADDRESS_ARRAY: [u8; 32] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0xff,
+    0xff,
+];
+
 #[numbat_wasm::module]
 pub trait TokenCommonModule {
     fn set_local_roles(&self, token_id: &TokenIdentifier, roles: &[DcdtLocalRole]) -> AsyncCall {
@@ -26,6 +33,47 @@ pub trait TokenCommonModule {
         )
     }
 
+    fn get_token_properties(
+        &self,
+        token_id: &TokenIdentifier,
+    ) -> ContractCall<Self::Api, MultiResultVec<ManagedBuffer>> {
+        let dcdt_system_sc_address =
+            ManagedAddress::new_from_bytes(self.raw_vm_api(), &DCDT_SYSTEM_SC_ADDRESS_ARRAY);
+        let mut contract_call = ContractCall::new(
+            self.raw_vm_api(),
+            dcdt_system_sc_address,
+            ManagedBuffer::from(GET_TOKEN_PROPERTIES_ENDPOINT_NAME),
+        );
+        contract_call.push_endpoint_arg(token_id);
+
+        contract_call
+    }
+
+    // the properties contain an entry like "NumDecimals-18"
+    fn parse_num_decimals(&self, properties: MultiResultVec<ManagedBuffer>) -> Option<u32> {
+        for property in properties.into_vec() {
+            let property_bytes = property.to_boxed_bytes();
+            let property_bytes = property_bytes.as_slice();
+            if !property_bytes.starts_with(NUM_DECIMALS_PREFIX) {
+                continue;
+            }
+
+            let mut num_decimals = 0u32;
+            for digit in &property_bytes[NUM_DECIMALS_PREFIX.len()..] {
+                if !digit.is_ascii_digit() {
+                    return None;
+                }
+                num_decimals = num_decimals
+                    .checked_mul(10)?
+                    .checked_add((digit - b'0') as u32)?;
+            }
+
+            return Some(num_decimals);
+        }
+
+        None
+    }
+
     fn refund_owner_failed_issue(&self) {
         let owner = self.blockchain().get_owner_address();
         let rewa_returned = self.call_value().rewa_value();
@@ -4,3 +4,18 @@ fn unwrap_rewa_go() {
     numbat_wasm_debug::denali_go("denali/.scen.json");
 }
 */
+
+#[test]
+fn deploy_go() {
+    numbat_wasm_debug::denali_go("denali/deploy.scen.json");
+}
+
+#[test]
+fn oracle_prices_go() {
+    numbat_wasm_debug::denali_go("denali/oracle-prices.scen.json");
+}
+
+#[test]
+fn fee_swap_go() {
+    numbat_wasm_debug::denali_go("denali/fee-swap.scen.json");
+}